@@ -0,0 +1,83 @@
+//! Detect the version of Tokio the crate is built against, with the
+//! `tokio-version` feature.
+//!
+//! Cargo doesn't tell build scripts the versions of the other packages in the
+//! graph, so this looks for the `Cargo.lock` of the workspace being built, and
+//! exposes the locked Tokio version as the `TOKIO_VERSION` environment
+//! variable. This is best-effort: the lockfile found may not be the one of
+//! the workspace, e.g. with a target directory shared between workspaces, and
+//! the version is left unset whenever it can't be found.
+
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    if env::var_os("CARGO_FEATURE_TOKIO_VERSION").is_none() {
+        return;
+    }
+
+    let Some(lockfile) = find_lockfile() else {
+        return;
+    };
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+
+    let Ok(contents) = fs::read_to_string(&lockfile) else {
+        return;
+    };
+
+    if let Some(version) = tokio_version(&contents) {
+        println!("cargo:rustc-env=TOKIO_VERSION={version}");
+    }
+}
+
+/// Find the `Cargo.lock` of the workspace being built.
+///
+/// The target directory usually lives in the workspace root, so look in the
+/// parents of the output directory first, then in the parents of this crate,
+/// for when it is built as part of its own workspace.
+fn find_lockfile() -> Option<PathBuf> {
+    ["OUT_DIR", "CARGO_MANIFEST_DIR"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .find_map(|dir| {
+            Path::new(&dir)
+                .ancestors()
+                .map(|dir| dir.join("Cargo.lock"))
+                .find(|path| path.is_file())
+        })
+}
+
+/// Find the version of the `tokio` 1.x package in a lockfile.
+///
+/// Other major versions may be locked too, for other dependencies, but this
+/// crate can only be built against Tokio 1.x, which Cargo unifies to a
+/// single version.
+fn tokio_version(lockfile: &str) -> Option<&str> {
+    let mut lines = lockfile.lines();
+    while let Some(line) = lines.next() {
+        if line != "name = \"tokio\"" {
+            continue;
+        }
+
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix("version = \""))
+            .and_then(|line| line.strip_suffix('"'));
+        if let Some(version) = version.filter(|version| is_tokio_1(version)) {
+            return Some(version);
+        }
+    }
+
+    None
+}
+
+/// Whether a version looks like a Tokio 1.x version.
+fn is_tokio_1(version: &str) -> bool {
+    version.strip_prefix("1.").is_some_and(|rest| {
+        rest.split(['.', '-', '+'])
+            .take(2)
+            .all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()))
+    })
+}
@@ -0,0 +1,160 @@
+//! Procedural macros for `opentelemetry-instrumentation-tokio`.
+//!
+//! These are re-exported by the main crate with the `macros` feature, and
+//! shouldn't be used directly.
+
+#![deny(clippy::all, clippy::pedantic)]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, ItemFn, Lit, LitInt, LitStr, ReturnType, Token};
+
+/// An argument of the `main` attribute.
+enum Arg {
+    /// `"key" = value`, a label of the runtime
+    Label(LitStr, Lit),
+
+    /// `flavor = "multi_thread"` or `flavor = "current_thread"`
+    Flavor(LitStr),
+
+    /// `worker_threads = 4`
+    WorkerThreads(LitInt),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            let key = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let value = input.parse()?;
+            return Ok(Self::Label(key, value));
+        }
+
+        let name: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        match name.to_string().as_str() {
+            "flavor" => Ok(Self::Flavor(input.parse()?)),
+            "worker_threads" => Ok(Self::WorkerThreads(input.parse()?)),
+            _ => Err(syn::Error::new(
+                name.span(),
+                "unknown argument, expected `flavor`, `worker_threads`, or a `\"label\" = value` pair",
+            )),
+        }
+    }
+}
+
+/// Mark an async function to be run as the `main` function, on a Tokio
+/// runtime observed by `opentelemetry-instrumentation-tokio`.
+///
+/// This is a drop-in replacement for `#[tokio::main]`: it builds a runtime
+/// with all its drivers enabled, observes it with a `Config` labelled with
+/// the `"key" = value` arguments, and runs the body of the function on it.
+///
+/// The runtime is observed as soon as the global meter provider is set, so
+/// the body of the function can set it up first, as the runtime may be
+/// needed to export the metrics. This relies on `Config::with_provider_check`,
+/// so a zero-sized custom meter provider is never considered as set.
+///
+/// The runtime is configured with the same arguments as `#[tokio::main]`:
+///
+/// - `flavor = "multi_thread"` (the default) or `flavor = "current_thread"`
+/// - `worker_threads = N`, for the `multi_thread` flavor
+///
+/// # Panics
+///
+/// The generated function panics if the runtime can't be built, or if it
+/// can't be observed for another reason than the global meter provider not
+/// being set.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[opentelemetry_instrumentation_tokio::main("service.name" = "api", worker_threads = 4)]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     // Set the global meter provider up
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(args, item) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
+
+fn expand(args: TokenStream, item: TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let args = syn::parse::Parser::parse(Punctuated::<Arg, Token![,]>::parse_terminated, args)?;
+    let mut function: ItemFn = syn::parse(item)?;
+
+    if function.sig.asyncness.take().is_none() {
+        return Err(syn::Error::new_spanned(
+            function.sig.fn_token,
+            "the `async` keyword is missing from the function declaration",
+        ));
+    }
+    if !function.sig.inputs.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &function.sig.inputs,
+            "the main function cannot accept arguments",
+        ));
+    }
+
+    let mut multi_thread = true;
+    let mut worker_threads = None;
+    let mut labels = Vec::new();
+    for arg in args {
+        match arg {
+            Arg::Label(key, value) => labels.push(quote!(.with_label(#key, #value))),
+            Arg::Flavor(flavor) => {
+                multi_thread = match flavor.value().as_str() {
+                    "multi_thread" => true,
+                    "current_thread" => false,
+                    _ => {
+                        return Err(syn::Error::new(
+                            flavor.span(),
+                            "unknown flavor, expected `multi_thread` or `current_thread`",
+                        ));
+                    }
+                };
+            }
+            Arg::WorkerThreads(threads) => worker_threads = Some(threads),
+        }
+    }
+
+    let krate = quote!(::opentelemetry_instrumentation_tokio);
+    let builder = if multi_thread {
+        let worker_threads = worker_threads.map(|threads| quote!(.worker_threads(#threads)));
+        quote!(#krate::__private::tokio::runtime::Builder::new_multi_thread() #worker_threads)
+    } else if let Some(threads) = worker_threads {
+        return Err(syn::Error::new(
+            threads.span(),
+            "`worker_threads` can only be set with the `multi_thread` flavor",
+        ));
+    } else {
+        quote!(#krate::__private::tokio::runtime::Builder::new_current_thread())
+    };
+
+    let output = match &function.sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+    let body = &function.block;
+    let body_ident = Ident::new("body", Span::mixed_site());
+    *function.block = syn::parse_quote!({
+        let #body_ident = async #body;
+        // Help the inference of the type of errors returned with `?`
+        let #body_ident: ::core::pin::Pin<&mut dyn ::core::future::Future<Output = #output>> =
+            ::core::pin::pin!(#body_ident);
+        #krate::__private::block_on(
+            #builder.enable_all(),
+            #krate::Config::new() #(#labels)*,
+            #body_ident,
+        )
+    });
+
+    Ok(quote!(#function))
+}
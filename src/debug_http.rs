@@ -0,0 +1,67 @@
+//! An HTTP endpoint serving the snapshots of the tracked runtimes as JSON.
+//!
+//! This lets the health of the runtimes be inspected on a box without a
+//! metrics backend, e.g. with `curl`. The [`response`] is built with the
+//! [`http`] types, which both `hyper` and `axum` use.
+//!
+//! # Examples
+//!
+//! With `axum`:
+//!
+//! ```ignore
+//! use axum::{Router, routing::get};
+//! use opentelemetry_instrumentation_tokio::debug_http;
+//!
+//! let app = Router::new().route("/debug/tokio", get(|| async { debug_http::response() }));
+//! ```
+//!
+//! With `hyper`, mapping the body to its own body type:
+//!
+//! ```ignore
+//! use http_body_util::Full;
+//! use hyper::service::service_fn;
+//! use opentelemetry_instrumentation_tokio::debug_http;
+//!
+//! let service = service_fn(|_request| async {
+//!     Ok::<_, std::convert::Infallible>(debug_http::response().map(Full::new))
+//! });
+//! ```
+
+use http::{Response, StatusCode, header};
+
+/// Serialize the snapshots of all the tracked runtimes as JSON.
+///
+/// See [`snapshots`](crate::snapshots).
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// opentelemetry_instrumentation_tokio::observe_current_runtime()?;
+///
+/// println!("{}", opentelemetry_instrumentation_tokio::debug_http::json());
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub fn json() -> String {
+    // The snapshots only hold strings, numbers and maps with string keys
+    serde_json::to_string(&crate::snapshots()).unwrap_or_else(|_| "[]".to_owned())
+}
+
+/// Build the response serving the snapshots of all the tracked runtimes as
+/// JSON.
+///
+/// # Panics
+///
+/// Never panics, the response headers being statically known.
+#[must_use]
+pub fn response() -> Response<String> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(json())
+        .expect("the response should be valid")
+}
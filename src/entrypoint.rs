@@ -0,0 +1,67 @@
+//! Support code for the [`main`](crate::main) attribute macro.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::runtime::{Builder, Handle};
+
+use crate::{Config, ObserveError};
+
+/// Build the runtime, and run the body of the `main` function on it, while
+/// observing the runtime with the given configuration.
+///
+/// # Panics
+///
+/// Panics if the runtime can't be built, or if it can't be observed for
+/// another reason than the global meter provider not being set.
+pub fn block_on<F: Future>(builder: &mut Builder, config: Config, future: F) -> F::Output {
+    let runtime = builder.build().expect("failed building the runtime");
+    runtime.block_on(ObserveWhenReady {
+        handle: runtime.handle().clone(),
+        // The provider is usually set by the body of the function, so the
+        // runtime is only observed once it is
+        config: Some(config.with_provider_check(true)),
+        future,
+    })
+}
+
+pin_project_lite::pin_project! {
+    /// Runs a future, and observes the runtime once the global meter provider
+    /// is set, which usually happens in the future itself.
+    struct ObserveWhenReady<F> {
+        handle: Handle,
+        config: Option<Config>,
+        #[pin]
+        future: F,
+    }
+}
+
+impl<F: Future> Future for ObserveWhenReady<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        observe(this.handle, this.config);
+        let output = this.future.poll(cx);
+        // The provider may have been set in this poll, and the next one may
+        // only come much later
+        if output.is_pending() {
+            observe(this.handle, this.config);
+        }
+        output
+    }
+}
+
+/// Observe the runtime if it isn't yet and the meter provider is set.
+fn observe(handle: &Handle, config: &mut Option<Config>) {
+    let Some(pending) = config else {
+        return;
+    };
+
+    match pending.clone().observe_runtime(handle) {
+        // The guard keeps the runtime observed when dropped
+        Ok(_guard) => *config = None,
+        Err(ObserveError::ProviderNotInitialized) => {}
+        Err(error) => panic!("failed to observe the runtime: {error}"),
+    }
+}
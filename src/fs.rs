@@ -0,0 +1,373 @@
+//! Instrumented filesystem operations.
+//!
+//! Filesystem operations in Tokio run on the blocking thread pool. The
+//! functions in this module mirror the ones in [`tokio::fs`], and record how
+//! many operations ran, how long they took and how many bytes they moved, so
+//! that blocking pool pressure can be traced back to filesystem usage:
+//!
+//! - `tokio.fs.operations` - Operations completed, with a `tokio.fs.operation`
+//!   attribute, and an `error.type` attribute for failed operations
+//! - `tokio.fs.operation.duration` - Duration of the operations, with the same
+//!   attributes
+//! - `tokio.fs.bytes_read` - Bytes read
+//! - `tokio.fs.bytes_written` - Bytes written
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::fs;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! let contents = fs::read_to_string("config.toml").await?;
+//! fs::write("config.toml.bak", contents).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue};
+use tokio::io::{AsyncRead, AsyncSeek, AsyncWrite, ReadBuf};
+
+/// Instruments shared by all filesystem operations.
+struct FsInstruments {
+    operations: Counter<u64>,
+    operation_duration: Histogram<f64>,
+    bytes_read: Counter<u64>,
+    bytes_written: Counter<u64>,
+}
+
+static FS_INSTRUMENTS: LazyLock<FsInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    FsInstruments {
+        operations: meter
+            .u64_counter("tokio.fs.operations")
+            .with_description("The number of filesystem operations completed")
+            .with_unit("{operation}")
+            .build(),
+        operation_duration: meter
+            .f64_histogram("tokio.fs.operation.duration")
+            .with_description("The duration of filesystem operations")
+            .with_unit("s")
+            .build(),
+        bytes_read: meter
+            .u64_counter("tokio.fs.bytes_read")
+            .with_description("The number of bytes read from the filesystem")
+            .with_unit("By")
+            .build(),
+        bytes_written: meter
+            .u64_counter("tokio.fs.bytes_written")
+            .with_description("The number of bytes written to the filesystem")
+            .with_unit("By")
+            .build(),
+    }
+});
+
+/// Run a filesystem operation, recording its outcome and duration.
+async fn instrumented<T>(
+    operation: &'static str,
+    future: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    let start = Instant::now();
+    let result = future.await;
+    let elapsed = start.elapsed();
+
+    let mut labels = vec![KeyValue::new(
+        Key::from_static_str("tokio.fs.operation"),
+        operation,
+    )];
+    if let Err(error) = &result {
+        labels.push(crate::io_error_type(error));
+    }
+
+    let instruments = &*FS_INSTRUMENTS;
+    instruments.operations.add(1, &labels);
+    instruments
+        .operation_duration
+        .record(elapsed.as_secs_f64(), &labels);
+
+    result
+}
+
+/// Convert a byte count for recording.
+fn bytes(len: usize, metric: &'static str) -> u64 {
+    len.try_into()
+        .unwrap_or_else(|_| crate::overflowed(metric, u64::MAX))
+}
+
+/// Read the entire contents of a file into a bytes vector.
+///
+/// See [`tokio::fs::read`].
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read.
+pub async fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let contents = instrumented("read", tokio::fs::read(path)).await?;
+    FS_INSTRUMENTS
+        .bytes_read
+        .add(bytes(contents.len(), "tokio.fs.bytes_read"), &[]);
+    Ok(contents)
+}
+
+/// Read the entire contents of a file into a string.
+///
+/// See [`tokio::fs::read_to_string`].
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read, or isn't valid UTF-8.
+pub async fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let contents = instrumented("read", tokio::fs::read_to_string(path)).await?;
+    FS_INSTRUMENTS
+        .bytes_read
+        .add(bytes(contents.len(), "tokio.fs.bytes_read"), &[]);
+    Ok(contents)
+}
+
+/// Write a slice as the entire contents of a file.
+///
+/// See [`tokio::fs::write`].
+///
+/// # Errors
+///
+/// Returns an error if the file can't be written.
+pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let contents = contents.as_ref();
+    instrumented("write", tokio::fs::write(path, contents)).await?;
+    FS_INSTRUMENTS
+        .bytes_written
+        .add(bytes(contents.len(), "tokio.fs.bytes_written"), &[]);
+    Ok(())
+}
+
+/// Copy the contents of one file to another.
+///
+/// See [`tokio::fs::copy`].
+///
+/// # Errors
+///
+/// Returns an error if the copy fails.
+pub async fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+    let copied = instrumented("copy", tokio::fs::copy(from, to)).await?;
+    FS_INSTRUMENTS.bytes_read.add(copied, &[]);
+    FS_INSTRUMENTS.bytes_written.add(copied, &[]);
+    Ok(copied)
+}
+
+/// Rename a file or directory.
+///
+/// See [`tokio::fs::rename`].
+///
+/// # Errors
+///
+/// Returns an error if the rename fails.
+pub async fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+    instrumented("rename", tokio::fs::rename(from, to)).await
+}
+
+/// Remove a file.
+///
+/// See [`tokio::fs::remove_file`].
+///
+/// # Errors
+///
+/// Returns an error if the file can't be removed.
+pub async fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
+    instrumented("remove_file", tokio::fs::remove_file(path)).await
+}
+
+/// Recursively create a directory and all of its parents.
+///
+/// See [`tokio::fs::create_dir_all`].
+///
+/// # Errors
+///
+/// Returns an error if the directory can't be created.
+pub async fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()> {
+    instrumented("create_dir_all", tokio::fs::create_dir_all(path)).await
+}
+
+/// Query the metadata of a file or directory.
+///
+/// See [`tokio::fs::metadata`].
+///
+/// # Errors
+///
+/// Returns an error if the metadata can't be queried.
+pub async fn metadata(path: impl AsRef<Path>) -> io::Result<std::fs::Metadata> {
+    instrumented("metadata", tokio::fs::metadata(path)).await
+}
+
+/// A [`tokio::fs::File`] recording its operations.
+///
+/// Opening and syncing the file are recorded as operations, and the bytes
+/// read and written through its [`AsyncRead`] and [`AsyncWrite`]
+/// implementations are recorded in the byte counters.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::fs::InstrumentedFile;
+/// use tokio::io::AsyncWriteExt;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let mut file = InstrumentedFile::create("output.log").await?;
+/// file.write_all(b"hello").await?;
+/// file.sync_all().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedFile {
+    inner: tokio::fs::File,
+}
+
+impl InstrumentedFile {
+    /// Open a file in read-only mode.
+    ///
+    /// See [`tokio::fs::File::open`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let inner = instrumented("open", tokio::fs::File::open(path)).await?;
+        Ok(Self { inner })
+    }
+
+    /// Open a file in write-only mode, creating or truncating it.
+    ///
+    /// See [`tokio::fs::File::create`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be created.
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let inner = instrumented("create", tokio::fs::File::create(path)).await?;
+        Ok(Self { inner })
+    }
+
+    /// Instrument an already opened file.
+    #[must_use]
+    pub fn new(file: tokio::fs::File) -> Self {
+        Self { inner: file }
+    }
+
+    /// Flush all data and metadata to disk.
+    ///
+    /// See [`tokio::fs::File::sync_all`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if syncing fails.
+    pub async fn sync_all(&self) -> io::Result<()> {
+        instrumented("sync_all", self.inner.sync_all()).await
+    }
+
+    /// Flush all data to disk, without necessarily syncing metadata.
+    ///
+    /// See [`tokio::fs::File::sync_data`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if syncing fails.
+    pub async fn sync_data(&self) -> io::Result<()> {
+        instrumented("sync_data", self.inner.sync_data()).await
+    }
+
+    /// Truncate or extend the file.
+    ///
+    /// See [`tokio::fs::File::set_len`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if resizing the file fails.
+    pub async fn set_len(&self, size: u64) -> io::Result<()> {
+        instrumented("set_len", self.inner.set_len(size)).await
+    }
+
+    /// Query the metadata of the file.
+    ///
+    /// See [`tokio::fs::File::metadata`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata can't be queried.
+    pub async fn metadata(&self) -> io::Result<std::fs::Metadata> {
+        instrumented("metadata", self.inner.metadata()).await
+    }
+
+    /// Get a reference to the underlying file.
+    #[must_use]
+    pub fn get_ref(&self) -> &tokio::fs::File {
+        &self.inner
+    }
+
+    /// Consume this wrapper, returning the underlying file.
+    #[must_use]
+    pub fn into_inner(self) -> tokio::fs::File {
+        self.inner
+    }
+}
+
+impl AsyncRead for InstrumentedFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            let read = buf.filled().len() - before;
+            FS_INSTRUMENTS
+                .bytes_read
+                .add(bytes(read, "tokio.fs.bytes_read"), &[]);
+        }
+        result
+    }
+}
+
+impl AsyncWrite for InstrumentedFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            FS_INSTRUMENTS
+                .bytes_written
+                .add(bytes(written, "tokio.fs.bytes_written"), &[]);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+impl AsyncSeek for InstrumentedFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        Pin::new(&mut self.inner).start_seek(position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_complete(cx)
+    }
+}
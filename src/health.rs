@@ -0,0 +1,277 @@
+//! Health checks of a runtime against saturation thresholds.
+
+use std::fmt;
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+use std::sync::Mutex;
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+use std::time::{Duration, Instant};
+
+use tokio::runtime::{Handle, RuntimeMetrics};
+
+/// The thresholds a runtime is checked against by [`health`].
+///
+/// The busy ratio is measured between two checks, so the same thresholds
+/// should be reused for every check of a runtime, e.g. by keeping them in the
+/// state of the readiness probe handler.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::Thresholds;
+///
+/// let thresholds = Thresholds::new()
+///     .with_max_queue_depth(1000)
+///     .with_max_busy_ratio(0.9);
+/// ```
+#[derive(Debug, Default)]
+pub struct Thresholds {
+    max_queue_depth: Option<usize>,
+    max_busy_ratio: Option<f64>,
+
+    /// When the busy durations were last read, and their values
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    last_busy_durations: Mutex<Option<(Instant, Vec<Duration>)>>,
+}
+
+impl Thresholds {
+    /// Create thresholds which never consider the runtime saturated.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consider the runtime saturated when more than `max` tasks are waiting
+    /// to be polled.
+    ///
+    /// This counts the tasks in the global queue, and with `tokio_unstable`,
+    /// the tasks in the local queues of the workers.
+    #[must_use]
+    pub fn with_max_queue_depth(mut self, max: usize) -> Self {
+        self.max_queue_depth = Some(max);
+        self
+    }
+
+    /// Consider the runtime saturated when its workers were busy for more than
+    /// the given fraction of the time since the previous check, on average.
+    ///
+    /// This is not evaluated on the first check, nor on targets without
+    /// 64-bit atomics or `wasm32-unknown-unknown`, where the busy duration of
+    /// the workers is not available.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is not between 0 and 1.
+    #[must_use]
+    pub fn with_max_busy_ratio(mut self, max: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&max),
+            "the busy ratio threshold must be between 0 and 1"
+        );
+        self.max_busy_ratio = Some(max);
+        self
+    }
+
+    /// The average busy ratio of the workers since the previous check, if
+    /// any.
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    fn busy_ratio(&self, metrics: &RuntimeMetrics) -> Option<f64> {
+        let now = Instant::now();
+        let busy_durations: Vec<Duration> = (0..metrics.num_workers())
+            .map(|worker_idx| metrics.worker_total_busy_duration(worker_idx))
+            .collect();
+
+        let (last_check, last_busy_durations) = crate::recover_lock(
+            self.last_busy_durations.lock(),
+            "health check busy durations",
+        )
+        .replace((now, busy_durations.clone()))?;
+
+        let elapsed = now.saturating_duration_since(last_check);
+        if elapsed.is_zero() || busy_durations.is_empty() {
+            return None;
+        }
+
+        let busy: Duration = busy_durations
+            .iter()
+            .zip(&last_busy_durations)
+            .map(|(busy, last_busy)| busy.saturating_sub(*last_busy))
+            .sum();
+
+        #[expect(clippy::cast_precision_loss)]
+        let ratio = busy.as_secs_f64() / elapsed.as_secs_f64() / busy_durations.len() as f64;
+        // The busy duration is only updated when the worker parks, so it can
+        // be slightly ahead of the wall-clock time
+        Some(ratio.min(1.0))
+    }
+
+    #[cfg(not(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    )))]
+    #[expect(clippy::unused_self)]
+    fn busy_ratio(&self, _metrics: &RuntimeMetrics) -> Option<f64> {
+        None
+    }
+}
+
+/// The result of a [`health`] check.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthStatus {
+    /// The runtime is within all the thresholds.
+    Healthy,
+
+    /// The runtime crossed at least one of the thresholds.
+    Saturated(Vec<Saturation>),
+}
+
+impl HealthStatus {
+    /// Whether the runtime is within all the thresholds.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+}
+
+/// A threshold crossed by a runtime, see [`HealthStatus::Saturated`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Saturation {
+    /// Too many tasks are waiting to be polled.
+    QueueDepth {
+        /// The number of tasks waiting to be polled.
+        depth: usize,
+
+        /// The threshold, see [`Thresholds::with_max_queue_depth`].
+        max: usize,
+    },
+
+    /// The workers are busy for too much of the time.
+    BusyRatio {
+        /// The average busy ratio of the workers since the previous check.
+        ratio: f64,
+
+        /// The threshold, see [`Thresholds::with_max_busy_ratio`].
+        max: f64,
+    },
+}
+
+impl fmt::Display for Saturation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QueueDepth { depth, max } => {
+                write!(f, "{depth} tasks are waiting to be polled (max {max})")
+            }
+            Self::BusyRatio { ratio, max } => {
+                write!(
+                    f,
+                    "the workers were busy {ratio:.2} of the time (max {max:.2})"
+                )
+            }
+        }
+    }
+}
+
+/// Check the given runtime against the thresholds.
+///
+/// This reads the runtime metrics directly, so the runtime doesn't need to be
+/// observed. It is cheap enough to be called from a readiness probe, which
+/// can then refuse traffic while the runtime is saturated.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::{HealthStatus, Thresholds};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let thresholds = Thresholds::new().with_max_queue_depth(1000);
+///
+/// let handle = tokio::runtime::Handle::current();
+/// if let HealthStatus::Saturated(saturations) =
+///     opentelemetry_instrumentation_tokio::health(&handle, &thresholds)
+/// {
+///     for saturation in saturations {
+///         eprintln!("runtime saturated: {saturation}");
+///     }
+/// }
+/// # }
+/// ```
+#[must_use]
+pub fn health(handle: &Handle, thresholds: &Thresholds) -> HealthStatus {
+    check(&handle.metrics(), thresholds)
+}
+
+/// Check the observed runtime with the given name against the thresholds.
+///
+/// This is the same as [`health`], for a runtime named with
+/// [`crate::Config::named`], e.g. from a readiness probe served by another
+/// runtime. Returns `None` if no observed runtime has this name.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::{Config, Thresholds};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// Config::new().named("api-server").observe_current_runtime()?;
+///
+/// let thresholds = Thresholds::new().with_max_queue_depth(1000);
+/// let healthy = opentelemetry_instrumentation_tokio::health_by_name("api-server", &thresholds)
+///     .is_some_and(|status| status.is_healthy());
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub fn health_by_name(name: &str, thresholds: &Thresholds) -> Option<HealthStatus> {
+    crate::runtime::with_named_runtime(name, |metrics| check(metrics, thresholds))
+}
+
+/// Check the metrics of a runtime against the thresholds.
+fn check(metrics: &RuntimeMetrics, thresholds: &Thresholds) -> HealthStatus {
+    let mut saturations = Vec::new();
+
+    if let Some(max) = thresholds.max_queue_depth {
+        let depth = queue_depth(metrics);
+        if depth > max {
+            saturations.push(Saturation::QueueDepth { depth, max });
+        }
+    }
+
+    if let Some(max) = thresholds.max_busy_ratio
+        && let Some(ratio) = thresholds.busy_ratio(metrics)
+        && ratio > max
+    {
+        saturations.push(Saturation::BusyRatio { ratio, max });
+    }
+
+    if saturations.is_empty() {
+        HealthStatus::Healthy
+    } else {
+        HealthStatus::Saturated(saturations)
+    }
+}
+
+/// The number of tasks waiting to be polled.
+fn queue_depth(metrics: &RuntimeMetrics) -> usize {
+    #[cfg(tokio_unstable)]
+    let local_queue_depth: usize = (0..metrics.num_workers())
+        .map(|worker_idx| metrics.worker_local_queue_depth(worker_idx))
+        .sum();
+    #[cfg(not(tokio_unstable))]
+    let local_queue_depth = 0;
+
+    metrics.global_queue_depth() + local_queue_depth
+}
@@ -0,0 +1,432 @@
+//! Instrumentation for [`AsyncRead`] and [`AsyncWrite`] types.
+//!
+//! [`InstrumentedIo`] wraps any I/O type, e.g. a TLS stream, a pipe or a
+//! custom transport, and records:
+//!
+//! - `tokio.io.bytes_read` - Bytes read
+//! - `tokio.io.bytes_written` - Bytes written
+//! - `tokio.io.operations` - Reads, writes, flushes and shutdowns completed,
+//!   with a `tokio.io.operation` attribute, and an `error.type` attribute for
+//!   failed operations
+//! - `tokio.io.pending_polls` - Polls of the same operations which returned
+//!   pending, with the `tokio.io.operation` attribute
+//!
+//! All are labelled with the name given on creation (`tokio.io.name`). Many
+//! pending polls compared to the completed operations mean the tasks using
+//! the I/O type spend their time waiting on it.
+//!
+//! The [`copy`] and [`copy_bidirectional`] functions mirror the ones in
+//! [`tokio::io`], for proxies spending most of their time moving bytes
+//! between two I/O types. They record, labelled with the name of the
+//! transfer (`tokio.io.name`):
+//!
+//! - `tokio.io.copy.bytes` - Bytes transferred, recorded as they are written
+//! - `tokio.io.copy.duration` - Duration of the transfers, with an `error.type`
+//!   attribute for failed transfers
+//! - `tokio.io.copy.throughput` - Average throughput of the transfers which
+//!   completed, in bytes per second
+//!
+//! With [`copy_bidirectional`], the bytes and the throughput also have a
+//! `tokio.io.copy.direction` attribute, set to either `a_to_b` or `b_to_a`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::io::InstrumentedIo;
+//! use tokio::io::{AsyncReadExt, AsyncWriteExt};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! let (client, server) = tokio::io::duplex(64);
+//! let mut client = InstrumentedIo::new("client", client);
+//! let mut server = InstrumentedIo::new("server", server);
+//!
+//! client.write_all(b"ping").await?;
+//! let mut buf = [0; 4];
+//! server.read_exact(&mut buf).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::pin::Pin;
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Instruments shared by all [`InstrumentedIo`]s.
+struct IoInstruments {
+    bytes_read: Counter<u64>,
+    bytes_written: Counter<u64>,
+    operations: Counter<u64>,
+    pending_polls: Counter<u64>,
+    copy_bytes: Counter<u64>,
+    copy_duration: Histogram<f64>,
+    copy_throughput: Histogram<f64>,
+}
+
+static IO_INSTRUMENTS: LazyLock<IoInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    IoInstruments {
+        bytes_read: meter
+            .u64_counter("tokio.io.bytes_read")
+            .with_description("The number of bytes read")
+            .with_unit("By")
+            .build(),
+        bytes_written: meter
+            .u64_counter("tokio.io.bytes_written")
+            .with_description("The number of bytes written")
+            .with_unit("By")
+            .build(),
+        operations: meter
+            .u64_counter("tokio.io.operations")
+            .with_description("The number of I/O operations completed")
+            .with_unit("{operation}")
+            .build(),
+        pending_polls: meter
+            .u64_counter("tokio.io.pending_polls")
+            .with_description("The number of polls of I/O operations which returned pending")
+            .with_unit("{poll}")
+            .build(),
+        copy_bytes: meter
+            .u64_counter("tokio.io.copy.bytes")
+            .with_description("The number of bytes transferred by copies")
+            .with_unit("By")
+            .build(),
+        copy_duration: meter
+            .f64_histogram("tokio.io.copy.duration")
+            .with_description("The duration of the copies")
+            .with_unit("s")
+            .build(),
+        copy_throughput: meter
+            .f64_histogram("tokio.io.copy.throughput")
+            .with_description("The average throughput of the copies which completed")
+            .with_unit("By/s")
+            .build(),
+    }
+});
+
+pin_project_lite::pin_project! {
+    /// An I/O type recording the bytes going through it, and the operations
+    /// done on it.
+    ///
+    /// See the [module documentation](self) for the metrics recorded.
+    #[derive(Debug)]
+    pub struct InstrumentedIo<T> {
+        #[pin]
+        inner: T,
+        labels: [KeyValue; 1],
+    }
+}
+
+impl<T> InstrumentedIo<T> {
+    /// Instrument an I/O type under the given name.
+    pub fn new(name: impl Into<Value>, io: T) -> Self {
+        Self {
+            inner: io,
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.io.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Get a reference to the underlying I/O type.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying I/O type.
+    ///
+    /// Operations done directly on the underlying I/O type are not recorded.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consume this wrapper, returning the underlying I/O type.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Record the outcome of polling an operation.
+fn record_poll<R>(labels: &[KeyValue; 1], operation: &'static str, poll: &Poll<io::Result<R>>) {
+    let [name] = labels;
+    let mut labels = vec![
+        name.clone(),
+        KeyValue::new(Key::from_static_str("tokio.io.operation"), operation),
+    ];
+
+    let instruments = &*IO_INSTRUMENTS;
+    match poll {
+        Poll::Pending => instruments.pending_polls.add(1, &labels),
+        Poll::Ready(Ok(_)) => instruments.operations.add(1, &labels),
+        Poll::Ready(Err(error)) => {
+            labels.push(crate::io_error_type(error));
+            instruments.operations.add(1, &labels);
+        }
+    }
+}
+
+/// Convert a byte count for recording.
+fn bytes(len: usize, metric: &'static str) -> u64 {
+    len.try_into()
+        .unwrap_or_else(|_| crate::overflowed(metric, u64::MAX))
+}
+
+impl<T: AsyncRead> AsyncRead for InstrumentedIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let before = buf.filled().len();
+        let result = this.inner.poll_read(cx, buf);
+        record_poll(this.labels, "read", &result);
+        if let Poll::Ready(Ok(())) = result {
+            let read = buf.filled().len() - before;
+            IO_INSTRUMENTS
+                .bytes_read
+                .add(bytes(read, "tokio.io.bytes_read"), this.labels);
+        }
+        result
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for InstrumentedIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let result = this.inner.poll_write(cx, buf);
+        record_poll(this.labels, "write", &result);
+        if let Poll::Ready(Ok(written)) = result {
+            IO_INSTRUMENTS
+                .bytes_written
+                .add(bytes(written, "tokio.io.bytes_written"), this.labels);
+        }
+        result
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        let result = this.inner.poll_write_vectored(cx, bufs);
+        record_poll(this.labels, "write", &result);
+        if let Poll::Ready(Ok(written)) = result {
+            IO_INSTRUMENTS
+                .bytes_written
+                .add(bytes(written, "tokio.io.bytes_written"), this.labels);
+        }
+        result
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let result = this.inner.poll_flush(cx);
+        record_poll(this.labels, "flush", &result);
+        result
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let result = this.inner.poll_shutdown(cx);
+        record_poll(this.labels, "shutdown", &result);
+        result
+    }
+}
+
+/// An I/O type borrowed by a copy, counting the bytes written to it.
+struct CopyTarget<'a, T: ?Sized> {
+    inner: &'a mut T,
+    labels: &'a [KeyValue],
+}
+
+impl<T: AsyncRead + Unpin + ?Sized> AsyncRead for CopyTarget<'_, T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite + Unpin + ?Sized> AsyncWrite for CopyTarget<'_, T> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut *self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            IO_INSTRUMENTS
+                .copy_bytes
+                .add(bytes(written, "tokio.io.copy.bytes"), self.labels);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Record the duration of a copy which ended, and the throughput of each of
+/// its directions if it completed.
+fn record_copy(name: &KeyValue, start: Instant, result: Result<&[(u64, &[KeyValue])], &io::Error>) {
+    let elapsed = start.elapsed().as_secs_f64();
+    let instruments = &*IO_INSTRUMENTS;
+
+    match result {
+        Ok(directions) => {
+            instruments
+                .copy_duration
+                .record(elapsed, std::slice::from_ref(name));
+            if elapsed > 0.0 {
+                for &(transferred, labels) in directions {
+                    #[expect(clippy::cast_precision_loss)]
+                    let throughput = transferred as f64 / elapsed;
+                    instruments.copy_throughput.record(throughput, labels);
+                }
+            }
+        }
+        Err(error) => {
+            instruments
+                .copy_duration
+                .record(elapsed, &[name.clone(), crate::io_error_type(error)]);
+        }
+    }
+}
+
+/// Copy the entire contents of a reader into a writer, recording the
+/// transfer under the given name.
+///
+/// This behaves like [`tokio::io::copy`], see the
+/// [module documentation](self) for the metrics recorded.
+///
+/// # Errors
+///
+/// Returns an error if reading or writing fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::io;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let mut reader: &[u8] = b"hello";
+/// let mut writer = Vec::new();
+/// io::copy("upload", &mut reader, &mut writer).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn copy<R, W>(name: impl Into<Value>, reader: &mut R, writer: &mut W) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    let labels = [KeyValue::new(
+        Key::from_static_str("tokio.io.name"),
+        name.into(),
+    )];
+    let mut writer = CopyTarget {
+        inner: writer,
+        labels: &labels,
+    };
+
+    let start = Instant::now();
+    let result = tokio::io::copy(reader, &mut writer).await;
+    let [name] = &labels;
+    match &result {
+        Ok(transferred) => record_copy(name, start, Ok(&[(*transferred, &labels)])),
+        Err(error) => record_copy(name, start, Err(error)),
+    }
+
+    result
+}
+
+/// Copy data in both directions between `a` and `b`, until both reach the
+/// end of their data, recording the transfer under the given name.
+///
+/// This behaves like [`tokio::io::copy_bidirectional`], see the
+/// [module documentation](self) for the metrics recorded. Like it, this
+/// returns the number of bytes copied from `a` to `b`, and from `b` to `a`.
+///
+/// # Errors
+///
+/// Returns an error if reading from or writing to either side fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::io;
+/// use tokio::io::{AsyncRead, AsyncWrite};
+///
+/// async fn proxy(
+///     client: &mut (impl AsyncRead + AsyncWrite + Unpin),
+///     upstream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+/// ) -> std::io::Result<()> {
+///     io::copy_bidirectional("proxy", client, upstream).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn copy_bidirectional<A, B>(
+    name: impl Into<Value>,
+    a: &mut A,
+    b: &mut B,
+) -> io::Result<(u64, u64)>
+where
+    A: AsyncRead + AsyncWrite + Unpin + ?Sized,
+    B: AsyncRead + AsyncWrite + Unpin + ?Sized,
+{
+    let name = KeyValue::new(Key::from_static_str("tokio.io.name"), name.into());
+    let direction = |direction: &'static str| {
+        KeyValue::new(Key::from_static_str("tokio.io.copy.direction"), direction)
+    };
+    let a_to_b = [name.clone(), direction("a_to_b")];
+    let b_to_a = [name.clone(), direction("b_to_a")];
+
+    // The bytes copied from `a` to `b` are the ones written to `b`
+    let mut a = CopyTarget {
+        inner: a,
+        labels: &b_to_a,
+    };
+    let mut b = CopyTarget {
+        inner: b,
+        labels: &a_to_b,
+    };
+
+    let start = Instant::now();
+    let result = tokio::io::copy_bidirectional(&mut a, &mut b).await;
+    match &result {
+        Ok((a_to_b_bytes, b_to_a_bytes)) => record_copy(
+            &name,
+            start,
+            Ok(&[(*a_to_b_bytes, &a_to_b), (*b_to_a_bytes, &b_to_a)]),
+        ),
+        Err(error) => record_copy(&name, start, Err(error)),
+    }
+
+    result
+}
@@ -0,0 +1,96 @@
+//! [`tracing_subscriber::Layer`] exposing tracing activity as metrics.
+//!
+//! The [`MetricsLayer`] counts spans and events, and attributes them to the
+//! tracked runtime they were recorded on, using the same labels as the runtime
+//! metrics.
+
+use opentelemetry::metrics::Counter;
+use tracing_core::span::{Attributes, Id};
+use tracing_core::{Event, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+/// A [`Layer`] counting spans created, spans closed and events emitted on
+/// tracked runtimes.
+///
+/// Activity is labelled with the labels of the runtime it happened on. When
+/// running outside of a tracked runtime, activity is recorded without runtime
+/// labels.
+///
+/// The instruments are created when the layer is constructed, so the global
+/// meter provider should be set up before that.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::layer::MetricsLayer;
+/// use tracing_subscriber::layer::SubscriberExt;
+/// use tracing_subscriber::util::SubscriberInitExt;
+///
+/// tracing_subscriber::registry()
+///     .with(MetricsLayer::new())
+///     .init();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    spans_created: Counter<u64>,
+    spans_closed: Counter<u64>,
+    events: Counter<u64>,
+}
+
+impl MetricsLayer {
+    /// Create a new layer, registering its instruments on the global meter
+    /// provider.
+    #[must_use]
+    pub fn new() -> Self {
+        let meter = crate::meter();
+
+        let spans_created = meter
+            .u64_counter("tokio.tracing.spans_created")
+            .with_description("The number of tracing spans created")
+            .with_unit("{span}")
+            .build();
+
+        let spans_closed = meter
+            .u64_counter("tokio.tracing.spans_closed")
+            .with_description("The number of tracing spans closed")
+            .with_unit("{span}")
+            .build();
+
+        let events = meter
+            .u64_counter("tokio.tracing.events")
+            .with_description("The number of tracing events emitted")
+            .with_unit("{event}")
+            .build();
+
+        Self {
+            spans_created,
+            spans_closed,
+            events,
+        }
+    }
+}
+
+impl Default for MetricsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Subscriber> Layer<S> for MetricsLayer {
+    fn on_new_span(&self, _attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        crate::runtime::with_current_runtime_labels(|labels| {
+            self.spans_created.add(1, labels);
+        });
+    }
+
+    fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {
+        crate::runtime::with_current_runtime_labels(|labels| self.events.add(1, labels));
+    }
+
+    fn on_close(&self, _id: Id, _ctx: Context<'_, S>) {
+        crate::runtime::with_current_runtime_labels(|labels| {
+            self.spans_closed.add(1, labels);
+        });
+    }
+}
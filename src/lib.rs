@@ -1,9 +1,242 @@
 #![doc = include_str!("../README.md")]
 #![deny(clippy::all, clippy::pedantic)]
 
-use opentelemetry::{Key, KeyValue, Value};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::{Arc, LockResult, OnceLock};
+use std::time::Duration;
 
+use opentelemetry::metrics::{Meter, MeterProvider};
+use opentelemetry::{InstrumentationScope, Key, KeyValue, Value};
+
+#[cfg(feature = "debug-http")]
+pub mod debug_http;
+#[cfg(feature = "macros")]
+mod entrypoint;
+#[cfg(feature = "fs")]
+pub mod fs;
+mod health;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "tracing-subscriber")]
+pub mod layer;
+#[cfg(feature = "logs")]
+mod logs;
+#[cfg(feature = "net")]
+pub mod net;
+#[cfg(all(feature = "os-metrics", unix))]
+mod os;
+#[cfg(feature = "process")]
+pub mod process;
+#[cfg(all(feature = "pprof", unix))]
+mod profile;
+pub mod queue;
 mod runtime;
+#[cfg(feature = "signal")]
+pub mod signal;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+// Measuring poll times is not possible without a clock
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub mod task;
+#[cfg(feature = "time")]
+pub mod time;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "tokio-util")]
+pub mod util;
+#[cfg(feature = "views")]
+pub mod views;
+
+#[cfg(feature = "macros")]
+pub use opentelemetry_instrumentation_tokio_macros::main;
+
+pub use self::health::{HealthStatus, Saturation, Thresholds, health, health_by_name};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use self::runtime::RuntimeBuilderExt;
+pub use self::runtime::{
+    Alert, Anomaly, ObservationGuard, Registry, RuntimeSnapshot, WorkerSnapshot, report, snapshots,
+};
+
+/// Items used by the code generated by the macros of this crate.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use tokio;
+
+    pub use crate::entrypoint::block_on;
+}
+
+/// The instrumentation scope of this crate, set by the first observed runtime.
+static SCOPE: OnceLock<InstrumentationScope> = OnceLock::new();
+
+/// Get the instrumentation scope of this crate, defaulting to the package name
+/// and version if no runtime was observed yet.
+fn scope() -> &'static InstrumentationScope {
+    SCOPE.get_or_init(|| Config::default().scope())
+}
+
+/// Whether the global meter provider was set, rather than being the no-op
+/// default one, see [`Config::with_provider_check`].
+pub(crate) fn meter_provider_initialized() -> bool {
+    // The no-op provider is zero-sized, unlike the providers of the SDK, but
+    // a custom provider could be too
+    size_of_val(&*opentelemetry::global::meter_provider()) != 0
+}
+
+/// Get the [`Meter`] shared by all the instruments of this crate.
+pub(crate) fn meter() -> Meter {
+    opentelemetry::global::meter_with_scope(scope().clone())
+}
+
+/// Get the [`Meter`] used by the background sampler.
+///
+/// The sampler records regular counters sharing their names with the
+/// observable counters, so they live in a separate instrumentation scope.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(crate) fn sampler_meter() -> Meter {
+    let scope = scope();
+    let mut builder = InstrumentationScope::builder(format!("{}/sampler", scope.name()))
+        .with_attributes(scope.attributes().cloned());
+    if let Some(version) = scope.version() {
+        builder = builder.with_version(version.to_owned());
+    }
+
+    opentelemetry::global::meter_with_scope(builder.build())
+}
+
+/// A meter provider the runtime instruments are registered with, in addition
+/// to the global one, see [`Config::also_with_meter_provider`].
+#[derive(Clone)]
+struct AdditionalMeterProvider {
+    provider: Arc<dyn MeterProvider + Send + Sync>,
+
+    // Shared by the clones of the configuration, so that observing several
+    // runtimes with it registers the instruments once
+    instruments: Arc<runtime::RegisteredInstruments>,
+}
+
+impl AdditionalMeterProvider {
+    /// Get the [`Meter`] of this provider, with the scope of this crate.
+    fn meter(&self) -> Meter {
+        self.provider.meter_with_scope(scope().clone())
+    }
+}
+
+impl fmt::Debug for AdditionalMeterProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdditionalMeterProvider")
+            .finish_non_exhaustive()
+    }
+}
+
+/// The error handler, set by the first observed runtime configuring one.
+static ERROR_HANDLER: OnceLock<ErrorHandler> = OnceLock::new();
+
+/// A handler for the errors encountered while collecting metrics, see
+/// [`Config::with_error_handler`].
+#[derive(Clone)]
+struct ErrorHandler(Arc<dyn Fn(&CollectionError) + Send + Sync>);
+
+impl fmt::Debug for ErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorHandler").finish_non_exhaustive()
+    }
+}
+
+/// A hook called when an alert is raised, see [`Config::on_anomaly`].
+#[derive(Clone)]
+struct AnomalyHook(Arc<dyn Fn(&Anomaly) + Send + Sync>);
+
+#[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), expect(dead_code))]
+impl AnomalyHook {
+    fn call(&self, anomaly: &Anomaly) {
+        (self.0)(anomaly);
+    }
+}
+
+impl fmt::Debug for AnomalyHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnomalyHook").finish_non_exhaustive()
+    }
+}
+
+/// A closure computing labels on each collection, see
+/// [`Config::with_dynamic_labels`].
+#[derive(Clone)]
+struct DynamicLabels(Arc<dyn Fn() -> Vec<KeyValue> + Send + Sync>);
+
+impl DynamicLabels {
+    /// Compute the current labels.
+    fn get(&self) -> Vec<KeyValue> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for DynamicLabels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynamicLabels").finish_non_exhaustive()
+    }
+}
+
+/// Report that a setting shared by all the runtimes was ignored, as a
+/// different one is already in use, see [`Config::install_shared_settings`].
+fn ignored_setting(setting: &'static str) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        setting,
+        "ignored the {setting} of the configuration, as a different one is already in use",
+    );
+    #[cfg(not(feature = "tracing"))]
+    let _ = setting;
+}
+
+/// Report an error to the error handler, if any.
+pub(crate) fn report_error(error: &CollectionError) {
+    if let Some(handler) = ERROR_HANDLER.get() {
+        (handler.0)(error);
+    }
+}
+
+/// Report that a value of the given metric overflowed, and return the value
+/// it is clamped to.
+pub(crate) fn overflowed<T>(metric: &'static str, max: T) -> T {
+    report_error(&CollectionError::Overflow { metric });
+    max
+}
+
+/// Get the guard of a lock, recovering it if it was poisoned.
+///
+/// The data behind the locks of this crate stays consistent if a panic
+/// happens while holding them, so there is no reason to panic in turn.
+pub(crate) fn recover_lock<G>(result: LockResult<G>, lock: &'static str) -> G {
+    result.unwrap_or_else(|error| {
+        report_error(&CollectionError::PoisonedLock { lock });
+        error.into_inner()
+    })
+}
+
+/// Attributes combining the labels of a runtime or resource with a few
+/// others, kept inline to avoid allocating on hot paths.
+#[cfg(any(
+    feature = "net",
+    feature = "tower",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub(crate) type Attributes = smallvec::SmallVec<[KeyValue; 8]>;
+
+/// Build the `error.type` label describing an I/O error.
+#[cfg(any(feature = "fs", feature = "io", feature = "net", feature = "process"))]
+pub(crate) fn io_error_type(error: &std::io::Error) -> KeyValue {
+    KeyValue::new(
+        Key::from_static_str("error.type"),
+        format!("{:?}", error.kind()),
+    )
+}
 
 /// Configuration for Tokio runtime instrumentation.
 ///
@@ -12,6 +245,7 @@ mod runtime;
 /// ```no_run
 /// use opentelemetry::KeyValue;
 /// use opentelemetry_instrumentation_tokio::Config;
+/// use tokio::runtime::Runtime;
 ///
 /// let rt1 = Runtime::new().unwrap();
 /// let rt2 = Runtime::new().unwrap();
@@ -19,21 +253,100 @@ mod runtime;
 /// // Add custom labels to distinguish runtimes
 /// Config::new()
 ///     .with_label("runtime.name", "api-server")
-///     .observe_runtime(rt1.handle());
+///     .observe_runtime(rt1.handle())
+///     .unwrap();
 /// Config::new()
 ///     .with_label("runtime.name", "worker")
-///     .observe_runtime(rt2.handle());
+///     .observe_runtime(rt2.handle())
+///     .unwrap();
 /// ```
 #[derive(Debug, Clone)]
+#[expect(clippy::struct_excessive_bools)]
 pub struct Config {
     labels: Vec<KeyValue>,
+    dynamic_labels: Option<DynamicLabels>,
+    baggage_labels: Vec<Key>,
+    runtime_id: Option<Value>,
+    scope: Option<(Cow<'static, str>, Cow<'static, str>)>,
+    scope_attributes: Vec<KeyValue>,
+    meter_providers: Vec<AdditionalMeterProvider>,
+    error_handler: Option<ErrorHandler>,
+    #[cfg(feature = "logs")]
+    logger_provider: Option<logs::LifecycleLoggerProvider>,
+    alive_tasks_as_up_down_counter: bool,
+    mean_poll_time_as_seconds: bool,
+    poll_time_histogram_mode: PollTimeHistogramMode,
+    poll_time_bucket_bounds_as_seconds: bool,
+    poll_time_histogram_attributes: bool,
+    sampling_interval: Option<Duration>,
+    #[cfg(feature = "tracing")]
+    summary_interval: Option<Duration>,
+    alerts: Vec<Alert>,
+    anomaly_hook: Option<AnomalyHook>,
+    #[cfg(all(feature = "pprof", unix))]
+    anomaly_profile_duration: Option<Duration>,
+    task_sampling_ratio: Option<f64>,
+    scheduler_probe_interval: Option<Duration>,
+    #[cfg(feature = "time")]
+    timer_probe_interval: Option<Duration>,
+    blocking_pool_probe_interval: Option<Duration>,
+    #[cfg(feature = "time")]
+    event_loop_lag_interval: Option<Duration>,
+    duration_unit: Option<DurationUnit>,
+    metric_set: MetricSet,
+    max_worker_series: Option<usize>,
+    change_detection_refresh: Option<u32>,
+    collection_cache_staleness: Option<Duration>,
+    collection_threads: Option<usize>,
+    duplicate_registrations: bool,
+    strict_labels: bool,
+    provider_check: bool,
 }
 
 impl Config {
     /// Create a new configuration with default settings.
     #[must_use]
     pub fn new() -> Self {
-        Self { labels: Vec::new() }
+        Self {
+            labels: Vec::new(),
+            dynamic_labels: None,
+            baggage_labels: Vec::new(),
+            runtime_id: None,
+            scope: None,
+            scope_attributes: Vec::new(),
+            meter_providers: Vec::new(),
+            error_handler: None,
+            #[cfg(feature = "logs")]
+            logger_provider: None,
+            alive_tasks_as_up_down_counter: false,
+            mean_poll_time_as_seconds: false,
+            poll_time_histogram_mode: PollTimeHistogramMode::default(),
+            poll_time_bucket_bounds_as_seconds: false,
+            poll_time_histogram_attributes: false,
+            sampling_interval: None,
+            #[cfg(feature = "tracing")]
+            summary_interval: None,
+            alerts: Vec::new(),
+            anomaly_hook: None,
+            #[cfg(all(feature = "pprof", unix))]
+            anomaly_profile_duration: None,
+            task_sampling_ratio: None,
+            scheduler_probe_interval: None,
+            #[cfg(feature = "time")]
+            timer_probe_interval: None,
+            blocking_pool_probe_interval: None,
+            #[cfg(feature = "time")]
+            event_loop_lag_interval: None,
+            duration_unit: None,
+            metric_set: MetricSet::default(),
+            max_worker_series: None,
+            change_detection_refresh: None,
+            collection_cache_staleness: None,
+            collection_threads: None,
+            duplicate_registrations: false,
+            strict_labels: false,
+            provider_check: false,
+        }
     }
 
     /// Add custom labels to this runtime's metrics.
@@ -41,8 +354,9 @@ impl Config {
     /// Labels help distinguish metrics from different runtimes when observing
     /// multiple runtimes in the same process.
     ///
-    /// When `tokio_unstable` is enabled, a `tokio.runtime.id` label is
-    /// automatically added in addition to any custom labels.
+    /// A `tokio.runtime.id` label is automatically added in addition to any
+    /// custom labels. It is the runtime ID, unless set with
+    /// [`Self::with_runtime_id`].
     ///
     /// # Examples
     ///
@@ -51,13 +365,14 @@ impl Config {
     /// use opentelemetry_instrumentation_tokio::Config;
     ///
     /// # #[tokio::main]
-    /// # async fn main() {
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// Config::new()
     ///     .with_labels([
     ///         KeyValue::new("runtime.name", "worker-pool"),
     ///         KeyValue::new("env", "production"),
     ///     ])
-    ///     .observe_current_runtime();
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
     /// # }
     /// ```
     #[must_use]
@@ -76,11 +391,12 @@ impl Config {
     /// use opentelemetry_instrumentation_tokio::Config;
     ///
     /// # #[tokio::main]
-    /// # async fn main() {
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// Config::new()
     ///     .with_label("runtime.name", "api-server")
     ///     .with_label("runtime.purpose", "http-requests")
-    ///     .observe_current_runtime();
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
     /// # }
     /// ```
     #[must_use]
@@ -89,42 +405,119 @@ impl Config {
         self
     }
 
-    /// Observe metrics for the current Tokio runtime.
+    /// Name this runtime, with the `tokio.runtime.name` label.
     ///
-    /// This is a convenience method that calls [`Self::observe_runtime`] with
-    /// the current runtime handle.
+    /// This is the same as `with_label("tokio.runtime.name", name)`, but the
+    /// name is also used to refer to the runtime outside of its metrics: in
+    /// the [`report`], the [`RuntimeSnapshot::name`], the `tracing` events
+    /// of the runtime summaries and alerts, and to look the runtime up with
+    /// [`health_by_name`].
     ///
-    /// # Panics
+    /// # Examples
     ///
-    /// Panics if called outside of a Tokio runtime context.
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new().named("api-server").observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn named(self, name: impl Into<Value>) -> Self {
+        self.with_label(runtime::RUNTIME_NAME_KEY, name)
+    }
+
+    /// Add labels computed on each collection to this runtime's metrics.
+    ///
+    /// Unlike [`Self::with_labels`], the labels can change over time without
+    /// observing the runtime again, e.g. the current deployment color or the
+    /// role of the instance in a leader election. The closure is called for
+    /// each metric collected, so it should be cheap, and its labels shouldn't
+    /// reuse the keys of the other labels of the runtime.
+    ///
+    /// The labels are only added to the exported metrics, and not to the
+    /// snapshots, logs or alerts of the runtime.
     ///
     /// # Examples
     ///
     /// ```no_run
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    ///
+    /// use opentelemetry::KeyValue;
     /// use opentelemetry_instrumentation_tokio::Config;
     ///
+    /// static LEADER: AtomicBool = AtomicBool::new(false);
+    ///
     /// # #[tokio::main]
-    /// # async fn main() {
-    /// Config::new().observe_current_runtime();
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_dynamic_labels(|| {
+    ///         let role = if LEADER.load(Ordering::Relaxed) {
+    ///             "leader"
+    ///         } else {
+    ///             "follower"
+    ///         };
+    ///         vec![KeyValue::new("role", role)]
+    ///     })
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
     /// # }
     /// ```
-    pub fn observe_current_runtime(self) {
-        let handle = tokio::runtime::Handle::current();
-        self.observe_runtime(&handle);
+    #[must_use]
+    pub fn with_dynamic_labels(
+        mut self,
+        labels: impl Fn() -> Vec<KeyValue> + Send + Sync + 'static,
+    ) -> Self {
+        self.dynamic_labels = Some(DynamicLabels(Arc::new(labels)));
+        self
     }
 
-    /// Observe metrics for a specific Tokio runtime.
+    /// Add the values of the given keys of the current
+    /// [`Baggage`](opentelemetry::baggage::Baggage) to this runtime's metrics.
     ///
-    /// Registers OpenTelemetry observable instruments that expose Tokio runtime
-    /// metrics. The metrics are collected on-demand by the configured meter
-    /// provider.
+    /// The baggage is read from the current [`Context`](opentelemetry::Context)
+    /// when the runtime is observed, so that e.g. the tenant or partition a
+    /// runtime is dedicated to is reported consistently with the traces. Keys
+    /// missing from the baggage are skipped. To read it on each collection
+    /// instead, use [`Self::with_dynamic_labels`].
     ///
-    /// This function can be called multiple times to observe multiple runtimes.
-    /// Each runtime's metrics will be distinguished by the labels configured
-    /// via [`Self::with_labels`] or [`Self::with_label`].
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry::baggage::BaggageExt;
+    /// use opentelemetry::{Context, KeyValue};
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let _guard =
+    ///     Context::current_with_baggage([KeyValue::new("tenant", "acme")]).attach();
+    /// Config::new()
+    ///     .with_baggage_labels(["tenant", "partition"])
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_baggage_labels(mut self, keys: impl IntoIterator<Item = impl Into<Key>>) -> Self {
+        self.baggage_labels.extend(keys.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the `tokio.runtime.id` label of this runtime.
+    ///
+    /// This overrides the runtime ID assigned by Tokio, which changes between
+    /// runs.
+    /// Deterministic IDs keep the series stable across restarts.
     ///
-    /// When `tokio_unstable` is enabled, a `tokio.runtime.id` label is
-    /// automatically added.
+    /// A runtime observed with the same ID and labels as a runtime which is
+    /// still observed, e.g. replacing it, gets a `tokio.runtime.instance`
+    /// label telling them apart, so that backends don't mistake its counters
+    /// starting from zero for the counters of the previous runtime going
+    /// backwards. Instances are reused once the runtime holding them shuts
+    /// down or is unobserved, in which case backends see a counter reset.
     ///
     /// # Examples
     ///
@@ -132,65 +525,1481 @@ impl Config {
     /// use opentelemetry_instrumentation_tokio::Config;
     ///
     /// # #[tokio::main]
-    /// # async fn main() {
-    /// let handle = tokio::runtime::Handle::current();
-    /// Config::new().observe_runtime(&handle);
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_runtime_id("ingest-0")
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_runtime_id(mut self, id: impl Into<Value>) -> Self {
+        self.runtime_id = Some(id.into());
+        self
+    }
+
+    /// Override the name and version of the instrumentation scope of this
+    /// crate's metrics.
+    ///
+    /// They default to the name and version of this crate. The metrics
+    /// recorded by the background sampler (see
+    /// [`Self::with_sampling_interval`]) use the same scope name, suffixed by
+    /// `/sampler`.
+    ///
+    /// The instrumentation scope is shared by all the instruments of this
+    /// crate, so this only applies to the first observed runtime, and should
+    /// be set before using any of the instrumented wrappers. A different
+    /// scope set later is ignored, which is logged with the `tracing` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_scope("com.example.tokio", "1.0.0")
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_scope(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        version: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.scope = Some((name.into(), version.into()));
+        self
+    }
+
+    /// Add attributes to the instrumentation scope of this crate's metrics.
+    ///
+    /// Unlike labels, these are attached once to the scope, and can be used
+    /// to filter the metrics of this crate in the collector.
+    ///
+    /// The instrumentation scope is shared by all the instruments of this
+    /// crate, so this only applies to the first observed runtime, and should
+    /// be set before using any of the instrumented wrappers. A different
+    /// scope set later is ignored, which is logged with the `tracing` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_scope_attributes([KeyValue::new("team", "platform")])
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_scope_attributes(mut self, attributes: impl IntoIterator<Item = KeyValue>) -> Self {
+        self.scope_attributes.extend(attributes);
+        self
+    }
+
+    /// Also register the runtime instruments with the given meter provider, in
+    /// addition to the global one.
+    ///
+    /// This allows exporting the runtime metrics to several pipelines with
+    /// separate providers. The instruments registered with this provider
+    /// report all the tracked runtimes, like the ones registered with the
+    /// global provider, so they are only registered once for all the runtimes
+    /// observed with this configuration and its clones. Adding the same
+    /// provider again to another configuration registers them a second time,
+    /// reporting each runtime twice, so each provider should only be added
+    /// once.
+    ///
+    /// Only the runtime metrics are reported to additional providers: the
+    /// instrumented wrappers and the background sampler (see
+    /// [`Self::with_sampling_interval`]) only use the global provider.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    /// use opentelemetry_sdk::metrics::SdkMeterProvider;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let prometheus_provider = SdkMeterProvider::builder().build();
+    ///
+    /// Config::new()
+    ///     .also_with_meter_provider(prometheus_provider.clone())
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn also_with_meter_provider(
+        mut self,
+        provider: impl MeterProvider + Send + Sync + 'static,
+    ) -> Self {
+        self.meter_providers.push(AdditionalMeterProvider {
+            provider: Arc::new(provider),
+            instruments: Arc::new(runtime::RegisteredInstruments::new()),
+        });
+        self
+    }
+
+    /// Emit the lifecycle events of this runtime as log records through the
+    /// given logger provider.
+    ///
+    /// A `tokio.runtime.observed` event is emitted when observing the runtime,
+    /// and a `tokio.runtime.shutdown` event when it shuts down, both with the
+    /// labels of the runtime and its number of workers (`tokio.workers`), so
+    /// that dashboards can annotate the metric discontinuities.
+    ///
+    /// The shutdown is detected through a task spawned on the runtime, which
+    /// is counted in the number of alive tasks.
+    ///
+    /// The panics of the tasks spawned through the [`task`] module are also
+    /// emitted as `tokio.task.panic` events, through the logger provider of
+    /// the first runtime setting one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    /// use opentelemetry_sdk::logs::SdkLoggerProvider;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let logger_provider = SdkLoggerProvider::builder().build();
+    ///
+    /// Config::new()
+    ///     .with_logger_provider(logger_provider.clone())
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "logs")]
+    #[must_use]
+    pub fn with_logger_provider<P>(mut self, provider: P) -> Self
+    where
+        P: opentelemetry::logs::LoggerProvider + Send + Sync + 'static,
+        P::Logger: Send + Sync + 'static,
+    {
+        self.logger_provider = Some(logs::LifecycleLoggerProvider::new(provider));
+        self
+    }
+
+    /// Set a handler for the errors encountered while collecting metrics.
+    ///
+    /// Values which don't fit in their instrument are clamped, poisoned locks
+    /// are recovered, and runtimes for which collecting a metric panics are
+    /// evicted, so that collecting metrics never panics inside the exporter.
+    /// The handler is told about these, e.g. to log them.
+    ///
+    /// The handler is shared by all the instruments of this crate, so this
+    /// only applies to the first observed runtime setting one. A different
+    /// handler set later is ignored, which is logged with the `tracing`
+    /// feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_error_handler(|error| eprintln!("failed to collect Tokio metrics: {error}"))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
     /// # }
     /// ```
+    #[must_use]
+    pub fn with_error_handler(
+        mut self,
+        handler: impl Fn(&CollectionError) + Send + Sync + 'static,
+    ) -> Self {
+        self.error_handler = Some(ErrorHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Only record a fraction of the tasks spawned through the [`task`]
+    /// module.
+    ///
+    /// Recording every task of a runtime spawning millions of them per second
+    /// would be too costly. With a ratio under 1, only this fraction of the
+    /// tasks are recorded, spread evenly, and the others run without any
+    /// overhead. The metrics of the recorded tasks carry the ratio in a
+    /// `tokio.task.sampling_ratio` label, so that the counts can be divided
+    /// by it to estimate the totals.
+    ///
+    /// The task metrics are shared by all the runtimes, so this only applies
+    /// to the first observed runtime setting a ratio. A different ratio set
+    /// later is ignored, which is logged with the `tracing` feature. This has
+    /// no effect on `wasm32-unknown-unknown`, where the tasks are not
+    /// instrumented.
     ///
     /// # Panics
     ///
-    /// Panics if the global runtime registry is poisoned.
-    pub fn observe_runtime(self, handle: &tokio::runtime::Handle) {
-        self::runtime::track_runtime(handle, &self.labels);
+    /// Panics if `ratio` is not greater than 0 and at most 1.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_task_sampling(0.01)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_task_sampling(mut self, ratio: f64) -> Self {
+        assert!(
+            ratio > 0.0 && ratio <= 1.0,
+            "the task sampling ratio must be greater than 0 and at most 1"
+        );
+        self.task_sampling_ratio = Some(ratio);
+        self
     }
-}
 
-impl Default for Config {
-    fn default() -> Self {
-        Self::new()
+    /// Export `tokio.alive_tasks` as an up-down counter instead of a gauge.
+    ///
+    /// The number of alive tasks is an additive value, which the OpenTelemetry
+    /// specification suggests exporting as an up-down counter. Some backends
+    /// mishandle rates computed over gauges, which this avoids.
+    ///
+    /// The instrument kind is shared between all runtimes, so this should be
+    /// set consistently when observing multiple runtimes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_alive_tasks_as_up_down_counter(true)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_alive_tasks_as_up_down_counter(mut self, enabled: bool) -> Self {
+        self.alive_tasks_as_up_down_counter = enabled;
+        self
     }
-}
 
-/// Observe metrics for the current Tokio runtime.
-///
-/// This is a convenience function that uses default configuration.
-/// For more control, use [`Config`].
-///
-/// # Panics
-///
-/// Panics if called outside of a Tokio runtime context.
-///
-/// # Examples
-///
-/// ```no_run
-/// use opentelemetry_instrumentation_tokio;
-///
-/// # #[tokio::main]
-/// # async fn main() {
-/// opentelemetry_instrumentation_tokio::observe_current_runtime();
-/// # }
-/// ```
-pub fn observe_current_runtime() {
-    Config::default().observe_current_runtime();
-}
+    /// Export `tokio.worker.mean_poll_time` as a floating point number of
+    /// seconds instead of an integer number of nanoseconds.
+    ///
+    /// This aligns the metric with the OpenTelemetry conventions for durations,
+    /// and avoids truncating it to whole nanoseconds.
+    ///
+    /// The instrument kind is shared between all runtimes, so this should be
+    /// set consistently when observing multiple runtimes. This has no effect
+    /// without `tokio_unstable`, as the metric isn't available.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_mean_poll_time_as_seconds(true)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_mean_poll_time_as_seconds(mut self, enabled: bool) -> Self {
+        self.mean_poll_time_as_seconds = enabled;
+        self
+    }
 
-/// Observe metrics for a specific Tokio runtime.
+    /// Set how the poll time histogram of the runtime is exported.
+    ///
+    /// See [`PollTimeHistogramMode`] for the available modes. This has no
+    /// effect without `tokio_unstable`, or if the poll time histogram isn't
+    /// enabled on the runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::{Config, PollTimeHistogramMode};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_poll_time_histogram_mode(PollTimeHistogramMode::Exponential)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_poll_time_histogram_mode(mut self, mode: PollTimeHistogramMode) -> Self {
+        self.poll_time_histogram_mode = mode;
+        self
+    }
+
+    /// Label the `tokio.worker.poll_time_bucket` gauges with their upper bound
+    /// as a decimal string in seconds, instead of an integer number of
+    /// nanoseconds.
+    ///
+    /// By default, the `le` attribute holds an integer, except for the last
+    /// bucket which is the string `+Inf`. Some backends reject attributes with
+    /// mixed types; with this option, all `le` values are strings formatted
+    /// the way Prometheus does (e.g. `0.0001`, `+Inf`).
+    ///
+    /// This should be set consistently when observing multiple runtimes. This
+    /// has no effect without `tokio_unstable`, or with
+    /// [`PollTimeHistogramMode::Exponential`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_poll_time_bucket_bounds_as_seconds(true)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_poll_time_bucket_bounds_as_seconds(mut self, enabled: bool) -> Self {
+        self.poll_time_bucket_bounds_as_seconds = enabled;
+        self
+    }
+
+    /// Label the `tokio.worker.poll_time_bucket` gauges with the configuration
+    /// of the poll time histogram of the runtime, so that the buckets can be
+    /// interpreted without reading the code building the runtime.
+    ///
+    /// The gauges get a `tokio.poll_time_histogram.buckets` attribute with the
+    /// number of buckets, and `tokio.poll_time_histogram.resolution` and
+    /// `tokio.poll_time_histogram.max` attributes with the width of the first
+    /// bucket and the start of the last, unbounded one, in the unit of the
+    /// `le` attribute.
+    ///
+    /// This has no effect without `tokio_unstable`, or with
+    /// [`PollTimeHistogramMode::Exponential`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_poll_time_histogram_attributes(true)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_poll_time_histogram_attributes(mut self, enabled: bool) -> Self {
+        self.poll_time_histogram_attributes = enabled;
+        self
+    }
+
+    /// Select which metrics are exported for this runtime.
+    ///
+    /// See [`MetricSet`] for what each set includes. All metrics are exported
+    /// by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::{Config, MetricSet};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_metric_set(MetricSet::Minimal)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_metric_set(mut self, metric_set: MetricSet) -> Self {
+        self.metric_set = metric_set;
+        self
+    }
+
+    /// Cap the number of series exported for the per-worker metrics.
+    ///
+    /// The first `max` workers get their own series, and the remaining ones
+    /// are grouped under a single series with `tokio.worker.index` set to
+    /// `other`. For this series, counters and queue depths are summed over
+    /// the grouped workers, while ratios and mean durations report the
+    /// maximum value. This keeps cardinality bounded for runtimes with many
+    /// workers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_max_worker_series(16)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_max_worker_series(mut self, max: usize) -> Self {
+        self.max_worker_series = Some(max);
+        self
+    }
+
+    /// Skip the observations of the per-worker counters which didn't change
+    /// since the previous collection, observing all of them again every
+    /// `refresh_every` collections.
+    ///
+    /// On idle runtimes, most per-worker counters don't move between two
+    /// collections, yet each of them is observed, once per worker. With this
+    /// option, the last observed values are cached, and only the series which
+    /// changed are observed. Since those are cumulative counters, the meter
+    /// provider keeps reporting the last observed value of the skipped series
+    /// with cumulative temporality. With delta temporality, the skipped series
+    /// aren't reported, and the meter provider forgets their last value, so
+    /// that the full refreshes report their totals again as increments. The
+    /// last observed values are also shared by all the readers of the meter
+    /// provider, so a reader can miss the changes observed by another one.
+    /// This option should thus only be used with a single reader, with
+    /// cumulative temporality.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `refresh_every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_change_detection(10)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_change_detection(mut self, refresh_every: u32) -> Self {
+        assert!(
+            refresh_every > 0,
+            "the change detection refresh must be non-zero"
+        );
+        self.change_detection_refresh = Some(refresh_every);
+        self
+    }
+
+    /// Set the unit in which durations are exported.
+    ///
+    /// This applies to `tokio.worker.busy_duration`,
+    /// `tokio.worker.mean_poll_time` and the bounds of the
+    /// `tokio.worker.poll_time_bucket` gauges, which otherwise are
+    /// respectively exported in milliseconds, nanoseconds and nanoseconds.
+    /// Values in seconds are exported as floating point numbers, and so are
+    /// mean poll times in milliseconds, as they are usually below one
+    /// millisecond.
+    ///
+    /// [`Self::with_mean_poll_time_as_seconds`] and
+    /// [`Self::with_poll_time_bucket_bounds_as_seconds`] take precedence over
+    /// this.
+    ///
+    /// The instrument kinds are shared between all runtimes, so this should be
+    /// set consistently when observing multiple runtimes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::{Config, DurationUnit};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_duration_unit(DurationUnit::Seconds)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_duration_unit(mut self, unit: DurationUnit) -> Self {
+        self.duration_unit = Some(unit);
+        self
+    }
+
+    /// Record the runtime counters from a background thread every
+    /// `interval`, instead of reading them on collection.
+    ///
+    /// By default, counters like `tokio.worker.polls` are exported through
+    /// observable instruments, which report the totals read from the runtime
+    /// whenever the meter provider collects them. With this option, a
+    /// background thread periodically computes how much the counters increased
+    /// and adds it to regular counter instruments, which works better with
+    /// push exporters and delta temporality.
+    ///
+    /// Those counters keep their names, but are recorded under a separate
+    /// `opentelemetry-instrumentation-tokio/sampler` instrumentation scope.
+    /// This interval is also used for sampling the poll time histogram with
+    /// [`PollTimeHistogramMode::Exponential`], instead of the default of 10
+    /// seconds.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_sampling_interval(Duration::from_secs(5))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_sampling_interval(mut self, interval: Duration) -> Self {
+        assert!(
+            !interval.is_zero(),
+            "the sampling interval must be non-zero"
+        );
+        self.sampling_interval = Some(interval);
+        self
+    }
+
+    /// Log a one-line summary of the runtime health through `tracing` every
+    /// `interval`, for environments which only collect logs.
+    ///
+    /// The summary is logged at the `INFO` level by the background sampler
+    /// thread, with the runtime labels, the number of alive tasks, the depth
+    /// of the global queue (and of the local queues with `tokio_unstable`),
+    /// and the utilization of the busiest worker since the last summary.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_summary_interval(Duration::from_secs(60))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "tracing")]
+    #[must_use]
+    pub fn with_summary_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "the summary interval must be non-zero");
+        self.summary_interval = Some(interval);
+        self
+    }
+
+    /// Raise an alert when a metric of the runtime crosses a threshold.
+    ///
+    /// The thresholds are checked by the background sampler thread at the
+    /// sampling interval (see [`Self::with_sampling_interval`]), 10 seconds by
+    /// default. When an alert is raised, and once the metric is back under the
+    /// threshold, a `tokio.runtime.alert` or `tokio.runtime.alert_resolved`
+    /// event is emitted through the logger provider (see
+    /// `Config::with_logger_provider`, with the `logs` feature), and a
+    /// `tracing` event is logged, with the `tracing` feature. Those carry the
+    /// runtime labels, and `tokio.alert.condition`, `tokio.alert.threshold`
+    /// and `tokio.alert.value` attributes.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// threads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::{Alert, Config};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_alert(Alert::global_queue_depth_above(1000).sustained_for(Duration::from_secs(30)))
+    ///     .with_alert(Alert::busy_ratio_above(0.95))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_alert(mut self, alert: Alert) -> Self {
+        self.alerts.push(alert);
+        self
+    }
+
+    /// Call a hook when one of the alerts set with [`Self::with_alert`] is
+    /// raised.
+    ///
+    /// This lets the application react to anomalies as they happen, e.g. by
+    /// dumping some state of its own when the mean poll time or the scheduler
+    /// latency spikes (see [`Alert::mean_poll_time_above`] and
+    /// [`Alert::scheduler_latency_above`]). The hook is called on the
+    /// background sampler thread, so anything slow should happen on another
+    /// thread, to keep the other alerts and samples on time. With the `pprof`
+    /// feature, [`Self::with_anomaly_profiling`] records a CPU profile
+    /// instead.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// threads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::{Alert, Config};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_scheduler_probe(Duration::from_secs(1))
+    ///     .with_alert(Alert::scheduler_latency_above(Duration::from_millis(100)))
+    ///     .with_alert(Alert::mean_poll_time_above(Duration::from_millis(10)))
+    ///     .on_anomaly(|anomaly| {
+    ///         eprintln!(
+    ///             "{} is {} (max {}), on the runtime {:?}",
+    ///             anomaly.condition(),
+    ///             anomaly.value(),
+    ///             anomaly.threshold(),
+    ///             anomaly.labels(),
+    ///         );
+    ///     })
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn on_anomaly(mut self, hook: impl Fn(&Anomaly) + Send + Sync + 'static) -> Self {
+        self.anomaly_hook = Some(AnomalyHook(Arc::new(hook)));
+        self
+    }
+
+    /// Record a CPU profile of the process for the given duration when one of
+    /// the alerts set with [`Self::with_alert`] is raised, and emit it through
+    /// the logger provider set with [`Self::with_logger_provider`].
+    ///
+    /// The profile is emitted as a `tokio.runtime.profile` event, with the
+    /// attributes of the alert and the duration of the profile
+    /// (`tokio.profile.duration`, in seconds), and the profile in the pprof
+    /// format as its body, which `go tool pprof` reads.
+    ///
+    /// The profile is recorded on a thread of its own, sampling the stacks of
+    /// all the threads 99 times per second. Profiles are capped at 30 seconds,
+    /// and only one is recorded at a time in the process, so the alerts raised
+    /// while a profile is being recorded don't get one.
+    ///
+    /// This has no effect without a logger provider, and on
+    /// `wasm32-unknown-unknown`, which doesn't support threads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::{Alert, Config};
+    /// use opentelemetry_sdk::logs::SdkLoggerProvider;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let logger_provider = SdkLoggerProvider::builder().build();
+    ///
+    /// Config::new()
+    ///     .with_logger_provider(logger_provider.clone())
+    ///     .with_alert(Alert::mean_poll_time_above(Duration::from_millis(10)))
+    ///     .with_anomaly_profiling(Duration::from_secs(5))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(all(feature = "pprof", unix))]
+    #[must_use]
+    pub fn with_anomaly_profiling(mut self, duration: Duration) -> Self {
+        self.anomaly_profile_duration = Some(duration.min(profile::MAX_PROFILE_DURATION));
+        self
+    }
+
+    /// Periodically spawn a no-op task on the runtime, and record how long it
+    /// took to be polled in a `tokio.scheduler.latency` histogram, in seconds.
+    ///
+    /// Unlike the queue depths, this tells how far behind the runtime is
+    /// running, whatever the reason: long polls hogging the workers, a deep
+    /// queue, or workers blocked outside the runtime. The task is spawned by
+    /// the background sampler thread every `interval`, and a new one is only
+    /// spawned once the previous one ran, so a stalled runtime records a
+    /// single long latency once it recovers.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_scheduler_probe(Duration::from_secs(1))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_scheduler_probe(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "the probe interval must be non-zero");
+        self.scheduler_probe_interval = Some(interval);
+        self
+    }
+
+    /// Periodically spawn a task sleeping for a millisecond on the runtime,
+    /// and record how late it resumed in a `tokio.timer.latency` histogram, in
+    /// seconds.
+    ///
+    /// The task resumes once the timer driver fired the sleep, and a worker
+    /// polled the task, so this includes the scheduler latency measured by
+    /// [`Self::with_scheduler_probe`]. Comparing the two tells whether the
+    /// runtime is slow to fire its timers, e.g. because the worker driving
+    /// them is blocked, or slow to run its tasks. As the timers have a
+    /// granularity of a millisecond, up to a millisecond of latency is
+    /// expected.
+    ///
+    /// The runtime must have its time driver enabled. This has no effect on
+    /// `wasm32-unknown-unknown`, which doesn't support threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_scheduler_probe(Duration::from_secs(1))
+    ///     .with_timer_probe(Duration::from_secs(1))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "time")]
+    #[must_use]
+    pub fn with_timer_probe(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "the probe interval must be non-zero");
+        self.timer_probe_interval = Some(interval);
+        self
+    }
+
+    /// Periodically run a no-op closure on the blocking pool of the runtime,
+    /// and record how long it took to start in a `tokio.blocking_pool.latency`
+    /// histogram, in seconds.
+    ///
+    /// Once all the blocking threads are busy, the closures passed to
+    /// [`tokio::task::spawn_blocking`] wait in a queue, whose depth is only
+    /// exported with `tokio_unstable`. This detects the blocking pool being
+    /// exhausted on stable Tokio too. The latency also includes spawning a
+    /// new blocking thread, when none is idle.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_blocking_pool_probe(Duration::from_secs(5))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_blocking_pool_probe(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "the probe interval must be non-zero");
+        self.blocking_pool_probe_interval = Some(interval);
+        self
+    }
+
+    /// On `current_thread` runtimes, run a task ticking every `interval`, and
+    /// record how late each tick runs in a `tokio.event_loop.lag` histogram,
+    /// in seconds.
+    ///
+    /// A `current_thread` runtime runs all its tasks and drives its timers on
+    /// a single thread, so the per-worker metrics don't tell much about it.
+    /// Like the event loop lag of Node.js, this measures how long any task
+    /// blocks the loop: a tick is late by as long as the task running when it
+    /// was due kept the thread. As the timers have a granularity of a
+    /// millisecond, up to a millisecond of lag is expected.
+    ///
+    /// Unlike [`Self::with_timer_probe`], the ticks are scheduled by the
+    /// runtime itself instead of the background sampler thread, so every
+    /// stall longer than `interval` is measured. The runtime must have its
+    /// time driver enabled. This has no effect on multi-threaded runtimes, nor
+    /// on `wasm32-unknown-unknown`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_event_loop_lag(Duration::from_millis(100))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "time")]
+    #[must_use]
+    pub fn with_event_loop_lag(mut self, interval: Duration) -> Self {
+        assert!(
+            !interval.is_zero(),
+            "the event loop lag interval must be non-zero"
+        );
+        self.event_loop_lag_interval = Some(interval);
+        self
+    }
+
+    /// Reuse the values read from the runtime for the collections happening
+    /// within `staleness` of each other.
+    ///
+    /// With several metric readers, e.g. a Prometheus exporter next to a
+    /// periodic OTLP exporter, each collection reads the runtime metrics
+    /// again. With this option, the values read from the runtime are cached
+    /// for `staleness`, so back-to-back or concurrent collections report the
+    /// same values from a single read. A short duration, like a second, is
+    /// usually enough.
+    ///
+    /// This has no effect on `wasm32-unknown-unknown`, which doesn't support
+    /// measuring time.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_collection_cache(Duration::from_secs(1))
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_collection_cache(mut self, staleness: Duration) -> Self {
+        self.collection_cache_staleness = Some(staleness);
+        self
+    }
+
+    /// Observe the tracked runtimes from up to `threads` threads on
+    /// collection, instead of one after the other on the collecting thread.
+    ///
+    /// With many tracked runtimes, e.g. one per tenant, reading the metrics of
+    /// each of them adds up, and the collection time grows with their number.
+    /// With this option, once at least 16 runtimes are tracked, they are
+    /// split in `threads` chunks observed concurrently, the collecting thread
+    /// taking one of the chunks and a pool of `threads - 1` threads, spawned
+    /// on the first collection needing them, the other ones. With fewer
+    /// runtimes, handing them over to other threads would cost more than it
+    /// saves, so they are observed on the collecting thread.
+    ///
+    /// This setting applies to all the tracked runtimes: the largest number of
+    /// threads configured for any of them is used. It has no effect on
+    /// `wasm32-unknown-unknown`, which doesn't support threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `threads` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new()
+    ///     .with_collection_threads(4)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_collection_threads(mut self, threads: usize) -> Self {
+        assert!(
+            threads > 0,
+            "the number of collection threads must be non-zero"
+        );
+        self.collection_threads = Some(threads);
+        self
+    }
+
+    /// Register runtimes which are already observed again, instead of
+    /// rejecting them.
+    ///
+    /// Observing the same runtime twice reports each of its observations
+    /// twice, so by default the second registration fails with
+    /// [`ObserveError::DuplicateRegistration`]. Enable this when registering a
+    /// runtime again is intentional, e.g. to report it with a second set of
+    /// labels.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// opentelemetry_instrumentation_tokio::observe_current_runtime()?;
+    /// Config::new()
+    ///     .with_label("view", "tenant")
+    ///     .with_duplicate_registrations(true)
+    ///     .observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_duplicate_registrations(mut self, allowed: bool) -> Self {
+        self.duplicate_registrations = allowed;
+        self
+    }
+
+    /// Reject runtimes whose labels collide with the labels of another
+    /// observed runtime.
+    ///
+    /// Runtimes observed with the same labels, e.g. with the same
+    /// [`Self::with_runtime_id`], are only told apart by their
+    /// `tokio.runtime.instance` label, which is usually a mistake, unless one
+    /// replaces the other. In strict mode, such a runtime is not observed, and
+    /// [`Self::observe_runtime`] returns [`ObserveError::LabelCollision`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use opentelemetry_instrumentation_tokio::{Config, ObserveError};
+    /// use opentelemetry_sdk::metrics::SdkMeterProvider;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// opentelemetry::global::set_meter_provider(SdkMeterProvider::default());
+    /// let rt1 = Runtime::new().unwrap();
+    /// let rt2 = Runtime::new().unwrap();
+    ///
+    /// Config::new()
+    ///     .with_runtime_id("ingest")
+    ///     .with_strict_labels(true)
+    ///     .observe_runtime(rt1.handle())
+    ///     .unwrap();
+    /// let result = Config::new()
+    ///     .with_runtime_id("ingest")
+    ///     .with_strict_labels(true)
+    ///     .observe_runtime(rt2.handle());
+    /// assert!(matches!(result, Err(ObserveError::LabelCollision { .. })));
+    /// ```
+    #[must_use]
+    pub fn with_strict_labels(mut self, enabled: bool) -> Self {
+        self.strict_labels = enabled;
+        self
+    }
+
+    /// Refuse to observe runtimes while the global meter provider is not set.
+    ///
+    /// The instruments of this crate are created from the global meter
+    /// provider when the first runtime is observed. If it is still the no-op
+    /// default one by then, they never report anything, even once a provider
+    /// is set. With this check, [`Self::observe_runtime`] returns
+    /// [`ObserveError::ProviderNotInitialized`] instead, so that observing the
+    /// runtime can be retried once the provider is set.
+    ///
+    /// The no-op provider is recognized by being zero-sized, so a zero-sized
+    /// custom meter provider is also considered as not set, which is why this
+    /// is disabled by default.
+    #[must_use]
+    pub fn with_provider_check(mut self, enabled: bool) -> Self {
+        self.provider_check = enabled;
+        self
+    }
+
+    /// Observe metrics for the current Tokio runtime.
+    ///
+    /// This is a convenience method that calls [`Self::observe_runtime`] with
+    /// the current runtime handle.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ObserveError::NoRuntimeContext`] if called outside of a Tokio
+    /// runtime context, or any of the errors of [`Self::observe_runtime`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// Config::new().observe_current_runtime()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn observe_current_runtime(self) -> Result<ObservationGuard, ObserveError> {
+        let handle =
+            tokio::runtime::Handle::try_current().map_err(|_| ObserveError::NoRuntimeContext)?;
+        self.observe_runtime(&handle)
+    }
+
+    /// Observe metrics for a specific Tokio runtime.
+    ///
+    /// Registers OpenTelemetry observable instruments that expose Tokio runtime
+    /// metrics. The metrics are collected on-demand by the configured meter
+    /// provider.
+    ///
+    /// This function can be called multiple times to observe multiple runtimes.
+    /// Each runtime's metrics will be distinguished by the labels configured
+    /// via [`Self::with_labels`] or [`Self::with_label`].
+    ///
+    /// A `tokio.runtime.id` label is automatically added: the runtime ID,
+    /// unless set with [`Self::with_runtime_id`].
+    ///
+    /// The runtime is observed until it shuts down, or until
+    /// [`ObservationGuard::unobserve`] is called on the returned guard. The
+    /// shutdown is detected through a task spawned on the runtime, which gets
+    /// dropped when it shuts down, and which is not counted in
+    /// `tokio.alive_tasks`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime can't be observed:
+    ///
+    /// - [`ObserveError::ProviderNotInitialized`] if the global meter provider
+    ///   is not set yet, and no other meter provider was added with
+    ///   [`Self::also_with_meter_provider`], when checked with
+    ///   [`Self::with_provider_check`]
+    /// - [`ObserveError::DuplicateRegistration`] if the runtime is already
+    ///   observed, see [`Self::with_duplicate_registrations`]
+    /// - [`ObserveError::LabelCollision`] in strict mode, see
+    ///   [`Self::with_strict_labels`], if another runtime is already observed
+    ///   with the same labels
+    /// - [`ObserveError::RegistryUnavailable`] if the registry of observed
+    ///   runtimes was left inconsistent by a panic
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::Config;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let handle = tokio::runtime::Handle::current();
+    /// Config::new().observe_runtime(&handle)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn observe_runtime(
+        self,
+        handle: &tokio::runtime::Handle,
+    ) -> Result<ObservationGuard, ObserveError> {
+        self.install_shared_settings();
+        self::runtime::track_runtime(handle, &self)
+    }
+
+    /// Observe metrics for several Tokio runtimes sharing this configuration.
+    ///
+    /// This is equivalent to calling [`Self::observe_runtime`] for each of the
+    /// runtimes, but adds them to the registry of tracked runtimes at once,
+    /// instead of contending on it for each runtime. This is meant for
+    /// services creating a pool of runtimes at startup.
+    ///
+    /// Each runtime comes with labels added to the labels of this
+    /// configuration for it only, e.g. its index in the pool. Otherwise, the
+    /// runtimes are distinguished by their `tokio.runtime.id` label, so
+    /// [`Self::with_runtime_id`] shouldn't be used here.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry::KeyValue;
+    /// use opentelemetry_instrumentation_tokio::Config;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// let runtimes: Vec<Runtime> = (0..8).map(|_| Runtime::new().unwrap()).collect();
+    ///
+    /// Config::new()
+    ///     .with_label("runtime.pool", "tenants")
+    ///     .observe_runtimes(runtimes.iter().enumerate().map(|(index, runtime)| {
+    ///         (runtime.handle(), vec![KeyValue::new("runtime.pool.index", index as i64)])
+    ///     }))
+    ///     .unwrap();
+    /// ```
+    ///
+    /// Runtimes without labels of their own are passed with an empty list:
+    ///
+    /// ```no_run
+    /// # use opentelemetry_instrumentation_tokio::Config;
+    /// # use tokio::runtime::Runtime;
+    /// # let runtimes: Vec<Runtime> = Vec::new();
+    /// Config::new()
+    ///     .observe_runtimes(runtimes.iter().map(|runtime| (runtime.handle(), Vec::new())))
+    ///     .unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Self::observe_runtime`], in which case
+    /// none of the runtimes are observed.
+    pub fn observe_runtimes<'a>(
+        self,
+        runtimes: impl IntoIterator<Item = (&'a tokio::runtime::Handle, Vec<KeyValue>)>,
+    ) -> Result<Vec<ObservationGuard>, ObserveError> {
+        self.install_shared_settings();
+        self::runtime::track_runtimes(runtimes, &self)
+    }
+
+    /// Install the settings shared by all the runtimes, unless they were
+    /// already set, e.g. by a previously observed runtime.
+    ///
+    /// Settings different from the ones already in use are ignored, which is
+    /// reported through `tracing`.
+    fn install_shared_settings(&self) {
+        let scope = SCOPE.get_or_init(|| self.scope());
+        if (self.scope.is_some() || !self.scope_attributes.is_empty()) && *scope != self.scope() {
+            ignored_setting("instrumentation scope");
+        }
+
+        if let Some(handler) = &self.error_handler {
+            let installed = ERROR_HANDLER.get_or_init(|| handler.clone());
+            if !Arc::ptr_eq(&installed.0, &handler.0) {
+                ignored_setting("error handler");
+            }
+        }
+
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Some(ratio) = self.task_sampling_ratio
+            && task::set_sampling_ratio(ratio).to_bits() != ratio.to_bits()
+        {
+            ignored_setting("task sampling ratio");
+        }
+    }
+
+    /// Build the instrumentation scope from this configuration.
+    fn scope(&self) -> InstrumentationScope {
+        let (name, version) = self.scope.clone().unwrap_or((
+            Cow::Borrowed(env!("CARGO_PKG_NAME")),
+            Cow::Borrowed(env!("CARGO_PKG_VERSION")),
+        ));
+
+        InstrumentationScope::builder(name)
+            .with_version(version)
+            .with_attributes(self.scope_attributes.iter().cloned())
+            .build()
+    }
+}
+
+/// A preset of metrics to export for a runtime, see
+/// [`Config::with_metric_set`].
+///
+/// Each set includes the metrics of the previous ones, trading cardinality
+/// for depth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum MetricSet {
+    /// Runtime-level gauges only: `tokio.workers`, `tokio.global_queue_depth`,
+    /// `tokio.alive_tasks`, and the blocking pool gauges
+    Minimal,
+
+    /// Adds the runtime-level counters, and the per-worker metrics
+    Standard,
+
+    /// Adds the poll time histogram and the I/O driver metrics
+    #[default]
+    Full,
+}
+
+/// The unit in which durations are exported, see
+/// [`Config::with_duration_unit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DurationUnit {
+    /// Nanoseconds (`ns`)
+    Nanoseconds,
+
+    /// Milliseconds (`ms`)
+    Milliseconds,
+
+    /// Seconds (`s`), as recommended by the OpenTelemetry semantic conventions
+    Seconds,
+}
+
+impl DurationUnit {
+    /// The UCUM symbol of the unit.
+    #[must_use]
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Nanoseconds => "ns",
+            Self::Milliseconds => "ms",
+            Self::Seconds => "s",
+        }
+    }
+}
+
+/// How the poll time histogram of a runtime is exported.
+///
+/// Tokio can record a histogram of task poll times, when enabled with
+/// [`tokio::runtime::Builder::enable_metrics_poll_time_histogram`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PollTimeHistogramMode {
+    /// Export each bucket as a separate `tokio.worker.poll_time_bucket` gauge,
+    /// labelled with its upper bound (`le`), in nanoseconds unless
+    /// [`Config::with_poll_time_bucket_bounds_as_seconds`] is set.
+    #[default]
+    Buckets,
+
+    /// Periodically sample the buckets and record them in a
+    /// `tokio.worker.poll_time` histogram instrument, in seconds.
+    ///
+    /// Tokio's log-scale buckets map naturally onto an exponential histogram,
+    /// which is much cheaper in series than one gauge per bucket. The meter
+    /// provider should be configured to aggregate this instrument as such:
+    ///
+    /// ```
+    /// use opentelemetry_sdk::metrics::{Aggregation, Instrument, SdkMeterProvider, Stream};
+    ///
+    /// let provider = SdkMeterProvider::builder()
+    ///     .with_view(|instrument: &Instrument| {
+    ///         (instrument.name() == "tokio.worker.poll_time").then(|| {
+    ///             Stream::builder()
+    ///                 .with_aggregation(Aggregation::Base2ExponentialHistogram {
+    ///                     max_size: 160,
+    ///                     max_scale: 20,
+    ///                     record_min_max: true,
+    ///                 })
+    ///                 .build()
+    ///                 .unwrap()
+    ///         })
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// Each bucket is recorded as the middle of its range, every 10 seconds
+    /// unless set otherwise with [`Config::with_sampling_interval`].
+    /// When a worker polled more than 1000 tasks between two samples, the
+    /// bucket counts are scaled down to 1000 samples: the histogram keeps the
+    /// shape of the distribution, and `tokio.worker.polls` has the absolute
+    /// count of polls.
+    Exponential,
+}
+
+/// An error encountered while collecting metrics, reported to the handler set
+/// with [`Config::with_error_handler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CollectionError {
+    /// A value of the given metric didn't fit in its instrument, and was
+    /// clamped to the maximum value.
+    Overflow {
+        /// The name of the metric.
+        metric: &'static str,
+    },
+
+    /// A panic happened while holding the given lock. The lock was recovered,
+    /// as the data it protects stays consistent.
+    PoisonedLock {
+        /// The name of the lock.
+        lock: &'static str,
+    },
+
+    /// Collecting the given metric panicked for a runtime, e.g. because Tokio
+    /// panicked on a torn-down runtime. The runtime is no longer reported.
+    Panicked {
+        /// The name of the metric, or `sampler` for the background sampler.
+        metric: &'static str,
+    },
+}
+
+impl fmt::Display for CollectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overflow { metric } => write!(f, "value of {metric} overflowed"),
+            Self::PoisonedLock { lock } => write!(f, "the {lock} lock was poisoned"),
+            Self::Panicked { metric } => {
+                write!(f, "collecting {metric} panicked, the runtime was evicted")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CollectionError {}
+
+/// An error preventing a runtime from being observed, see
+/// [`Config::observe_runtime`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ObserveError {
+    /// There is no current Tokio runtime, see
+    /// [`Config::observe_current_runtime`].
+    NoRuntimeContext,
+
+    /// The runtime is already observed, see
+    /// [`Config::with_duplicate_registrations`].
+    DuplicateRegistration,
+
+    /// Another runtime is already observed with the same labels, which is
+    /// rejected in strict mode, see [`Config::with_strict_labels`].
+    LabelCollision {
+        /// The labels of the runtime.
+        labels: Vec<KeyValue>,
+    },
+
+    /// The registry of observed runtimes was poisoned by a panic while
+    /// registering a runtime.
+    RegistryUnavailable,
+
+    /// The global meter provider is not set, so the metrics wouldn't be
+    /// reported anywhere. Set it with
+    /// [`opentelemetry::global::set_meter_provider`] before observing
+    /// runtimes. Only checked with [`Config::with_provider_check`].
+    ProviderNotInitialized,
+}
+
+impl fmt::Display for ObserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoRuntimeContext => write!(f, "not called from a Tokio runtime context"),
+            Self::DuplicateRegistration => write!(f, "the runtime is already observed"),
+            Self::RegistryUnavailable => {
+                write!(f, "the registry of observed runtimes was poisoned")
+            }
+            Self::ProviderNotInitialized => write!(f, "the global meter provider is not set"),
+            Self::LabelCollision { labels } => {
+                write!(f, "another runtime is already observed with the labels")?;
+                for label in labels {
+                    write!(f, " {}={}", label.key, label.value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObserveError {}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Observe metrics for the current Tokio runtime.
 ///
 /// This is a convenience function that uses default configuration.
 /// For more control, use [`Config`].
 ///
+/// # Errors
+///
+/// See [`Config::observe_current_runtime`].
+///
 /// # Examples
 ///
 /// ```no_run
-/// use opentelemetry_instrumentation_tokio;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// opentelemetry_instrumentation_tokio::observe_current_runtime()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn observe_current_runtime() -> Result<ObservationGuard, ObserveError> {
+    Config::default().observe_current_runtime()
+}
+
+/// Observe metrics for a specific Tokio runtime.
 ///
+/// This is a convenience function that uses default configuration.
+/// For more control, use [`Config`].
+///
+/// # Errors
+///
+/// See [`Config::observe_runtime`].
+///
+/// # Examples
+///
+/// ```no_run
 /// # #[tokio::main]
-/// # async fn main() {
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// let handle = tokio::runtime::Handle::current();
-/// opentelemetry_instrumentation_tokio::observe_runtime(&handle);
+/// opentelemetry_instrumentation_tokio::observe_runtime(&handle)?;
+/// # Ok(())
 /// # }
 /// ```
-pub fn observe_runtime(handle: &tokio::runtime::Handle) {
-    Config::default().observe_runtime(handle);
+pub fn observe_runtime(handle: &tokio::runtime::Handle) -> Result<ObservationGuard, ObserveError> {
+    Config::default().observe_runtime(handle)
 }
@@ -0,0 +1,218 @@
+//! Runtime lifecycle events, emitted as OpenTelemetry log records.
+//!
+//! With [`Config::with_logger_provider`](crate::Config::with_logger_provider),
+//! a log record is emitted when a runtime is observed, and when it shuts down,
+//! with the labels and the number of workers of the runtime, so that
+//! dashboards can annotate the metric discontinuities.
+//!
+//! The panics of the tasks spawned through the [`task`](crate::task) module
+//! are emitted as well, through the logger provider of the first runtime
+//! setting one.
+
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider, Severity};
+use opentelemetry::{InstrumentationScope, Key, KeyValue, Value};
+
+/// A logger emitting lifecycle events, erasing the type of the underlying
+/// [`Logger`].
+trait EventLogger: Send + Sync {
+    fn emit_event(
+        &self,
+        name: &'static str,
+        severity: Severity,
+        body: AnyValue,
+        attributes: &[KeyValue],
+    );
+}
+
+impl<L> EventLogger for L
+where
+    L: Logger + Send + Sync,
+{
+    fn emit_event(
+        &self,
+        name: &'static str,
+        severity: Severity,
+        body: AnyValue,
+        attributes: &[KeyValue],
+    ) {
+        let mut record = self.create_log_record();
+        record.set_event_name(name);
+        record.set_severity_number(severity);
+        record.set_severity_text(severity.name());
+        record.set_body(body);
+        record.add_attributes(
+            attributes
+                .iter()
+                .map(|attribute| (attribute.key.clone(), any_value(&attribute.value))),
+        );
+        self.emit(record);
+    }
+}
+
+/// Convert an attribute [`Value`] to an [`AnyValue`].
+fn any_value(value: &Value) -> AnyValue {
+    match value {
+        Value::Bool(value) => AnyValue::Boolean(*value),
+        Value::I64(value) => AnyValue::Int(*value),
+        Value::F64(value) => AnyValue::Double(*value),
+        Value::String(value) => AnyValue::String(value.clone()),
+        value => AnyValue::String(value.to_string().into()),
+    }
+}
+
+/// A logger provider, erasing the type of its [`Logger`]s.
+trait EventLoggerProvider: Send + Sync {
+    fn event_logger(&self, scope: InstrumentationScope) -> Arc<dyn EventLogger>;
+}
+
+impl<P> EventLoggerProvider for P
+where
+    P: LoggerProvider + Send + Sync,
+    P::Logger: Send + Sync + 'static,
+{
+    fn event_logger(&self, scope: InstrumentationScope) -> Arc<dyn EventLogger> {
+        Arc::new(self.logger_with_scope(scope))
+    }
+}
+
+/// The logger provider set with
+/// [`Config::with_logger_provider`](crate::Config::with_logger_provider).
+#[derive(Clone)]
+pub(crate) struct LifecycleLoggerProvider(Arc<dyn EventLoggerProvider>);
+
+impl LifecycleLoggerProvider {
+    pub(crate) fn new<P>(provider: P) -> Self
+    where
+        P: LoggerProvider + Send + Sync + 'static,
+        P::Logger: Send + Sync + 'static,
+    {
+        Self(Arc::new(provider))
+    }
+}
+
+impl fmt::Debug for LifecycleLoggerProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LifecycleLoggerProvider")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Emits the alert events of a runtime.
+#[derive(Clone)]
+pub(crate) struct AlertLogger(Arc<dyn EventLogger>);
+
+impl AlertLogger {
+    pub(crate) fn new(provider: &LifecycleLoggerProvider) -> Self {
+        Self(provider.0.event_logger(crate::scope().clone()))
+    }
+
+    /// Emit the event for an alert being raised.
+    pub(crate) fn alert_raised(&self, attributes: &[KeyValue]) {
+        self.0.emit_event(
+            "tokio.runtime.alert",
+            Severity::Warn,
+            "Tokio runtime alert raised".into(),
+            attributes,
+        );
+    }
+
+    /// Emit the event for an alert being resolved.
+    pub(crate) fn alert_resolved(&self, attributes: &[KeyValue]) {
+        self.0.emit_event(
+            "tokio.runtime.alert_resolved",
+            Severity::Info,
+            "Tokio runtime alert resolved".into(),
+            attributes,
+        );
+    }
+
+    /// Emit the event for a CPU profile recorded when an alert was raised,
+    /// with the profile in the pprof format as its body.
+    #[cfg(all(feature = "pprof", unix))]
+    pub(crate) fn profile_recorded(&self, attributes: &[KeyValue], profile: Vec<u8>) {
+        self.0.emit_event(
+            "tokio.runtime.profile",
+            Severity::Info,
+            AnyValue::Bytes(Box::new(profile)),
+            attributes,
+        );
+    }
+}
+
+/// The logger emitting the task panic events, from the first runtime setting a
+/// logger provider.
+static TASK_PANIC_LOGGER: OnceLock<Arc<dyn EventLogger>> = OnceLock::new();
+
+/// Emits the shutdown event when dropped, which happens when the runtime
+/// drops its tasks on shutdown.
+struct ShutdownGuard {
+    logger: Arc<dyn EventLogger>,
+    attributes: Vec<KeyValue>,
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        self.logger.emit_event(
+            "tokio.runtime.shutdown",
+            Severity::Info,
+            "Tokio runtime shut down".into(),
+            &self.attributes,
+        );
+    }
+}
+
+/// Emit the event for a newly observed runtime, and watch for its shutdown.
+///
+/// The shutdown is detected through a task which never completes, and gets
+/// dropped when the runtime shuts down.
+pub(crate) fn runtime_observed(
+    provider: &LifecycleLoggerProvider,
+    handle: &tokio::runtime::Handle,
+    labels: &[KeyValue],
+) {
+    let logger = provider.0.event_logger(crate::scope().clone());
+    TASK_PANIC_LOGGER.get_or_init(|| logger.clone());
+
+    let mut attributes = labels.to_vec();
+    attributes.push(KeyValue::new(
+        Key::from_static_str("tokio.workers"),
+        i64::try_from(handle.metrics().num_workers()).unwrap_or(i64::MAX),
+    ));
+
+    logger.emit_event(
+        "tokio.runtime.observed",
+        Severity::Info,
+        "Tokio runtime observed".into(),
+        &attributes,
+    );
+
+    let guard = ShutdownGuard { logger, attributes };
+    handle.spawn(async move {
+        let _guard = guard;
+        std::future::pending::<()>().await;
+    });
+}
+
+/// Emit the event for a panic in an instrumented task, with the labels of the
+/// task and the panic message.
+pub(crate) fn task_panicked(labels: &[KeyValue], message: &str) {
+    let Some(logger) = TASK_PANIC_LOGGER.get() else {
+        return;
+    };
+
+    let mut attributes = labels.to_vec();
+    attributes.push(KeyValue::new(
+        Key::from_static_str("exception.message"),
+        message.to_owned(),
+    ));
+
+    logger.emit_event(
+        "tokio.task.panic",
+        Severity::Error,
+        "Tokio task panicked".into(),
+        &attributes,
+    );
+}
@@ -0,0 +1,540 @@
+//! Instrumented networking primitives.
+//!
+//! These wrap the [`tokio::net`] types and record metrics about their usage
+//! under the same meter as the runtime metrics:
+//!
+//! - [`InstrumentedTcpListener`] for accepted connections
+//! - [`InstrumentedUdpSocket`] for datagrams sent and received
+//! - [`lookup_host`] for DNS lookups
+
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{LazyLock, Mutex};
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+
+/// Build the labels identifying a socket.
+fn socket_labels(name_key: &'static str, name: Value, addr: Option<SocketAddr>) -> Vec<KeyValue> {
+    let mut labels = vec![KeyValue::new(Key::from_static_str(name_key), name)];
+
+    if let Some(addr) = addr {
+        labels.push(KeyValue::new(
+            Key::from_static_str("network.local.address"),
+            addr.ip().to_string(),
+        ));
+        labels.push(KeyValue::new(
+            Key::from_static_str("network.local.port"),
+            i64::from(addr.port()),
+        ));
+    }
+
+    labels
+}
+
+/// Build the labels of an I/O error, on top of the socket labels.
+fn error_labels(labels: &[KeyValue], error: &io::Error) -> crate::Attributes {
+    let mut labels: crate::Attributes = labels.iter().cloned().collect();
+    labels.push(crate::io_error_type(error));
+    labels
+}
+
+// ============================================================================
+// TCP
+// ============================================================================
+
+/// Instruments shared by all [`InstrumentedTcpListener`]s.
+struct TcpListenerInstruments {
+    accepted_connections: Counter<u64>,
+    accept_errors: Counter<u64>,
+    accept_loop_latency: Histogram<f64>,
+}
+
+static TCP_LISTENER_INSTRUMENTS: LazyLock<TcpListenerInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    TcpListenerInstruments {
+        accepted_connections: meter
+            .u64_counter("tokio.tcp_listener.accepted_connections")
+            .with_description("The number of connections accepted by the listener")
+            .with_unit("{connection}")
+            .build(),
+        accept_errors: meter
+            .u64_counter("tokio.tcp_listener.accept_errors")
+            .with_description("The number of errors returned when accepting connections")
+            .with_unit("{error}")
+            .build(),
+        accept_loop_latency: meter
+            .f64_histogram("tokio.tcp_listener.accept_loop_latency")
+            .with_description(
+                "The time between an accept returning and the next accept being started",
+            )
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// A [`TcpListener`] recording metrics about accepted connections.
+///
+/// Metrics are labelled with the listener name (`tokio.listener.name`) and its
+/// local address (`network.local.address` and `network.local.port`):
+///
+/// - `tokio.tcp_listener.accepted_connections` - Connections accepted
+/// - `tokio.tcp_listener.accept_errors` - Errors returned by accept, with an
+///   `error.type` attribute
+/// - `tokio.tcp_listener.accept_loop_latency` - Time spent between an accept
+///   returning and the next accept being started, which is how long the accept
+///   loop took to handle a connection, as long as a single task accepts
+///   connections from the listener
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::net::InstrumentedTcpListener;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let listener = InstrumentedTcpListener::bind("http", "127.0.0.1:8080").await?;
+///
+/// loop {
+///     let (stream, _addr) = listener.accept().await?;
+///     tokio::spawn(async move {
+///         // Handle the connection
+///         drop(stream);
+///     });
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedTcpListener {
+    inner: TcpListener,
+    labels: Vec<KeyValue>,
+    last_accept: Mutex<Option<Instant>>,
+}
+
+impl InstrumentedTcpListener {
+    /// Create a new [`TcpListener`] bound to the given address, and instrument
+    /// it under the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding the listener fails.
+    pub async fn bind(name: impl Into<Value>, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self::new(name, listener))
+    }
+
+    /// Instrument an existing [`TcpListener`] under the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>, listener: TcpListener) -> Self {
+        let labels = socket_labels(
+            "tokio.listener.name",
+            name.into(),
+            listener.local_addr().ok(),
+        );
+
+        Self {
+            inner: listener,
+            labels,
+            last_accept: Mutex::new(None),
+        }
+    }
+
+    /// Accept a new incoming connection, recording it in the metrics.
+    ///
+    /// See [`TcpListener::accept`]. The accept loop latency is measured from
+    /// the last accept returning, which assumes a single task accepting
+    /// connections: with several tasks accepting concurrently on the same
+    /// listener, it is measured from an accept of any of them, and doesn't
+    /// tell how long a loop took to handle its connection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if accepting the connection fails.
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let instruments = &*TCP_LISTENER_INSTRUMENTS;
+
+        if let Some(last_accept) =
+            crate::recover_lock(self.last_accept.lock(), "last accept").take()
+        {
+            instruments
+                .accept_loop_latency
+                .record(last_accept.elapsed().as_secs_f64(), &self.labels);
+        }
+
+        let result = self.inner.accept().await;
+        *crate::recover_lock(self.last_accept.lock(), "last accept") = Some(Instant::now());
+
+        match &result {
+            Ok(_) => instruments.accepted_connections.add(1, &self.labels),
+            Err(error) => instruments
+                .accept_errors
+                .add(1, &error_labels(&self.labels, error)),
+        }
+
+        result
+    }
+
+    /// Returns the local address that this listener is bound to.
+    ///
+    /// # Errors
+    ///
+    /// See [`TcpListener::local_addr`].
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Get a reference to the underlying [`TcpListener`].
+    #[must_use]
+    pub fn get_ref(&self) -> &TcpListener {
+        &self.inner
+    }
+
+    /// Consume this wrapper, returning the underlying [`TcpListener`].
+    #[must_use]
+    pub fn into_inner(self) -> TcpListener {
+        self.inner
+    }
+}
+
+// ============================================================================
+// UDP
+// ============================================================================
+
+/// Instruments shared by all [`InstrumentedUdpSocket`]s.
+struct UdpSocketInstruments {
+    datagrams_sent: Counter<u64>,
+    datagrams_received: Counter<u64>,
+    bytes_sent: Counter<u64>,
+    bytes_received: Counter<u64>,
+    send_errors: Counter<u64>,
+}
+
+static UDP_SOCKET_INSTRUMENTS: LazyLock<UdpSocketInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    UdpSocketInstruments {
+        datagrams_sent: meter
+            .u64_counter("tokio.udp_socket.datagrams_sent")
+            .with_description("The number of datagrams sent by the socket")
+            .with_unit("{datagram}")
+            .build(),
+        datagrams_received: meter
+            .u64_counter("tokio.udp_socket.datagrams_received")
+            .with_description("The number of datagrams received by the socket")
+            .with_unit("{datagram}")
+            .build(),
+        bytes_sent: meter
+            .u64_counter("tokio.udp_socket.bytes_sent")
+            .with_description("The number of bytes sent by the socket")
+            .with_unit("By")
+            .build(),
+        bytes_received: meter
+            .u64_counter("tokio.udp_socket.bytes_received")
+            .with_description("The number of bytes received by the socket")
+            .with_unit("By")
+            .build(),
+        send_errors: meter
+            .u64_counter("tokio.udp_socket.send_errors")
+            .with_description("The number of errors returned when sending datagrams")
+            .with_unit("{error}")
+            .build(),
+    }
+});
+
+/// A [`UdpSocket`] recording metrics about datagrams sent and received.
+///
+/// Metrics are labelled with the socket name (`tokio.socket.name`) and its
+/// local address (`network.local.address` and `network.local.port`):
+///
+/// - `tokio.udp_socket.datagrams_sent` - Datagrams sent
+/// - `tokio.udp_socket.datagrams_received` - Datagrams received
+/// - `tokio.udp_socket.bytes_sent` - Bytes sent
+/// - `tokio.udp_socket.bytes_received` - Bytes received
+/// - `tokio.udp_socket.send_errors` - Errors returned when sending, with an
+///   `error.type` attribute. This includes `WouldBlock` errors returned by the
+///   `try_send*` methods.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::net::InstrumentedUdpSocket;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let socket = InstrumentedUdpSocket::bind("dns", "0.0.0.0:5353").await?;
+///
+/// let mut buf = [0; 1500];
+/// loop {
+///     let (len, addr) = socket.recv_from(&mut buf).await?;
+///     socket.send_to(&buf[..len], addr).await?;
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedUdpSocket {
+    inner: UdpSocket,
+    labels: Vec<KeyValue>,
+}
+
+impl InstrumentedUdpSocket {
+    /// Create a new [`UdpSocket`] bound to the given address, and instrument it
+    /// under the given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if binding the socket fails.
+    pub async fn bind(name: impl Into<Value>, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr).await?;
+        Ok(Self::new(name, socket))
+    }
+
+    /// Instrument an existing [`UdpSocket`] under the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>, socket: UdpSocket) -> Self {
+        let labels = socket_labels("tokio.socket.name", name.into(), socket.local_addr().ok());
+
+        Self {
+            inner: socket,
+            labels,
+        }
+    }
+
+    /// Record the outcome of a send operation.
+    fn record_send(&self, result: &io::Result<usize>) {
+        let instruments = &*UDP_SOCKET_INSTRUMENTS;
+        match result {
+            Ok(len) => {
+                instruments.datagrams_sent.add(1, &self.labels);
+                instruments.bytes_sent.add(
+                    (*len).try_into().unwrap_or_else(|_| {
+                        crate::overflowed("tokio.udp_socket.bytes_sent", u64::MAX)
+                    }),
+                    &self.labels,
+                );
+            }
+            Err(error) => instruments
+                .send_errors
+                .add(1, &error_labels(&self.labels, error)),
+        }
+    }
+
+    /// Record the outcome of a receive operation.
+    fn record_recv(&self, len: usize) {
+        let instruments = &*UDP_SOCKET_INSTRUMENTS;
+        instruments.datagrams_received.add(1, &self.labels);
+        instruments.bytes_received.add(
+            len.try_into()
+                .unwrap_or_else(|_| crate::overflowed("tokio.udp_socket.bytes_received", u64::MAX)),
+            &self.labels,
+        );
+    }
+
+    /// Connect the socket to a remote address.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::connect`].
+    pub async fn connect(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        self.inner.connect(addr).await
+    }
+
+    /// Send a datagram to the connected remote address.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::send`].
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.send(buf).await;
+        self.record_send(&result);
+        result
+    }
+
+    /// Try to send a datagram to the connected remote address, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::try_send`].
+    pub fn try_send(&self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.inner.try_send(buf);
+        self.record_send(&result);
+        result
+    }
+
+    /// Send a datagram to the given address.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::send_to`].
+    pub async fn send_to(&self, buf: &[u8], target: impl ToSocketAddrs) -> io::Result<usize> {
+        let result = self.inner.send_to(buf, target).await;
+        self.record_send(&result);
+        result
+    }
+
+    /// Try to send a datagram to the given address, without waiting.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::try_send_to`].
+    pub fn try_send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        let result = self.inner.try_send_to(buf, target);
+        self.record_send(&result);
+        result
+    }
+
+    /// Receive a datagram from the connected remote address.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::recv`].
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.recv(buf).await?;
+        self.record_recv(len);
+        Ok(len)
+    }
+
+    /// Try to receive a datagram from the connected remote address, without
+    /// waiting.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::try_recv`].
+    pub fn try_recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.try_recv(buf)?;
+        self.record_recv(len);
+        Ok(len)
+    }
+
+    /// Receive a datagram, returning the address it came from.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::recv_from`].
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (len, addr) = self.inner.recv_from(buf).await?;
+        self.record_recv(len);
+        Ok((len, addr))
+    }
+
+    /// Try to receive a datagram without waiting, returning the address it
+    /// came from.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::try_recv_from`].
+    pub fn try_recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (len, addr) = self.inner.try_recv_from(buf)?;
+        self.record_recv(len);
+        Ok((len, addr))
+    }
+
+    /// Returns the local address that this socket is bound to.
+    ///
+    /// # Errors
+    ///
+    /// See [`UdpSocket::local_addr`].
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Get a reference to the underlying [`UdpSocket`].
+    ///
+    /// Operations done directly on the underlying socket are not recorded.
+    #[must_use]
+    pub fn get_ref(&self) -> &UdpSocket {
+        &self.inner
+    }
+
+    /// Consume this wrapper, returning the underlying [`UdpSocket`].
+    #[must_use]
+    pub fn into_inner(self) -> UdpSocket {
+        self.inner
+    }
+}
+
+// ============================================================================
+// DNS
+// ============================================================================
+
+/// Instruments recording the DNS lookups.
+struct DnsInstruments {
+    lookups: Counter<u64>,
+    lookup_duration: Histogram<f64>,
+}
+
+static DNS_INSTRUMENTS: LazyLock<DnsInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    DnsInstruments {
+        lookups: meter
+            .u64_counter("tokio.dns.lookups")
+            .with_description("The number of DNS lookups completed")
+            .with_unit("{lookup}")
+            .build(),
+        lookup_duration: meter
+            .f64_histogram("tokio.dns.lookup.duration")
+            .with_description("The duration of DNS lookups")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// Resolve the addresses of a host, recording the lookup.
+///
+/// This behaves like [`tokio::net::lookup_host`], and records:
+///
+/// - `tokio.dns.lookups` - Lookups completed, with a `tokio.dns.outcome`
+///   attribute set to `ok`, `empty` when no address was found, or `error` along
+///   with an `error.type` attribute
+/// - `tokio.dns.lookup.duration` - Duration of the lookups, with the same
+///   attributes
+///
+/// The lookups run on the blocking thread pool, so a slow resolver shows up
+/// as blocking pool pressure.
+///
+/// # Errors
+///
+/// Returns an error if the lookup fails.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::net;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// for addr in net::lookup_host("example.com:443").await? {
+///     println!("{addr}");
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn lookup_host(host: impl ToSocketAddrs) -> io::Result<impl Iterator<Item = SocketAddr>> {
+    let start = Instant::now();
+    let result = tokio::net::lookup_host(host)
+        .await
+        .map(Iterator::collect::<Vec<_>>);
+    let elapsed = start.elapsed();
+
+    let labels = match &result {
+        Ok(addrs) => vec![KeyValue::new(
+            Key::from_static_str("tokio.dns.outcome"),
+            if addrs.is_empty() { "empty" } else { "ok" },
+        )],
+        Err(error) => vec![
+            KeyValue::new(Key::from_static_str("tokio.dns.outcome"), "error"),
+            crate::io_error_type(error),
+        ],
+    };
+
+    let instruments = &*DNS_INSTRUMENTS;
+    instruments.lookups.add(1, &labels);
+    instruments
+        .lookup_duration
+        .record(elapsed.as_secs_f64(), &labels);
+
+    result.map(Vec::into_iter)
+}
@@ -0,0 +1,78 @@
+//! Process-level metrics read from the operating system.
+//!
+//! Scheduler anomalies are often caused by the process being preempted by the
+//! operating system, e.g. when it is CPU-throttled. These metrics are read
+//! with `getrusage` on each collection, alongside the runtime metrics, so
+//! that both can be correlated in the same pipeline.
+
+use std::mem::MaybeUninit;
+use std::time::Duration;
+
+use opentelemetry::metrics::Meter;
+use opentelemetry::{Key, KeyValue};
+
+/// Register the process-level instruments with the given meter.
+pub(crate) fn register_instruments(meter: &Meter) {
+    meter
+        .u64_observable_counter("process.context_switches")
+        .with_description("The number of times the process has been context switched")
+        .with_unit("{context_switch}")
+        .with_callback(|instrument| {
+            let Some(usage) = resource_usage() else {
+                return;
+            };
+
+            for (value, context_switch_type) in [
+                (usage.ru_nvcsw, "voluntary"),
+                (usage.ru_nivcsw, "involuntary"),
+            ] {
+                instrument.observe(
+                    u64::try_from(value).unwrap_or_default(),
+                    &[KeyValue::new(
+                        Key::from_static_str("process.context_switch.type"),
+                        context_switch_type,
+                    )],
+                );
+            }
+        })
+        .build();
+
+    meter
+        .f64_observable_counter("process.cpu.time")
+        .with_description("The CPU time consumed by the process")
+        .with_unit("s")
+        .with_callback(|instrument| {
+            let Some(usage) = resource_usage() else {
+                return;
+            };
+
+            for (time, cpu_mode) in [(usage.ru_utime, "user"), (usage.ru_stime, "system")] {
+                instrument.observe(
+                    duration(time).as_secs_f64(),
+                    &[KeyValue::new(Key::from_static_str("cpu.mode"), cpu_mode)],
+                );
+            }
+        })
+        .build();
+}
+
+/// Read the resource usage of the process.
+fn resource_usage() -> Option<libc::rusage> {
+    let mut usage = MaybeUninit::<libc::rusage>::uninit();
+    // SAFETY: `getrusage` only writes to the given pointer, which is valid for
+    // writes of a `rusage`
+    let result = unsafe { libc::getrusage(libc::RUSAGE_SELF, usage.as_mut_ptr()) };
+    if result != 0 {
+        return None;
+    }
+
+    // SAFETY: `getrusage` succeeded, so it initialized the value
+    Some(unsafe { usage.assume_init() })
+}
+
+/// Convert a `timeval` into a duration, clamping negative values to zero.
+fn duration(time: libc::timeval) -> Duration {
+    let seconds = u64::try_from(time.tv_sec).unwrap_or_default();
+    let micros = u64::try_from(time.tv_usec).unwrap_or_default();
+    Duration::from_secs(seconds) + Duration::from_micros(micros)
+}
@@ -0,0 +1,380 @@
+//! Instrumented child processes.
+//!
+//! [`InstrumentedCommand`] wraps a [`tokio::process::Command`], and records,
+//! labelled with the name given on creation (`tokio.process.name`):
+//!
+//! - `tokio.process.spawned` - Child processes spawned
+//! - `tokio.process.spawn_errors` - Child processes which failed to spawn, with
+//!   an `error.type` attribute
+//! - `tokio.process.running` - Child processes currently running
+//! - `tokio.process.wait_duration` - Time between spawning a child process and
+//!   its exit being observed, with a `process.exit.code` attribute when it
+//!   exited with a code, or an `error.type` attribute when waiting failed
+//!
+//! A child process stops being counted as running once its exit is observed
+//! through [`InstrumentedChild`], or once the [`InstrumentedChild`] is
+//! dropped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::process::InstrumentedCommand;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! let mut child = InstrumentedCommand::new("convert", "convert")
+//!     .args(["input.png", "output.jpg"])
+//!     .spawn()?;
+//! let status = child.wait().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::process::{ExitStatus, Output, Stdio};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::process::{Child, Command};
+
+/// Instruments shared by all [`InstrumentedCommand`]s.
+struct ProcessInstruments {
+    spawned: Counter<u64>,
+    spawn_errors: Counter<u64>,
+    running: UpDownCounter<i64>,
+    wait_duration: Histogram<f64>,
+}
+
+static PROCESS_INSTRUMENTS: LazyLock<ProcessInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    ProcessInstruments {
+        spawned: meter
+            .u64_counter("tokio.process.spawned")
+            .with_description("The number of child processes spawned")
+            .with_unit("{process}")
+            .build(),
+        spawn_errors: meter
+            .u64_counter("tokio.process.spawn_errors")
+            .with_description("The number of child processes which failed to spawn")
+            .with_unit("{error}")
+            .build(),
+        running: meter
+            .i64_up_down_counter("tokio.process.running")
+            .with_description("The number of child processes currently running")
+            .with_unit("{process}")
+            .build(),
+        wait_duration: meter
+            .f64_histogram("tokio.process.wait_duration")
+            .with_description(
+                "The time between spawning a child process and its exit being observed",
+            )
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// A [`Command`] recording the child processes it spawns.
+///
+/// See the [module documentation](self) for the metrics recorded.
+#[derive(Debug)]
+pub struct InstrumentedCommand {
+    inner: Command,
+    labels: Vec<KeyValue>,
+}
+
+impl InstrumentedCommand {
+    /// Create a new command running the given program, instrumented under the
+    /// given name.
+    ///
+    /// See [`Command::new`].
+    pub fn new(name: impl Into<Value>, program: impl AsRef<OsStr>) -> Self {
+        Self::from_command(name, Command::new(program))
+    }
+
+    /// Instrument an existing [`Command`] under the given name.
+    #[must_use]
+    pub fn from_command(name: impl Into<Value>, command: Command) -> Self {
+        Self {
+            inner: command,
+            labels: vec![KeyValue::new(
+                Key::from_static_str("tokio.process.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Add an argument to pass to the program.
+    ///
+    /// See [`Command::arg`].
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// Add multiple arguments to pass to the program.
+    ///
+    /// See [`Command::args`].
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    /// Set an environment variable for the program.
+    ///
+    /// See [`Command::env`].
+    pub fn env(&mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> &mut Self {
+        self.inner.env(key, value);
+        self
+    }
+
+    /// Set the working directory of the program.
+    ///
+    /// See [`Command::current_dir`].
+    pub fn current_dir(&mut self, dir: impl AsRef<Path>) -> &mut Self {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// Set the configuration of the standard input of the child process.
+    ///
+    /// See [`Command::stdin`].
+    pub fn stdin(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+        self.inner.stdin(cfg);
+        self
+    }
+
+    /// Set the configuration of the standard output of the child process.
+    ///
+    /// See [`Command::stdout`].
+    pub fn stdout(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+        self.inner.stdout(cfg);
+        self
+    }
+
+    /// Set the configuration of the standard error of the child process.
+    ///
+    /// See [`Command::stderr`].
+    pub fn stderr(&mut self, cfg: impl Into<Stdio>) -> &mut Self {
+        self.inner.stderr(cfg);
+        self
+    }
+
+    /// Kill the child process when its [`InstrumentedChild`] is dropped.
+    ///
+    /// See [`Command::kill_on_drop`].
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Self {
+        self.inner.kill_on_drop(kill_on_drop);
+        self
+    }
+
+    /// Get a mutable reference to the underlying [`Command`], to set the
+    /// options not exposed by this wrapper.
+    pub fn get_mut(&mut self) -> &mut Command {
+        &mut self.inner
+    }
+
+    /// Spawn the command as a child process, recording it.
+    ///
+    /// See [`Command::spawn`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child process could not be spawned.
+    pub fn spawn(&mut self) -> io::Result<InstrumentedChild> {
+        let instruments = &*PROCESS_INSTRUMENTS;
+        match self.inner.spawn() {
+            Ok(child) => {
+                instruments.spawned.add(1, &self.labels);
+                Ok(InstrumentedChild {
+                    inner: child,
+                    running: RunningChild::new(self.labels.clone()),
+                })
+            }
+            Err(error) => {
+                let mut labels = self.labels.clone();
+                labels.push(crate::io_error_type(&error));
+                instruments.spawn_errors.add(1, &labels);
+                Err(error)
+            }
+        }
+    }
+
+    /// Spawn the command as a child process, and wait for it to exit.
+    ///
+    /// See [`Command::status`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child process could not be spawned, or if
+    /// waiting for it failed.
+    pub async fn status(&mut self) -> io::Result<ExitStatus> {
+        self.spawn()?.wait().await
+    }
+}
+
+/// Counts a child process as running until its exit is observed, or its
+/// handle is dropped.
+#[derive(Debug)]
+struct RunningChild {
+    labels: Vec<KeyValue>,
+    spawned_at: Instant,
+    exited: bool,
+}
+
+impl RunningChild {
+    fn new(labels: Vec<KeyValue>) -> Self {
+        PROCESS_INSTRUMENTS.running.add(1, &labels);
+        Self {
+            labels,
+            spawned_at: Instant::now(),
+            exited: false,
+        }
+    }
+
+    /// Record the outcome of waiting for the child process.
+    fn record_wait(&mut self, result: Result<ExitStatus, &io::Error>) {
+        if self.exited {
+            return;
+        }
+        self.exited = true;
+
+        let mut labels = self.labels.clone();
+        match result {
+            Ok(status) => {
+                if let Some(code) = status.code() {
+                    labels.push(KeyValue::new(
+                        Key::from_static_str("process.exit.code"),
+                        i64::from(code),
+                    ));
+                }
+            }
+            Err(error) => labels.push(crate::io_error_type(error)),
+        }
+
+        let instruments = &*PROCESS_INSTRUMENTS;
+        instruments.running.add(-1, &self.labels);
+        instruments
+            .wait_duration
+            .record(self.spawned_at.elapsed().as_secs_f64(), &labels);
+    }
+}
+
+impl Drop for RunningChild {
+    fn drop(&mut self) {
+        if !self.exited {
+            PROCESS_INSTRUMENTS.running.add(-1, &self.labels);
+        }
+    }
+}
+
+/// A [`Child`] spawned by an [`InstrumentedCommand`], recording when it
+/// exits.
+#[derive(Debug)]
+pub struct InstrumentedChild {
+    inner: Child,
+    running: RunningChild,
+}
+
+impl InstrumentedChild {
+    /// Returns the OS-assigned process identifier of the child, if it is
+    /// still running.
+    ///
+    /// See [`Child::id`].
+    #[must_use]
+    pub fn id(&self) -> Option<u32> {
+        self.inner.id()
+    }
+
+    /// Wait for the child process to exit, recording it.
+    ///
+    /// See [`Child::wait`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if waiting for the child process failed.
+    pub async fn wait(&mut self) -> io::Result<ExitStatus> {
+        let result = self.inner.wait().await;
+        self.running.record_wait(result.as_ref().copied());
+        result
+    }
+
+    /// Check whether the child process exited, without waiting, recording it
+    /// if it did.
+    ///
+    /// See [`Child::try_wait`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if checking the child process failed.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        let result = self.inner.try_wait();
+        match &result {
+            Ok(Some(status)) => self.running.record_wait(Ok(*status)),
+            Ok(None) => {}
+            Err(error) => self.running.record_wait(Err(error)),
+        }
+        result
+    }
+
+    /// Kill the child process, and wait for it to exit.
+    ///
+    /// See [`Child::kill`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if killing or waiting for the child process failed.
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.start_kill()?;
+        self.wait().await.map(drop)
+    }
+
+    /// Ask the child process to exit, without waiting for it.
+    ///
+    /// See [`Child::start_kill`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the child process could not be signalled.
+    pub fn start_kill(&mut self) -> io::Result<()> {
+        self.inner.start_kill()
+    }
+
+    /// Wait for the child process to exit, collecting its output, and
+    /// recording its exit.
+    ///
+    /// See [`Child::wait_with_output`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if waiting for the child process or reading its
+    /// output failed.
+    pub async fn wait_with_output(self) -> io::Result<Output> {
+        let Self { inner, mut running } = self;
+        let result = inner.wait_with_output().await;
+        running.record_wait(result.as_ref().map(|output| output.status));
+        result
+    }
+
+    /// Get a reference to the underlying [`Child`].
+    #[must_use]
+    pub fn get_ref(&self) -> &Child {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying [`Child`], e.g. to take its
+    /// standard input or output handles.
+    ///
+    /// Waiting for the child process directly through it is not recorded.
+    pub fn get_mut(&mut self) -> &mut Child {
+        &mut self.inner
+    }
+}
@@ -0,0 +1,76 @@
+//! CPU profiles captured when an alert is raised, see
+//! [`Config::with_anomaly_profiling`](crate::Config::with_anomaly_profiling).
+//!
+//! The profile is recorded with `pprof` on a thread of its own, so that the
+//! background sampler raising the alert keeps checking the other alerts in
+//! the meantime, and is emitted through the logger provider once done.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use opentelemetry::{Key, KeyValue};
+use pprof::protos::Message;
+
+use crate::logs::AlertLogger;
+
+/// The longest profile recorded, which bounds the profiling overhead.
+pub(crate) const MAX_PROFILE_DURATION: Duration = Duration::from_secs(30);
+
+/// The sampling frequency of the profiler, in hertz. Not a round number, so
+/// that the samples don't line up with periodic work.
+const FREQUENCY: i32 = 99;
+
+/// Whether a profile is being recorded. Only one profiler can run at a time in
+/// a process, so the alerts raised in the meantime don't get a profile.
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+/// Record a CPU profile for the given duration on a new thread, and emit it
+/// through the logger with the attributes of the alert, unless a profile is
+/// already being recorded.
+pub(crate) fn capture(logger: AlertLogger, duration: Duration, mut attributes: Vec<KeyValue>) {
+    if PROFILING.swap(true, Ordering::AcqRel) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("a CPU profile is already being recorded, not profiling this alert");
+        return;
+    }
+
+    let spawned = thread::Builder::new()
+        .name("otel-tokio-profile".to_owned())
+        .spawn(move || {
+            let profile = record(duration);
+            PROFILING.store(false, Ordering::Release);
+
+            match profile {
+                Ok(profile) => {
+                    attributes.push(KeyValue::new(
+                        Key::from_static_str("tokio.profile.duration"),
+                        duration.as_secs_f64(),
+                    ));
+                    logger.profile_recorded(&attributes, profile);
+                }
+                Err(error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%error, "failed to record a CPU profile for the alert");
+                    #[cfg(not(feature = "tracing"))]
+                    let _ = error;
+                }
+            }
+        });
+
+    if spawned.is_err() {
+        PROFILING.store(false, Ordering::Release);
+    }
+}
+
+/// Record a CPU profile of the whole process, encoded in the pprof format.
+fn record(duration: Duration) -> Result<Vec<u8>, pprof::Error> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(FREQUENCY)
+        // Unwinding through these libraries can crash the profiler
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()?;
+    thread::sleep(duration);
+    let profile = guard.report().build()?.pprof()?;
+    Ok(profile.encode_to_vec())
+}
@@ -0,0 +1,158 @@
+//! Instrumentation for custom queues and channels.
+//!
+//! Applications with their own queue or channel types can implement
+//! [`InstrumentedQueue`] for them, and [`register`] them to have their depth
+//! collected alongside the runtime metrics, under the same meter:
+//!
+//! - `tokio.queue.depth` - Items currently in the queue
+//! - `tokio.queue.capacity` - Maximum number of items the queue can hold, for
+//!   bounded queues
+//! - `tokio.queue.utilization` - Fraction of the capacity of the queue in use,
+//!   between 0 and 1, for bounded queues
+//!
+//! Like the runtime metrics, those are observed when the metrics are
+//! collected, and labelled with the name given on registration
+//! (`tokio.queue.name`). Queues are only held weakly, and stop being reported
+//! once dropped.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::collections::VecDeque;
+//! use std::sync::{Arc, Mutex};
+//!
+//! use opentelemetry_instrumentation_tokio::queue::{self, InstrumentedQueue};
+//!
+//! struct JobQueue(Mutex<VecDeque<String>>);
+//!
+//! impl InstrumentedQueue for JobQueue {
+//!     fn len(&self) -> usize {
+//!         self.0.lock().unwrap().len()
+//!     }
+//! }
+//!
+//! let jobs = Arc::new(JobQueue(Mutex::new(VecDeque::new())));
+//! queue::register("jobs", &jobs);
+//! ```
+
+use std::sync::{Arc, Once, RwLock, RwLockReadGuard, Weak};
+
+use opentelemetry::metrics::Meter;
+use opentelemetry::{Key, KeyValue, Value};
+
+/// A queue or channel whose depth can be collected in the metrics.
+pub trait InstrumentedQueue: Send + Sync {
+    /// Returns the number of items currently in the queue.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if there are no items in the queue.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of items the queue can hold, or `None` if
+    /// it is unbounded.
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// A registered queue, with its labels.
+struct RegisteredQueue {
+    queue: Weak<dyn InstrumentedQueue>,
+    labels: [KeyValue; 1],
+}
+
+/// All the registered queues.
+static QUEUES: RwLock<Vec<RegisteredQueue>> = RwLock::new(Vec::new());
+
+/// Get a read guard on the registered queues.
+fn read_queues() -> RwLockReadGuard<'static, Vec<RegisteredQueue>> {
+    crate::recover_lock(QUEUES.read(), "registered queues")
+}
+
+/// Register a queue under the given name, to collect its depth in the
+/// metrics.
+///
+/// The queue is held weakly, and stops being reported once all the strong
+/// references to it are dropped.
+pub fn register<Q>(name: impl Into<Value>, queue: &Arc<Q>)
+where
+    Q: InstrumentedQueue + 'static,
+{
+    static INSTRUMENTS: Once = Once::new();
+    INSTRUMENTS.call_once(|| register_instruments(&crate::meter()));
+
+    let queue: Arc<dyn InstrumentedQueue> = queue.clone();
+    let mut queues = crate::recover_lock(QUEUES.write(), "registered queues");
+    // Clean up the queues which were dropped since the last registration
+    queues.retain(|registered| registered.queue.strong_count() > 0);
+    queues.push(RegisteredQueue {
+        queue: Arc::downgrade(&queue),
+        labels: [KeyValue::new(
+            Key::from_static_str("tokio.queue.name"),
+            name.into(),
+        )],
+    });
+}
+
+/// Call the given function with each live registered queue and its labels.
+fn for_each_queue(mut f: impl FnMut(&dyn InstrumentedQueue, &[KeyValue])) {
+    for registered in read_queues().iter() {
+        if let Some(queue) = registered.queue.upgrade() {
+            f(&*queue, &registered.labels);
+        }
+    }
+}
+
+fn register_instruments(meter: &Meter) {
+    meter
+        .u64_observable_gauge("tokio.queue.depth")
+        .with_description("The number of items currently in the queue")
+        .with_unit("{item}")
+        .with_callback(|instrument| {
+            for_each_queue(|queue, labels| {
+                instrument.observe(
+                    queue
+                        .len()
+                        .try_into()
+                        .unwrap_or_else(|_| crate::overflowed("tokio.queue.depth", u64::MAX)),
+                    labels,
+                );
+            });
+        })
+        .build();
+
+    meter
+        .u64_observable_gauge("tokio.queue.capacity")
+        .with_description("The maximum number of items the queue can hold")
+        .with_unit("{item}")
+        .with_callback(|instrument| {
+            for_each_queue(|queue, labels| {
+                if let Some(capacity) = queue.capacity() {
+                    instrument.observe(
+                        capacity.try_into().unwrap_or_else(|_| {
+                            crate::overflowed("tokio.queue.capacity", u64::MAX)
+                        }),
+                        labels,
+                    );
+                }
+            });
+        })
+        .build();
+
+    meter
+        .f64_observable_gauge("tokio.queue.utilization")
+        .with_description("The fraction of the capacity of the queue in use")
+        .with_unit("1")
+        .with_callback(|instrument| {
+            for_each_queue(|queue, labels| {
+                if let Some(capacity) = queue.capacity().filter(|capacity| *capacity > 0) {
+                    #[expect(clippy::cast_precision_loss)]
+                    let utilization = queue.len() as f64 / capacity as f64;
+                    instrument.observe(utilization, labels);
+                }
+            });
+        })
+        .build();
+}
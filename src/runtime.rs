@@ -4,178 +4,1652 @@
 //! metrics. Each metric is implemented as a separate function for clarity and
 //! maintainability.
 
-use std::sync::{Once, RwLock};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Once, RwLock};
 
-use opentelemetry::metrics::Meter;
-use opentelemetry::{InstrumentationScope, Key, KeyValue};
+use opentelemetry::baggage::BaggageExt;
+use opentelemetry::metrics::{AsyncInstrument, Meter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::runtime::RuntimeFlavor;
 
-/// One-time instrument initialization.
-static INSTRUMENTS_INITIALIZED: Once = Once::new();
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use crate::DurationUnit;
+use crate::{Config, MetricSet, ObserveError};
+
+mod alert;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod builder;
+mod cache;
+#[cfg(feature = "worker-metrics")]
+mod change_detection;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod pool;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod probe;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+mod sampler;
+mod snapshot;
+#[cfg(all(
+    feature = "tracing",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+mod summary;
+
+pub use self::alert::{Alert, Anomaly};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use self::builder::RuntimeBuilderExt;
+pub use self::snapshot::{RuntimeSnapshot, WorkerSnapshot, report, snapshots};
+
+/// The instruments registered with the global meter provider.
+static GLOBAL_INSTRUMENTS: RegisteredInstruments = RegisteredInstruments::new();
+
+/// The metrics which are only reported with `--cfg tokio_unstable`, listed in
+/// the diagnostics when they are skipped.
+#[cfg(all(feature = "tracing", not(tokio_unstable)))]
+const UNSTABLE_METRICS: &[&str] = &[
+    "tokio.blocking_threads",
+    "tokio.idle_blocking_threads",
+    "tokio.remote_schedules",
+    "tokio.budget_forced_yields",
+    "tokio.io_driver.fd_registrations",
+    "tokio.io_driver.fd_deregistrations",
+    "tokio.io_driver.fd_readies",
+    "tokio.spawned_tasks_count",
+    "tokio.blocking_queue_depth",
+    "tokio.worker.noops",
+    "tokio.worker.task_steals",
+    "tokio.worker.steal_operations",
+    "tokio.worker.polls",
+    "tokio.worker.local_schedules",
+    "tokio.worker.overflows",
+    "tokio.worker.local_queue_depth",
+    "tokio.worker.local_queue_saturation",
+    "tokio.worker.mean_poll_time",
+    "tokio.worker.poll_time_bucket",
+    "tokio.worker.poll_time_count",
+    "tokio.worker.poll_time_sum",
+];
+
+/// One-time initialization of the instruments registered with a meter
+/// provider, so that each of them reports the runtimes only once.
+pub(crate) struct RegisteredInstruments {
+    all: Once,
+
+    // The instruments which have a variant selected through the `Config`,
+    // only registered once a runtime uses them
+    alive_tasks_gauge: Once,
+    alive_tasks_up_down_counter: Once,
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "worker-metrics"
+    ))]
+    busy_duration: [Once; 3],
+    #[cfg(all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "worker-metrics"
+    ))]
+    mean_poll_time: [Once; 3],
+}
+
+impl RegisteredInstruments {
+    pub(crate) const fn new() -> Self {
+        Self {
+            all: Once::new(),
+            alive_tasks_gauge: Once::new(),
+            alive_tasks_up_down_counter: Once::new(),
+            #[cfg(all(
+                target_has_atomic = "64",
+                not(all(target_arch = "wasm32", target_os = "unknown")),
+                feature = "worker-metrics"
+            ))]
+            busy_duration: [const { Once::new() }; 3],
+            #[cfg(all(
+                tokio_unstable,
+                not(all(target_arch = "wasm32", target_os = "unknown")),
+                feature = "worker-metrics"
+            ))]
+            mean_poll_time: [const { Once::new() }; 3],
+        }
+    }
+
+    /// Register the instruments needed by the given configuration with the
+    /// meter returned by `meter`, unless they were already registered,
+    /// returning whether this registered the instruments shared by all the
+    /// configurations.
+    fn register(&self, meter: impl Fn() -> Meter, config: &Config) -> bool {
+        let mut registered = false;
+        self.all.call_once(|| {
+            register_all_instruments(&meter());
+            registered = true;
+        });
+
+        if config.alive_tasks_as_up_down_counter {
+            self.alive_tasks_up_down_counter
+                .call_once(|| register_alive_tasks_up_down_counter(&meter()));
+        } else {
+            self.alive_tasks_gauge
+                .call_once(|| register_alive_tasks_gauge(&meter()));
+        }
+
+        #[cfg(all(
+            target_has_atomic = "64",
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "worker-metrics"
+        ))]
+        {
+            let unit = busy_duration_unit(config);
+            self.busy_duration[unit as usize]
+                .call_once(|| register_worker_busy_duration_counter(&meter(), unit));
+        }
+
+        #[cfg(all(
+            tokio_unstable,
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "worker-metrics"
+        ))]
+        {
+            let unit = mean_poll_time_unit(config);
+            self.mean_poll_time[unit as usize]
+                .call_once(|| register_worker_mean_poll_time_gauge(&meter(), unit));
+        }
+
+        registered
+    }
+}
 
 /// Registry of all observed runtimes.
-static RUNTIMES: RwLock<Vec<TrackedRuntime>> = RwLock::new(Vec::new());
+static RUNTIMES: RwLock<Vec<Arc<TrackedRuntime>>> = RwLock::new(Vec::new());
+
+/// Incremented on each change of [`RUNTIMES`], to tell when the snapshots of
+/// the registry taken by the collecting threads are outdated.
+static RUNTIMES_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of threads observing the tracked runtimes on collection, see
+/// [`Config::with_collection_threads`].
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+static COLLECTION_THREADS: AtomicUsize = AtomicUsize::new(1);
+
+/// A snapshot of the registry of tracked runtimes.
+type RuntimesSnapshot = Arc<[Arc<TrackedRuntime>]>;
+
+thread_local! {
+    /// The snapshot of the registry last taken by this thread, with the
+    /// generation it was taken at.
+    ///
+    /// The instruments are collected one after the other on the same thread,
+    /// so they share a single snapshot instead of each locking the registry.
+    static RUNTIMES_SNAPSHOT: RefCell<(usize, RuntimesSnapshot)> =
+        RefCell::new((0, Arc::new([])));
+}
+
+/// The instances of the runtimes in the registry with each set of labels, see
+/// [`register_label_set`].
+///
+/// Label sets are removed once no runtime in the registry uses them anymore,
+/// so that services creating many short-lived runtimes don't grow it forever.
+static LABEL_SETS: LazyLock<Mutex<HashMap<String, BTreeSet<u64>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 /// A tracked runtime with its metrics and labels.
 struct TrackedRuntime {
-    metrics: tokio::runtime::RuntimeMetrics,
-    labels: Vec<KeyValue>,
+    id: tokio::runtime::Id,
+    metrics: cache::CachedMetrics,
+    labels: Arc<[KeyValue]>,
+
+    // The set of labels of this runtime and its instance in it, released once
+    // the runtime is removed from the registry or evicted
+    label_set: LabelSet,
+
+    // The labels computed on each collection, added to the pre-computed ones
+    dynamic_labels: Option<crate::DynamicLabels>,
+
+    // The set of metrics exported for this runtime
+    metric_set: MetricSet,
+
+    // Whether `tokio.alive_tasks` is reported through the up-down counter instead of the gauge
+    alive_tasks_as_up_down_counter: bool,
+
+    // The unit in which `tokio.worker.busy_duration` is reported
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    busy_duration_unit: DurationUnit,
+
+    // The unit in which `tokio.worker.mean_poll_time` is reported
+    #[cfg(all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "worker-metrics"
+    ))]
+    mean_poll_time_unit: DurationUnit,
+
+    // Whether this is a current_thread runtime. Those have a single worker, and never steal
+    // tasks, so we skip the worker attribute and work stealing metrics
+    #[cfg(tokio_unstable)]
+    #[cfg_attr(
+        all(
+            target_arch = "wasm32",
+            target_os = "unknown",
+            not(feature = "worker-metrics")
+        ),
+        expect(dead_code)
+    )]
+    is_current_thread: bool,
+
+    // The time and busy duration of each worker as of the registration, and
+    // as of the last collection of each callback of `tokio.worker.utilization`
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "worker-metrics"
+    ))]
+    registered_busy_durations: BusyDurations,
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "worker-metrics"
+    ))]
+    last_busy_durations: Mutex<HashMap<usize, BusyDurations>>,
 
     // Pre-computed labels for each worker. This assumes the # of workers never change in Tokio,
     // which I think is the case?
-    workers_labels: Vec<Vec<KeyValue>>,
+    workers_labels: Vec<Arc<[KeyValue]>>,
 
     // Pre-computed labels for each bucket in the poll time histogram, for each worker
-    #[cfg(tokio_unstable)]
-    histogram_bucket_labels: Vec<Vec<Vec<KeyValue>>>,
+    #[cfg(all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "histograms"
+    ))]
+    histogram_bucket_labels: Vec<Vec<Arc<[KeyValue]>>>,
+
+    // The value each bucket of the poll time histogram contributes to
+    // `tokio.worker.poll_time_sum`, in the unit of the `le` labels
+    #[cfg(all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "histograms"
+    ))]
+    histogram_bucket_values: Vec<f64>,
+
+    // The last observed values of the per-worker counters, when unchanged
+    // observations are skipped
+    #[cfg(feature = "worker-metrics")]
+    change_detection: Option<change_detection::ChangeDetection>,
+
+    // Background sampler recording some of the metrics, if any
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    sampler: Option<sampler::RuntimeSampler>,
+
+    // Whether collecting the metrics of this runtime panicked, in which case
+    // it is skipped, and removed from the registry on the next registration
+    evicted: std::sync::atomic::AtomicBool,
+
+    // Whether this runtime was removed from the registry, after shutting down
+    // or being unobserved
+    removed: std::sync::atomic::AtomicBool,
+
+    // Wakes the task watching for the shutdown of the runtime once it is
+    // removed, see `watch_shutdown`
+    shutdown_waker: Mutex<Option<std::task::Waker>>,
+}
+
+// The per-worker state is only read by the per-worker metrics on
+// wasm32-unknown-unknown, where there is no background sampler
+#[cfg_attr(
+    all(
+        target_arch = "wasm32",
+        target_os = "unknown",
+        not(feature = "worker-metrics")
+    ),
+    expect(dead_code)
+)]
+impl TrackedRuntime {
+    /// Whether this runtime was evicted after a panic while collecting its
+    /// metrics.
+    fn is_evicted(&self) -> bool {
+        self.evicted.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Run a collection step for this runtime, catching panics.
+    ///
+    /// A panic (e.g. from Tokio on a torn-down runtime) would otherwise
+    /// propagate into the exporter, so the runtime gets evicted instead and
+    /// the panic is reported to the error handler.
+    fn collect<R>(&self, metric: &'static str, f: impl FnOnce() -> R) -> Option<R> {
+        if self.is_evicted() {
+            return None;
+        }
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        if result.is_err() {
+            self.evicted
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+            self.label_set.release();
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                runtime = %describe_runtime(&self.labels),
+                metric,
+                "evicted the tokio runtime after a panic while collecting its metrics",
+            );
+            crate::report_error(&crate::CollectionError::Panicked { metric });
+        }
+        result.ok()
+    }
+
+    /// The labels computed for this collection, see
+    /// [`Config::with_dynamic_labels`].
+    fn dynamic_labels(&self) -> Vec<KeyValue> {
+        self.dynamic_labels
+            .as_ref()
+            .map(crate::DynamicLabels::get)
+            .unwrap_or_default()
+    }
+
+    /// Whether the metrics of the given set are exported for this runtime.
+    fn exports(&self, metric_set: MetricSet) -> bool {
+        self.metric_set >= metric_set
+    }
+
+    /// The labels of each series of the per-worker metrics, which are only
+    /// exported from [`MetricSet::Standard`].
+    ///
+    /// There is one series per worker, unless capped with
+    /// [`Config::with_max_worker_series`], in which case the last series is
+    /// shared by the remaining workers.
+    fn worker_series(&self) -> &[Arc<[KeyValue]>] {
+        if self.exports(MetricSet::Standard) {
+            &self.workers_labels
+        } else {
+            &[]
+        }
+    }
+
+    /// The labels of the series a worker is reported under, if any.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    fn worker_labels(&self, worker_idx: usize) -> Option<&[KeyValue]> {
+        let series = self.worker_series();
+        let last_series = series.len().checked_sub(1)?;
+        Some(&series[worker_idx.min(last_series)])
+    }
+
+    /// Observe a per-worker value, for each worker series in order.
+    ///
+    /// The values of the workers sharing the last series when capped are
+    /// combined with `combine`.
+    #[cfg(any(
+        feature = "worker-metrics",
+        all(
+            tokio_unstable,
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "histograms"
+        )
+    ))]
+    fn observe_workers<T: Copy>(
+        &self,
+        read: impl Fn(usize) -> T,
+        combine: impl Fn(T, T) -> T,
+        mut observe: impl FnMut(T, &[KeyValue]),
+    ) {
+        let series = self.worker_series();
+        let num_workers = self.metrics.num_workers();
+        if series.len() == num_workers {
+            for (worker_idx, labels) in series.iter().enumerate() {
+                observe(read(worker_idx), labels);
+            }
+            return;
+        }
+
+        let Some((other_labels, individual)) = series.split_last() else {
+            return;
+        };
+
+        for (worker_idx, labels) in individual.iter().enumerate() {
+            observe(read(worker_idx), labels);
+        }
+
+        let other = (individual.len()..num_workers).map(read).reduce(combine);
+        if let Some(other) = other {
+            observe(other, other_labels);
+        }
+    }
+
+    /// Observe a per-worker counter like [`Self::observe_workers`], skipping
+    /// the series which didn't change since the previous collection if
+    /// enabled through [`Config::with_change_detection`].
+    #[cfg(feature = "worker-metrics")]
+    fn observe_changed_workers<T: change_detection::Observation>(
+        &self,
+        metric: &'static str,
+        read: impl Fn(usize) -> T,
+        combine: impl Fn(T, T) -> T,
+        mut observe: impl FnMut(T, &[KeyValue]),
+    ) {
+        let Some(change_detection) = &self.change_detection else {
+            self.observe_workers(read, combine, observe);
+            return;
+        };
+
+        change_detection.collect(metric, |filter| {
+            let mut series_idx = 0;
+            self.observe_workers(read, combine, |value, labels| {
+                if filter.forward(series_idx, value) {
+                    observe(value, labels);
+                }
+                series_idx += 1;
+            });
+        });
+    }
+
+    /// Whether this tracks the runtime of the given handle.
+    fn is_runtime(&self, handle: &tokio::runtime::Handle) -> bool {
+        self.id == handle.id()
+    }
+
+    /// Whether the counters of this runtime are recorded by the background
+    /// sampler, in which case the observable counters skip it.
+    #[cfg(any(tokio_unstable, feature = "worker-metrics"))]
+    #[cfg_attr(
+        all(target_arch = "wasm32", target_os = "unknown"),
+        expect(clippy::unused_self)
+    )]
+    fn counters_sampled(&self) -> bool {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        return self
+            .sampler
+            .as_ref()
+            .is_some_and(sampler::RuntimeSampler::samples_counters);
+
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        false
+    }
 }
 
 /// Track a Tokio runtime for metrics collection.
 ///
 /// This also initializes the instruments on the first call.
-pub(crate) fn track_runtime(handle: &tokio::runtime::Handle, labels: &[KeyValue]) {
-    // Ensure instruments are initialized (one-time, thread-safe).
-    INSTRUMENTS_INITIALIZED.call_once(|| {
-        register_all_instruments();
-    });
+pub(crate) fn track_runtime(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+) -> Result<ObservationGuard, ObserveError> {
+    let mut guards = track_runtimes([(handle, Vec::new())], config)?;
+    Ok(guards.remove(0))
+}
 
-    let labels = build_runtime_labels(handle, labels);
+/// Track several Tokio runtimes sharing the same configuration, each with
+/// its own additional labels, adding them to the registry at once.
+///
+/// None of them are added if one of them can't be, e.g. when its labels
+/// collide with the labels of another runtime in strict mode.
+pub(crate) fn track_runtimes<'a>(
+    runtimes: impl IntoIterator<Item = (&'a tokio::runtime::Handle, Vec<KeyValue>)>,
+    config: &Config,
+) -> Result<Vec<ObservationGuard>, ObserveError> {
+    register_instruments(config)?;
 
-    let workers_labels: Vec<Vec<_>> = (0..handle.metrics().num_workers())
-        .map(|i| {
-            let mut worker_labels = labels.clone();
-            worker_labels.push(worker_idx_attribute(i));
-            worker_labels
-        })
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    if let Some(threads) = config.collection_threads {
+        COLLECTION_THREADS.fetch_max(threads, Ordering::Relaxed);
+    }
+
+    // The labels only depend on the configuration and the current context,
+    // so they are computed before taking the registry lock
+    let candidates: Vec<_> = runtimes
+        .into_iter()
+        .map(|(handle, labels)| (handle, base_runtime_labels(handle, config, labels)))
         .collect();
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    #[cfg(tokio_unstable)]
-    let histogram_bucket_labels = 'result: {
-        if !handle.metrics().poll_time_histogram_enabled() {
-            // Don't collect histogram if not enabled
-            //
-            break 'result Vec::new();
+    let mut added: Vec<(_, Arc<TrackedRuntime>)> = Vec::with_capacity(candidates.len());
+    {
+        // Poisoned only if a registration panicked half-way, in which case
+        // the registry can't be trusted anymore
+        let mut runtimes = RUNTIMES
+            .write()
+            .map_err(|_| ObserveError::RegistryUnavailable)?;
+        runtimes.retain(|runtime| !runtime.is_evicted());
+
+        // Check all the runtimes before building any of them, as building a
+        // runtime registers its set of labels, which a rejected registration
+        // shouldn't do
+        for (index, (handle, labels)) in candidates.iter().enumerate() {
+            let earlier = &candidates[..index];
+            if !config.duplicate_registrations
+                && (runtimes.iter().any(|runtime| runtime.is_runtime(handle))
+                    || earlier.iter().any(|(other, _)| same_runtime(other, handle)))
+            {
+                return Err(ObserveError::DuplicateRegistration);
+            }
+
+            if config.strict_labels
+                && (runtimes
+                    .iter()
+                    .any(|runtime| same_labels(&runtime.labels, labels))
+                    || earlier.iter().any(|(_, other)| same_labels(other, labels)))
+            {
+                return Err(ObserveError::LabelCollision {
+                    labels: labels.clone(),
+                });
+            }
         }
 
-        let num_buckets = handle.metrics().poll_time_histogram_num_buckets();
-        let mut buckets_label: Vec<_> = (0..num_buckets)
-            .map(|bucket_idx| {
-                let range = handle
-                    .metrics()
-                    .poll_time_histogram_bucket_range(bucket_idx);
-                let value = range.end.as_nanos().try_into().unwrap_or(i64::MAX);
-                KeyValue::new("le", value)
-            })
-            .collect();
+        for (handle, labels) in candidates {
+            let tracked_runtime = build_tracked_runtime(handle, config, labels);
+            added.push((handle, Arc::new(tracked_runtime)));
+        }
+
+        runtimes.extend(added.iter().map(|(_, runtime)| runtime.clone()));
+        RUNTIMES_GENERATION.fetch_add(1, Ordering::Release);
+    }
+
+    for (handle, runtime) in &added {
+        watch_shutdown(handle, runtime);
+    }
+
+    #[cfg(feature = "tracing")]
+    for (handle, runtime) in &added {
+        tracing::info!(
+            runtime = %describe_runtime(&runtime.labels),
+            flavor = ?handle.runtime_flavor(),
+            metric_set = ?runtime.metric_set,
+            "tokio runtime observed",
+        );
+    }
+
+    #[cfg(feature = "logs")]
+    if let Some(provider) = &config.logger_provider {
+        for (handle, runtime) in &added {
+            crate::logs::runtime_observed(provider, handle, &runtime.labels);
+        }
+    }
+
+    #[cfg(all(
+        feature = "time",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    if let Some(interval) = config.event_loop_lag_interval {
+        for (handle, runtime) in &added {
+            if handle.runtime_flavor() == RuntimeFlavor::CurrentThread {
+                probe::monitor_event_loop(handle, runtime, interval);
+            }
+        }
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    let start_sampler = added.iter().any(|(_, runtime)| runtime.sampler.is_some());
+
+    // Start the sampler once the runtimes are in the registry, so that it
+    // doesn't miss them
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    if start_sampler {
+        sampler::start();
+    }
+
+    Ok(added
+        .iter()
+        .map(|(_, runtime)| ObservationGuard {
+            runtime: Arc::downgrade(runtime),
+        })
+        .collect())
+}
+
+/// Register the instruments needed by the given configuration, if not done
+/// already.
+fn register_instruments(config: &Config) -> Result<(), ObserveError> {
+    // Registering the instruments with the no-op provider would bind them to
+    // it for good, so that they wouldn't report anything once the provider
+    // is set
+    if config.provider_check
+        && config.meter_providers.is_empty()
+        && !crate::meter_provider_initialized()
+    {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("the global meter provider is not set yet, not observing the runtimes");
+        return Err(ObserveError::ProviderNotInitialized);
+    }
+
+    #[cfg_attr(not(feature = "tracing"), expect(unused_variables))]
+    let registered = GLOBAL_INSTRUMENTS.register(crate::meter, config);
+    #[cfg(feature = "tracing")]
+    if registered {
+        tracing::debug!("registered the runtime instruments with the global meter provider");
+        #[cfg(not(tokio_unstable))]
+        tracing::info!(
+            skipped = ?UNSTABLE_METRICS,
+            "not built with `--cfg tokio_unstable`, skipping the metrics which require it",
+        );
+    }
+
+    for provider in &config.meter_providers {
+        #[cfg_attr(not(feature = "tracing"), expect(unused_variables))]
+        let registered = provider.instruments.register(|| provider.meter(), config);
+        #[cfg(feature = "tracing")]
+        if registered {
+            tracing::debug!("registered the runtime instruments with an additional meter provider");
+        }
+    }
+
+    Ok(())
+}
+
+/// Watch for the shutdown of a tracked runtime, removing it from the registry
+/// once it shuts down.
+///
+/// The shutdown is detected through a task which only completes once the
+/// runtime is removed, and gets dropped when the runtime shuts down before.
+/// This task is not reported in the alive tasks of the runtime.
+fn watch_shutdown(handle: &tokio::runtime::Handle, runtime: &Arc<TrackedRuntime>) {
+    /// Removes the runtime when dropped, along with the task.
+    struct ShutdownWatch(std::sync::Weak<TrackedRuntime>);
+
+    impl Drop for ShutdownWatch {
+        fn drop(&mut self) {
+            if let Some(runtime) = self.0.upgrade() {
+                runtime.metrics.remove_internal_task();
+                remove_runtime(&runtime, Removal::ShutDown);
+            }
+        }
+    }
+
+    let watch = ShutdownWatch(Arc::downgrade(runtime));
+    runtime.metrics.add_internal_task();
+    drop(handle.spawn(async move {
+        std::future::poll_fn(|cx| {
+            let Some(runtime) = watch.0.upgrade() else {
+                return std::task::Poll::Ready(());
+            };
+            if runtime.removed.load(Ordering::Acquire) {
+                return std::task::Poll::Ready(());
+            }
 
-        // Change the last bucket to +Inf
-        if let Some(last) = buckets_label.last_mut() {
-            *last = KeyValue::new("le", "+Inf");
+            *crate::recover_lock(runtime.shutdown_waker.lock(), "shutdown waker") =
+                Some(cx.waker().clone());
+            // The runtime may have been removed before the waker was set
+            if runtime.removed.load(Ordering::Acquire) {
+                std::task::Poll::Ready(())
+            } else {
+                std::task::Poll::Pending
+            }
+        })
+        .await;
+    }));
+}
+
+/// Why a runtime is removed from the registry, see [`remove_runtime`].
+#[derive(Debug, Clone, Copy)]
+enum Removal {
+    Unobserved,
+    ShutDown,
+}
+
+/// Remove a runtime from the registry, if it wasn't already.
+fn remove_runtime(runtime: &Arc<TrackedRuntime>, removal: Removal) {
+    if runtime.removed.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    {
+        let mut runtimes = crate::recover_lock(RUNTIMES.write(), "runtimes registry");
+        runtimes.retain(|tracked| !Arc::ptr_eq(tracked, runtime));
+        RUNTIMES_GENERATION.fetch_add(1, Ordering::Release);
+    }
+    runtime.label_set.release();
+
+    // Let the task watching for the shutdown complete
+    if let Some(waker) = crate::recover_lock(runtime.shutdown_waker.lock(), "shutdown waker").take()
+    {
+        waker.wake();
+    }
+
+    #[cfg(feature = "tracing")]
+    match removal {
+        Removal::Unobserved => tracing::info!(
+            runtime = %describe_runtime(&runtime.labels),
+            "tokio runtime unobserved",
+        ),
+        Removal::ShutDown => tracing::info!(
+            runtime = %describe_runtime(&runtime.labels),
+            "tokio runtime shut down, no longer observed",
+        ),
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = removal;
+}
+
+/// The registration of an observed runtime, returned by
+/// [`Config::observe_runtime`].
+///
+/// The runtime is observed until it shuts down, or until
+/// [`Self::unobserve`] is called: dropping the guard keeps it observed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::Config;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let guard = Config::new()
+///     .with_label("phase", "warmup")
+///     .observe_current_runtime()?;
+///
+/// // Stop reporting the runtime under the warmup labels
+/// guard.unobserve();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ObservationGuard {
+    runtime: std::sync::Weak<TrackedRuntime>,
+}
+
+impl ObservationGuard {
+    /// Stop observing the runtime.
+    ///
+    /// Its metrics are no longer reported from the next collection, and it
+    /// can be observed again.
+    pub fn unobserve(self) {
+        if let Some(runtime) = self.runtime.upgrade() {
+            remove_runtime(&runtime, Removal::Unobserved);
         }
+    }
+}
+
+/// The registry of the runtimes observed in the process, shared by all the
+/// [`Config`]s.
+///
+/// Runtimes are added to it by [`Config::observe_runtime`], or at once by
+/// [`Config::observe_runtimes`] for a pool of runtimes. Services creating many
+/// runtimes can size it beforehand, so that registering them doesn't
+/// repeatedly grow it while holding its lock.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::{Config, Registry};
+/// use tokio::runtime::Runtime;
+///
+/// let registry = Registry::with_capacity(64);
+///
+/// let runtimes: Vec<Runtime> = (0..64).map(|_| Runtime::new().unwrap()).collect();
+/// Config::new()
+///     .observe_runtimes(runtimes.iter().map(|runtime| (runtime.handle(), Vec::new())))
+///     .unwrap();
+///
+/// assert_eq!(registry.len(), 64);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Registry {
+    _private: (),
+}
 
-        workers_labels
+impl Registry {
+    /// Get the registry of the observed runtimes.
+    #[must_use]
+    pub fn global() -> Self {
+        Self { _private: () }
+    }
+
+    /// Get the registry of the observed runtimes, with capacity for at least
+    /// `capacity` runtimes in total.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut runtimes = crate::recover_lock(RUNTIMES.write(), "runtimes registry");
+        let additional = capacity.saturating_sub(runtimes.len());
+        runtimes.reserve(additional);
+        Self::global()
+    }
+
+    /// Reserve capacity for at least `additional` more runtimes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity overflows `isize::MAX` bytes.
+    pub fn reserve(&self, additional: usize) {
+        crate::recover_lock(RUNTIMES.write(), "runtimes registry").reserve(additional);
+    }
+
+    /// The number of runtimes the registry can hold without growing.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        crate::recover_lock(RUNTIMES.read(), "runtimes registry").capacity()
+    }
+
+    /// The number of runtimes observed.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        crate::recover_lock(RUNTIMES.read(), "runtimes registry")
             .iter()
-            .map(|worker_labels| {
-                buckets_label
-                    .iter()
-                    .map(|bucket_label| {
-                        let mut labels = worker_labels.clone();
-                        labels.push(bucket_label.clone());
-                        labels
-                    })
-                    .collect()
-            })
-            .collect()
+            .filter(|runtime| !runtime.is_evicted())
+            .count()
+    }
+
+    /// Whether no runtime is observed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Build the state tracked for a runtime accepted in the registry, with its
+/// pre-computed labels.
+fn build_tracked_runtime(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+    labels: Vec<KeyValue>,
+) -> TrackedRuntime {
+    let (labels, label_set) = finish_runtime_labels(labels);
+    let is_current_thread = handle.runtime_flavor() == RuntimeFlavor::CurrentThread;
+
+    let workers_labels = build_workers_labels(handle, config, &labels, is_current_thread);
+
+    #[cfg(all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "histograms"
+    ))]
+    let histogram_bucket_labels = build_histogram_bucket_labels(handle, config, &workers_labels);
+
+    #[cfg(all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "histograms"
+    ))]
+    let histogram_bucket_values = if histogram_bucket_labels.is_empty() {
+        Vec::new()
+    } else {
+        build_histogram_bucket_values(handle, config)
     };
 
-    let tracked_runtime = TrackedRuntime {
-        metrics: handle.metrics().clone(),
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    let sampler = sampler::RuntimeSampler::new(handle, config);
+
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown")),
+        feature = "worker-metrics"
+    ))]
+    let registered_busy_durations = busy_durations(&handle.metrics());
+
+    TrackedRuntime {
+        id: handle.id(),
+        metrics: cache::CachedMetrics::new(handle.metrics(), config.collection_cache_staleness),
         labels,
-        workers_labels,
+        label_set,
+        dynamic_labels: config.dynamic_labels.clone(),
+        metric_set: config.metric_set,
+        alive_tasks_as_up_down_counter: config.alive_tasks_as_up_down_counter,
+        #[cfg(all(
+            target_has_atomic = "64",
+            not(all(target_arch = "wasm32", target_os = "unknown"))
+        ))]
+        busy_duration_unit: busy_duration_unit(config),
+        #[cfg(all(
+            tokio_unstable,
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "worker-metrics"
+        ))]
+        mean_poll_time_unit: mean_poll_time_unit(config),
         #[cfg(tokio_unstable)]
+        is_current_thread,
+        #[cfg(all(
+            target_has_atomic = "64",
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "worker-metrics"
+        ))]
+        registered_busy_durations,
+        #[cfg(all(
+            target_has_atomic = "64",
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "worker-metrics"
+        ))]
+        last_busy_durations: Mutex::new(HashMap::new()),
+        workers_labels,
+        #[cfg(all(
+            tokio_unstable,
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "histograms"
+        ))]
         histogram_bucket_labels,
+        #[cfg(all(
+            tokio_unstable,
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "histograms"
+        ))]
+        histogram_bucket_values,
+        #[cfg(feature = "worker-metrics")]
+        change_detection: config
+            .change_detection_refresh
+            .map(change_detection::ChangeDetection::new),
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        sampler,
+        evicted: std::sync::atomic::AtomicBool::new(false),
+        removed: std::sync::atomic::AtomicBool::new(false),
+        shutdown_waker: Mutex::new(None),
+    }
+}
+
+/// Pre-compute the labels for each worker series.
+fn build_workers_labels(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+    labels: &[KeyValue],
+    is_current_thread: bool,
+) -> Vec<Arc<[KeyValue]>> {
+    if is_current_thread {
+        // The only worker is the runtime itself
+        return vec![labels.into()];
+    }
+
+    let num_workers = handle.metrics().num_workers();
+    let max_series = config.max_worker_series.unwrap_or(usize::MAX);
+    let mut workers_labels: Vec<_> = (0..num_workers.min(max_series))
+        .map(|i| {
+            let mut worker_labels = labels.to_vec();
+            worker_labels.push(worker_idx_attribute(i));
+            worker_labels.into()
+        })
+        .collect();
+
+    // Group the remaining workers under a single series
+    if num_workers > max_series {
+        let mut other_labels = labels.to_vec();
+        other_labels.push(KeyValue::new(
+            Key::from_static_str("tokio.worker.index"),
+            "other",
+        ));
+        workers_labels.push(other_labels.into());
+    }
+
+    workers_labels
+}
+
+/// The unit in which `tokio.worker.busy_duration` is reported.
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn busy_duration_unit(config: &Config) -> DurationUnit {
+    config.duration_unit.unwrap_or(DurationUnit::Milliseconds)
+}
+
+/// The unit in which `tokio.worker.mean_poll_time` is reported.
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "worker-metrics"
+))]
+fn mean_poll_time_unit(config: &Config) -> DurationUnit {
+    if config.mean_poll_time_as_seconds {
+        DurationUnit::Seconds
+    } else {
+        config.duration_unit.unwrap_or(DurationUnit::Nanoseconds)
+    }
+}
+
+/// The unit of the bounds of the poll time histogram buckets.
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "histograms"
+))]
+fn poll_time_bucket_unit(config: &Config) -> DurationUnit {
+    if config.poll_time_bucket_bounds_as_seconds {
+        DurationUnit::Seconds
+    } else {
+        config.duration_unit.unwrap_or(DurationUnit::Nanoseconds)
+    }
+}
+
+/// Convert a duration to a whole number of the given unit.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn duration_as_u64(duration: std::time::Duration, unit: DurationUnit, metric: &'static str) -> u64 {
+    let value = match unit {
+        DurationUnit::Nanoseconds => duration.as_nanos(),
+        DurationUnit::Milliseconds => duration.as_millis(),
+        DurationUnit::Seconds => duration.as_secs().into(),
+    };
+    value
+        .try_into()
+        .unwrap_or_else(|_| crate::overflowed(metric, u64::MAX))
+}
+
+/// Convert a duration to a fractional number of the given unit.
+#[cfg(all(
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    any(
+        feature = "worker-metrics",
+        all(tokio_unstable, feature = "histograms")
+    )
+))]
+fn duration_as_f64(duration: std::time::Duration, unit: DurationUnit) -> f64 {
+    // Durations measured by Tokio are far below 2^52 nanoseconds
+    #[expect(clippy::cast_precision_loss)]
+    let nanos = duration.as_nanos() as f64;
+    match unit {
+        DurationUnit::Nanoseconds => nanos,
+        // Divide instead of multiplying seconds, so that the result is the
+        // closest to the exact value (e.g. 0.3 and not 0.30000000000000004)
+        DurationUnit::Milliseconds => nanos / 1_000_000.0,
+        DurationUnit::Seconds => duration.as_secs_f64(),
+    }
+}
+
+/// Pre-compute the labels for each bucket of the poll time histogram, for
+/// each worker.
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "histograms"
+))]
+fn build_histogram_bucket_labels(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+    workers_labels: &[Arc<[KeyValue]>],
+) -> Vec<Vec<Arc<[KeyValue]>>> {
+    if !handle.metrics().poll_time_histogram_enabled()
+        || config.poll_time_histogram_mode != crate::PollTimeHistogramMode::Buckets
+        || config.metric_set < MetricSet::Full
+    {
+        // Don't collect histogram if not enabled, if exported by the sampler, or
+        // if excluded from the metric set
+        return Vec::new();
+    }
+
+    let metrics = handle.metrics();
+    let num_buckets = metrics.poll_time_histogram_num_buckets();
+    let bound = |duration: std::time::Duration| -> opentelemetry::Value {
+        match poll_time_bucket_unit(config) {
+            DurationUnit::Nanoseconds => duration.as_nanos().try_into().unwrap_or(i64::MAX).into(),
+            // Display for f64 gives the shortest decimal representation,
+            // without an exponent, which is how Prometheus formats bounds
+            unit => duration_as_f64(duration, unit).to_string().into(),
+        }
+    };
+    let mut buckets_label: Vec<_> = (0..num_buckets)
+        .map(|bucket_idx| {
+            let range = metrics.poll_time_histogram_bucket_range(bucket_idx);
+            KeyValue::new("le", bound(range.end))
+        })
+        .collect();
+
+    // Change the last bucket to +Inf
+    if let Some(last) = buckets_label.last_mut() {
+        *last = KeyValue::new("le", "+Inf");
+    }
+
+    // The configuration of the histogram, in the unit of the `le` labels: the
+    // width of the first bucket, and the start of the last, unbounded one
+    let histogram_labels = if config.poll_time_histogram_attributes && num_buckets > 0 {
+        vec![
+            KeyValue::new(
+                Key::from_static_str("tokio.poll_time_histogram.buckets"),
+                i64::try_from(num_buckets).unwrap_or(i64::MAX),
+            ),
+            KeyValue::new(
+                Key::from_static_str("tokio.poll_time_histogram.resolution"),
+                bound(metrics.poll_time_histogram_bucket_range(0).end),
+            ),
+            KeyValue::new(
+                Key::from_static_str("tokio.poll_time_histogram.max"),
+                bound(
+                    metrics
+                        .poll_time_histogram_bucket_range(num_buckets - 1)
+                        .start,
+                ),
+            ),
+        ]
+    } else {
+        Vec::new()
+    };
+
+    workers_labels
+        .iter()
+        .map(|worker_labels| {
+            buckets_label
+                .iter()
+                .map(|bucket_label| {
+                    let mut labels = worker_labels.to_vec();
+                    labels.extend(histogram_labels.iter().cloned());
+                    labels.push(bucket_label.clone());
+                    labels.into()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Compute the value each bucket of the poll time histogram contributes to
+/// `tokio.worker.poll_time_sum`, in the unit of the `le` labels.
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "histograms"
+))]
+fn build_histogram_bucket_values(handle: &tokio::runtime::Handle, config: &Config) -> Vec<f64> {
+    let metrics = handle.metrics();
+    let unit = poll_time_bucket_unit(config);
+    (0..metrics.poll_time_histogram_num_buckets())
+        .map(|bucket_idx| duration_as_f64(poll_time_bucket_value(&metrics, bucket_idx), unit))
+        .collect()
+}
+
+/// The time at which the busy duration of each worker of a runtime was read,
+/// and these busy durations.
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+type BusyDurations = (std::time::Instant, Vec<std::time::Duration>);
+
+/// Read the current time and the busy duration of each worker of a runtime.
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn busy_durations(metrics: &tokio::runtime::RuntimeMetrics) -> BusyDurations {
+    let busy_durations = (0..metrics.num_workers())
+        .map(|worker_idx| metrics.worker_total_busy_duration(worker_idx))
+        .collect();
+    (std::time::Instant::now(), busy_durations)
+}
+
+/// The duration representing the polls counted in a bucket of the poll time
+/// histogram: the middle of the bucket, or its start for the last, unbounded
+/// bucket.
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+fn poll_time_bucket_value(
+    metrics: &tokio::runtime::RuntimeMetrics,
+    bucket_idx: usize,
+) -> std::time::Duration {
+    let range = metrics.poll_time_histogram_bucket_range(bucket_idx);
+    if bucket_idx + 1 == metrics.poll_time_histogram_num_buckets() {
+        range.start
+    } else {
+        range.start + range.end.saturating_sub(range.start) / 2
+    }
+}
+
+/// Run the given closure with the labels of the tracked runtime the current
+/// thread is running on.
+///
+/// The closure gets an empty slice when not running on a tracked runtime.
+#[cfg(any(
+    feature = "signal",
+    feature = "time",
+    feature = "tower",
+    feature = "tracing-subscriber",
+    all(
+        tokio_unstable,
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    )
+))]
+pub(crate) fn with_current_runtime_labels<R>(f: impl FnOnce(&[KeyValue]) -> R) -> R {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => with_runtime_labels(&handle, f),
+        Err(_) => f(&[]),
+    }
+}
+
+/// Run the given closure with the labels of the given runtime.
+///
+/// The closure gets an empty slice when the runtime isn't tracked.
+#[cfg(any(
+    feature = "time",
+    feature = "tower",
+    feature = "tracing-subscriber",
+    not(all(target_arch = "wasm32", target_os = "unknown"))
+))]
+pub(crate) fn with_runtime_labels<R>(
+    handle: &tokio::runtime::Handle,
+    f: impl FnOnce(&[KeyValue]) -> R,
+) -> R {
+    // This can be called from within arbitrary code paths (e.g. a tracing
+    // layer), so don't risk deadlocking on the registry lock
+    if let Some(runtimes) = snapshot_runtimes(true) {
+        let id = handle.id();
+        if let Some(runtime) = runtimes.iter().find(|runtime| runtime.id == id) {
+            return f(&runtime.labels);
+        }
+    }
+
+    f(&[])
+}
+
+/// The key of the label naming a runtime, see [`Config::named`].
+pub(crate) const RUNTIME_NAME_KEY: Key = Key::from_static_str("tokio.runtime.name");
+
+/// The name of a runtime, from its labels.
+fn runtime_name(labels: &[KeyValue]) -> Option<&Value> {
+    labels
+        .iter()
+        .find(|label| label.key == RUNTIME_NAME_KEY)
+        .map(|label| &label.value)
+}
+
+/// Refer to a runtime in logs: by its name if it has one, and by its labels
+/// otherwise.
+#[cfg(feature = "tracing")]
+fn describe_runtime(labels: &[KeyValue]) -> String {
+    runtime_name(labels).map_or_else(|| format_labels(labels), Value::to_string)
+}
+
+/// Run a closure with the metrics of the tracked runtime with the given
+/// name, if any.
+pub(crate) fn with_named_runtime<R>(
+    name: &str,
+    f: impl FnOnce(&tokio::runtime::RuntimeMetrics) -> R,
+) -> Option<R> {
+    let runtimes = read_runtimes();
+    let runtime = runtimes.iter().find(|runtime| {
+        runtime_name(&runtime.labels).is_some_and(|value| value.as_str() == name)
+    })?;
+    Some(f(&runtime.metrics))
+}
+
+/// Format labels as space-separated `key=value` pairs, for log messages.
+#[cfg(feature = "tracing")]
+fn format_labels(labels: &[KeyValue]) -> String {
+    labels
+        .iter()
+        .map(|label| format!("{}={}", label.key, label.value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether two handles are handles of the same runtime, see
+/// [`TrackedRuntime::is_runtime`].
+fn same_runtime(handle: &tokio::runtime::Handle, other: &tokio::runtime::Handle) -> bool {
+    handle.id() == other.id()
+}
+
+/// Whether two runtimes are reported with the same labels, in any order,
+/// besides the `tokio.runtime.instance` label telling them apart.
+fn same_labels(labels: &[KeyValue], other: &[KeyValue]) -> bool {
+    let without_instance = |labels: &[KeyValue]| {
+        labels
+            .iter()
+            .filter(|label| label.key.as_str() != "tokio.runtime.instance")
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    let (labels, other) = (without_instance(labels), without_instance(other));
+    labels.len() == other.len() && labels.iter().all(|label| other.contains(label))
+}
+
+/// Build the labels of a runtime being registered (user labels + baggage
+/// labels + tokio.version if detected + tokio.runtime.id), before it gets the
+/// parts which are only allocated once it is accepted, see
+/// [`finish_runtime_labels`].
+fn base_runtime_labels(
+    handle: &tokio::runtime::Handle,
+    config: &Config,
+    runtime_labels: Vec<KeyValue>,
+) -> Vec<KeyValue> {
+    let mut labels = config.labels.clone();
+    labels.extend(runtime_labels);
+
+    if !config.baggage_labels.is_empty() {
+        let context = opentelemetry::Context::current();
+        let baggage = context.baggage();
+        labels.extend(config.baggage_labels.iter().filter_map(|key| {
+            let value = baggage.get(key.as_str())?;
+            Some(KeyValue::new(key.clone(), value.clone()))
+        }));
+    }
+
+    // Detected at build time if possible, see build.rs
+    #[cfg(feature = "tokio-version")]
+    if let Some(version) = option_env!("TOKIO_VERSION") {
+        labels.push(KeyValue::new(
+            Key::from_static_str("tokio.version"),
+            version,
+        ));
+    }
+
+    // Unless set explicitly, use the runtime ID, so that runtimes with the
+    // same user labels still get distinct series
+    let id = config
+        .runtime_id
+        .clone()
+        .unwrap_or_else(|| handle.id().to_string().into());
+    labels.push(KeyValue::new(Key::from_static_str("tokio.runtime.id"), id));
+
+    labels
+}
+
+/// Finish the labels of a runtime accepted in the registry, telling it apart
+/// from the previous runtimes with the same labels.
+fn finish_runtime_labels(mut labels: Vec<KeyValue>) -> (Arc<[KeyValue]>, LabelSet) {
+    // A runtime replacing another one with the same labels starts its
+    // counters from zero, which would look like the counters of the previous
+    // one going backwards, so it gets its own series while the previous one is
+    // still in the registry
+    let label_set = register_label_set(&labels);
+    if label_set.instance > 0 {
+        labels.push(KeyValue::new(
+            Key::from_static_str("tokio.runtime.instance"),
+            i64::try_from(label_set.instance).unwrap_or(i64::MAX),
+        ));
+    }
+
+    (labels.into(), label_set)
+}
+
+/// The set of labels of a runtime in the registry, and its instance among the
+/// runtimes with the same labels.
+struct LabelSet {
+    key: String,
+    instance: u64,
+    released: std::sync::atomic::AtomicBool,
+}
+
+impl LabelSet {
+    /// Free the instance of this runtime, if it wasn't already, removing the
+    /// set of labels once no other runtime uses it.
+    fn release(&self) {
+        if self.released.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let mut label_sets = crate::recover_lock(LABEL_SETS.lock(), "label sets");
+        if let Some(instances) = label_sets.get_mut(&self.key) {
+            instances.remove(&self.instance);
+            if instances.is_empty() {
+                label_sets.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Register a runtime observed with the given labels, taking the lowest
+/// instance not used by the runtimes in the registry with the same labels.
+fn register_label_set(labels: &[KeyValue]) -> LabelSet {
+    let mut key: Vec<_> = labels
+        .iter()
+        .map(|label| format!("{}={}", label.key, label.value))
+        .collect();
+    key.sort_unstable();
+    let key = key.join(" ");
+
+    let mut label_sets = crate::recover_lock(LABEL_SETS.lock(), "label sets");
+    let instances = label_sets.entry(key.clone()).or_default();
+    let instance = (0..u64::MAX)
+        .find(|instance| !instances.contains(instance))
+        .unwrap_or(u64::MAX);
+    instances.insert(instance);
+
+    LabelSet {
+        key,
+        instance,
+        released: std::sync::atomic::AtomicBool::new(false),
+    }
+}
+
+/// Helper to construct a [`KeyValue`] with the worker index.
+fn worker_idx_attribute(i: usize) -> KeyValue {
+    KeyValue::new(
+        Key::from_static_str("tokio.worker.index"),
+        i.try_into().unwrap_or(i64::MAX),
+    )
+}
+
+/// Get a snapshot of the registry of tracked runtimes.
+fn read_runtimes() -> RuntimesSnapshot {
+    snapshot_runtimes(false).unwrap_or_else(|| Arc::new([]))
+}
+
+/// Get the snapshot of the registry taken by this thread, taking a new one if
+/// the registry changed since.
+///
+/// With `try_lock`, this gives up instead of waiting for the registry lock
+/// when a new snapshot is needed.
+fn snapshot_runtimes(try_lock: bool) -> Option<RuntimesSnapshot> {
+    let take_snapshot = || {
+        let runtimes = if try_lock {
+            RUNTIMES.try_read().ok()?
+        } else {
+            crate::recover_lock(RUNTIMES.read(), "runtimes registry")
+        };
+        // Changes happen with the write lock held, so this is the generation
+        // of the runtimes being read
+        let generation = RUNTIMES_GENERATION.load(Ordering::Acquire);
+        Some((
+            generation,
+            runtimes.iter().cloned().collect::<RuntimesSnapshot>(),
+        ))
     };
 
-    let mut runtimes = RUNTIMES.write().unwrap();
-    runtimes.push(tracked_runtime);
-}
+    // The thread-local snapshot is not available while the thread is being
+    // torn down
+    let cached = RUNTIMES_SNAPSHOT.try_with(|snapshot| {
+        let mut snapshot = snapshot.borrow_mut();
+        if snapshot.0 != RUNTIMES_GENERATION.load(Ordering::Acquire) {
+            *snapshot = take_snapshot()?;
+        }
+        Some(snapshot.1.clone())
+    });
+
+    match cached {
+        Ok(snapshot) => snapshot,
+        Err(_) => take_snapshot().map(|(_, runtimes)| runtimes),
+    }
+}
+
+/// Observe the given metric for each tracked runtime.
+///
+/// The observations of each runtime go through an instrument adding its
+/// dynamic labels, see [`Config::with_dynamic_labels`]. Runtimes for which
+/// `observe` panics are skipped and evicted, see [`TrackedRuntime::collect`].
+/// With [`Config::with_collection_threads`], the runtimes are split in chunks
+/// observed concurrently by the threads of the [`pool`] once there are enough
+/// of them.
+fn observe_runtimes<T: Send + 'static>(
+    metric: &'static str,
+    instrument: &dyn AsyncInstrument<T>,
+    observe: impl Fn(&dyn AsyncInstrument<T>, &TrackedRuntime) + Send + Sync + 'static,
+) {
+    let runtimes = read_runtimes();
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    if runtimes.len() >= pool::PARALLEL_COLLECTION_THRESHOLD {
+        let threads = COLLECTION_THREADS
+            .load(Ordering::Relaxed)
+            .min(runtimes.len());
+        if threads > 1 {
+            let threads = pool::ensure_threads(threads - 1) + 1;
+            observe_runtimes_concurrently(metric, instrument, observe, &runtimes, threads);
+            return;
+        }
+    }
+
+    observe_chunk(metric, instrument, &observe, &runtimes);
+}
+
+/// Observe the given metric for a chunk of the tracked runtimes.
+fn observe_chunk<T>(
+    metric: &'static str,
+    instrument: &dyn AsyncInstrument<T>,
+    observe: &impl Fn(&dyn AsyncInstrument<T>, &TrackedRuntime),
+    runtimes: &[Arc<TrackedRuntime>],
+) {
+    for runtime in runtimes {
+        runtime.collect(metric, || {
+            let labels = runtime.dynamic_labels();
+            observe(&LabeledInstrument { instrument, labels }, runtime);
+        });
+    }
+}
+
+/// Observe the given metric for the tracked runtimes split in chunks, one
+/// per thread, all but the first one being observed by the threads of the
+/// [`pool`].
+///
+/// The instrument can only be used on the collecting thread, so the threads
+/// of the pool record their observations, which are then replayed into it.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn observe_runtimes_concurrently<T: Send + 'static>(
+    metric: &'static str,
+    instrument: &dyn AsyncInstrument<T>,
+    observe: impl Fn(&dyn AsyncInstrument<T>, &TrackedRuntime) + Send + Sync + 'static,
+    runtimes: &RuntimesSnapshot,
+    threads: usize,
+) {
+    let chunk_size = runtimes.len().div_ceil(threads);
+    let observe = Arc::new(observe);
+    let pending: Vec<_> = (chunk_size..runtimes.len())
+        .step_by(chunk_size)
+        .map(|start| {
+            let chunk = start..runtimes.len().min(start + chunk_size);
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let (runtimes, observe) = (Arc::clone(runtimes), Arc::clone(&observe));
+            pool::execute(Box::new(move || {
+                let recorded = RecordingInstrument(Mutex::new(Vec::new()));
+                observe_chunk(metric, &recorded, &*observe, &runtimes[chunk]);
+                let _ = sender.send(recorded);
+            }));
+            (start, receiver)
+        })
+        .collect();
+
+    observe_chunk(metric, instrument, &*observe, &runtimes[..chunk_size]);
 
-/// Build labels for a runtime (user labels + tokio.runtime.id if available).
-fn build_runtime_labels(handle: &tokio::runtime::Handle, labels: &[KeyValue]) -> Vec<KeyValue> {
-    #[cfg_attr(not(tokio_unstable), expect(unused_mut))]
-    let mut labels = labels.to_vec();
+    for (start, receiver) in pending {
+        // Observe the chunk on this thread if the pool couldn't
+        let Ok(recorded) = receiver.recv() else {
+            let chunk = start..runtimes.len().min(start + chunk_size);
+            observe_chunk(metric, instrument, &*observe, &runtimes[chunk]);
+            continue;
+        };
 
-    // Auto-add tokio.runtime.id when tokio_unstable is available
-    #[cfg(tokio_unstable)]
-    {
-        labels.push(KeyValue::new(
-            Key::from_static_str("tokio.runtime.id"),
-            handle.id().to_string(),
-        ));
+        let observations = recorded
+            .0
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        for (measurement, attributes) in observations {
+            instrument.observe(measurement, &attributes);
+        }
     }
+}
 
-    // Silence unused parameter warning when tokio_unstable is not set
-    #[cfg(not(tokio_unstable))]
-    let _ = handle;
+/// An instrument recording its observations, to replay them into another
+/// one, see [`observe_runtimes_concurrently`].
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct RecordingInstrument<T>(Mutex<Vec<(T, Vec<KeyValue>)>>);
 
-    labels
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl<T: Send> AsyncInstrument<T> for RecordingInstrument<T> {
+    fn observe(&self, measurement: T, attributes: &[KeyValue]) {
+        crate::recover_lock(self.0.lock(), "recorded observations")
+            .push((measurement, attributes.to_vec()));
+    }
 }
 
-/// Helper to construct a [`KeyValue`] with the worker index.
-fn worker_idx_attribute(i: usize) -> KeyValue {
-    KeyValue::new(
-        Key::from_static_str("tokio.worker.index"),
-        i.try_into().unwrap_or(i64::MAX),
-    )
+/// An instrument adding labels to the observations of another one.
+struct LabeledInstrument<'a, T> {
+    instrument: &'a dyn AsyncInstrument<T>,
+    labels: Vec<KeyValue>,
 }
 
-/// Register all instruments (one-time, called via `Once`).
-fn register_all_instruments() {
-    let scope = InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
-        .with_version(env!("CARGO_PKG_VERSION"))
-        .build();
+impl<T> AsyncInstrument<T> for LabeledInstrument<'_, T> {
+    fn observe(&self, measurement: T, attributes: &[KeyValue]) {
+        self.instrument
+            .observe(measurement, &with_labels(attributes, &self.labels));
+    }
+}
 
-    let meter = opentelemetry::global::meter_with_scope(scope);
+/// Add labels to the given attributes, without copying them if there are
+/// none to add.
+fn with_labels<'a>(attributes: &'a [KeyValue], labels: &[KeyValue]) -> Cow<'a, [KeyValue]> {
+    if labels.is_empty() {
+        Cow::Borrowed(attributes)
+    } else {
+        Cow::Owned([attributes, labels].concat())
+    }
+}
 
+/// Register all instruments with the given meter (one-time for the global
+/// meter, called via `Once`).
+fn register_all_instruments(meter: &Meter) {
     // Always-available metrics
-    register_workers_gauge(&meter);
-    register_global_queue_depth_gauge(&meter);
-    register_alive_tasks_gauge(&meter);
+    register_workers_gauge(meter);
+    register_global_queue_depth_gauge(meter);
 
     // Metrics requiring 64-bit atomics
-    #[cfg(target_has_atomic = "64")]
+    #[cfg(all(target_has_atomic = "64", feature = "worker-metrics"))]
     {
-        register_worker_park_count_counter(&meter);
-        register_worker_busy_duration_counter(&meter);
+        register_worker_park_count_counter(meter);
+        register_worker_park_unpark_count_counter(meter);
+
+        // Tokio can't measure time on wasm32-unknown-unknown
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        register_worker_utilization_gauge(meter);
     }
 
     // Metrics requiring `--cfg tokio_unstable`
     #[cfg(tokio_unstable)]
     {
-        register_blocking_threads_gauge(&meter);
-        register_idle_blocking_threads_gauge(&meter);
-        register_remote_schedules_counter(&meter);
-        register_budget_forced_yields_counter(&meter);
+        register_blocking_threads_gauge(meter);
+        register_idle_blocking_threads_gauge(meter);
+        register_remote_schedules_counter(meter);
+        register_budget_forced_yields_counter(meter);
+
+        // I/O driver metrics require the net feature of Tokio
+        #[cfg(all(
+            not(target_family = "wasm"),
+            target_has_atomic = "64",
+            feature = "io-driver-metrics"
+        ))]
+        {
+            register_io_driver_fd_registrations_counter(meter);
+            register_io_driver_fd_deregistrations_counter(meter);
+            register_io_driver_fd_readies_counter(meter);
+        }
+
+        register_spawned_tasks_count_counter(meter);
+        register_blocking_queue_depth_gauge(meter);
 
-        // I/O driver metrics require net feature
-        #[cfg(all(not(target_family = "wasm"), target_has_atomic = "64", feature = "net"))]
+        #[cfg(feature = "worker-metrics")]
         {
-            register_io_driver_fd_registrations_counter(&meter);
-            register_io_driver_fd_deregistrations_counter(&meter);
-            register_io_driver_fd_readies_counter(&meter);
+            register_worker_noops_counter(meter);
+            register_worker_task_steals_counter(meter);
+            register_worker_steal_operations_counter(meter);
+            register_worker_polls_counter(meter);
+            register_worker_local_schedules_counter(meter);
+            register_worker_overflows_counter(meter);
+            register_worker_local_queue_depth_gauge(meter);
+            register_worker_local_queue_saturation_gauge(meter);
         }
 
-        register_spawned_tasks_count_counter(&meter);
-        register_blocking_queue_depth_gauge(&meter);
-        register_worker_noops_counter(&meter);
-        register_worker_task_steals_counter(&meter);
-        register_worker_steal_operations_counter(&meter);
-        register_worker_polls_counter(&meter);
-        register_worker_local_schedules_counter(&meter);
-        register_worker_overflows_counter(&meter);
-        register_worker_local_queue_depth_gauge(&meter);
-        register_worker_mean_poll_time_gauge(&meter);
-        register_poll_time_histogram(&meter);
+        // Tokio can't measure time on wasm32-unknown-unknown
+        #[cfg(all(
+            not(all(target_arch = "wasm32", target_os = "unknown")),
+            feature = "histograms"
+        ))]
+        {
+            register_poll_time_histogram(meter);
+            register_poll_time_count_counter(meter);
+            register_poll_time_sum_counter(meter);
+        }
     }
+
+    #[cfg(all(feature = "os-metrics", unix))]
+    crate::os::register_instruments(meter);
 }
 
 // ============================================================================
@@ -188,13 +1662,16 @@ fn register_workers_gauge(meter: &Meter) {
         .with_description("The number of worker threads used by the runtime")
         .with_unit("{worker}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.workers", instrument, |instrument, runtime| {
                 instrument.observe(
-                    runtime.metrics.num_workers().try_into().unwrap_or(u64::MAX),
+                    runtime
+                        .metrics
+                        .num_workers()
+                        .try_into()
+                        .unwrap_or_else(|_| crate::overflowed("tokio.workers", u64::MAX)),
                     &runtime.labels,
                 );
-            }
+            });
         })
         .build();
 }
@@ -205,58 +1682,200 @@ fn register_global_queue_depth_gauge(meter: &Meter) {
         .with_description("The number of tasks currently scheduled in the runtime's global queue")
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                instrument.observe(
-                    runtime
-                        .metrics
-                        .global_queue_depth()
-                        .try_into()
-                        .unwrap_or(u64::MAX),
-                    &runtime.labels,
-                );
-            }
+            observe_runtimes(
+                "tokio.global_queue_depth",
+                instrument,
+                |instrument, runtime| {
+                    instrument.observe(
+                        runtime
+                            .metrics
+                            .global_queue_depth()
+                            .try_into()
+                            .unwrap_or_else(|_| {
+                                crate::overflowed("tokio.global_queue_depth", u64::MAX)
+                            }),
+                        &runtime.labels,
+                    );
+                },
+            );
         })
         .build();
 }
 
-#[cfg(target_has_atomic = "64")]
+#[cfg(all(target_has_atomic = "64", feature = "worker-metrics"))]
 fn register_worker_park_count_counter(meter: &Meter) {
     meter
         .u64_observable_counter("tokio.worker.park_count")
         .with_description("The total number of times the given worker thread has parked")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(runtime.metrics.worker_park_count(worker_idx), &labels[..]);
-                }
-            }
+            observe_runtimes(
+                "tokio.worker.park_count",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.counters_sampled() {
+                        return;
+                    }
+
+                    runtime.observe_changed_workers(
+                        "tokio.worker.park_count",
+                        |worker_idx| runtime.metrics.worker_park_count(worker_idx),
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
+                    );
+                },
+            );
         })
         .build();
 }
 
-#[cfg(target_has_atomic = "64")]
-fn register_worker_busy_duration_counter(meter: &Meter) {
+#[cfg(all(target_has_atomic = "64", feature = "worker-metrics"))]
+fn register_worker_park_unpark_count_counter(meter: &Meter) {
     meter
-        .u64_observable_counter("tokio.worker.busy_duration")
-        .with_description("The amount of time the given worker thread has been busy")
-        .with_unit("ms")
+        .u64_observable_counter("tokio.worker.park_unpark_count")
+        .with_description(
+            "The total number of times the given worker thread has parked and unparked",
+        )
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(
-                        runtime
-                            .metrics
-                            .worker_total_busy_duration(worker_idx)
-                            .as_millis()
-                            .try_into()
-                            .unwrap_or(u64::MAX),
-                        &labels[..],
+            observe_runtimes(
+                "tokio.worker.park_unpark_count",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.counters_sampled() {
+                        return;
+                    }
+
+                    runtime.observe_changed_workers(
+                        "tokio.worker.park_unpark_count",
+                        |worker_idx| runtime.metrics.worker_park_unpark_count(worker_idx),
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
                     );
-                }
-            }
+                },
+            );
+        })
+        .build();
+}
+
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "worker-metrics"
+))]
+fn register_worker_busy_duration_counter(meter: &Meter, unit: DurationUnit) {
+    const DESCRIPTION: &str = "The amount of time the given worker thread has been busy";
+
+    let runtimes_in_unit = move |runtime: &&TrackedRuntime| {
+        runtime.busy_duration_unit == unit && !runtime.counters_sampled()
+    };
+
+    if unit == DurationUnit::Seconds {
+        meter
+            .f64_observable_counter("tokio.worker.busy_duration")
+            .with_description(DESCRIPTION)
+            .with_unit(unit.symbol())
+            .with_callback(move |instrument| {
+                observe_runtimes(
+                    "tokio.worker.busy_duration",
+                    instrument,
+                    move |instrument, runtime| {
+                        if !runtimes_in_unit(&runtime) {
+                            return;
+                        }
+
+                        runtime.observe_changed_workers(
+                            "tokio.worker.busy_duration",
+                            |worker_idx| {
+                                let busy_duration =
+                                    runtime.metrics.worker_total_busy_duration(worker_idx);
+                                duration_as_f64(busy_duration, unit)
+                            },
+                            |a, b| a + b,
+                            |value, labels| instrument.observe(value, labels),
+                        );
+                    },
+                );
+            })
+            .build();
+    } else {
+        meter
+            .u64_observable_counter("tokio.worker.busy_duration")
+            .with_description(DESCRIPTION)
+            .with_unit(unit.symbol())
+            .with_callback(move |instrument| {
+                observe_runtimes(
+                    "tokio.worker.busy_duration",
+                    instrument,
+                    move |instrument, runtime| {
+                        if !runtimes_in_unit(&runtime) {
+                            return;
+                        }
+
+                        runtime.observe_changed_workers(
+                            "tokio.worker.busy_duration",
+                            |worker_idx| {
+                                let busy_duration =
+                                    runtime.metrics.worker_total_busy_duration(worker_idx);
+                                duration_as_u64(busy_duration, unit, "tokio.worker.busy_duration")
+                            },
+                            u64::saturating_add,
+                            |value, labels| instrument.observe(value, labels),
+                        );
+                    },
+                );
+            })
+            .build();
+    }
+}
+
+#[cfg(all(
+    target_has_atomic = "64",
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "worker-metrics"
+))]
+fn register_worker_utilization_gauge(meter: &Meter) {
+    // The instruments are registered once per meter provider, each of them
+    // computing the utilization since its own last collection. The readers of
+    // a provider share its callback though, so with several of them, each
+    // reports the utilization since the last collection of any of them.
+    static NEXT_CALLBACK: AtomicUsize = AtomicUsize::new(0);
+    let callback = NEXT_CALLBACK.fetch_add(1, Ordering::Relaxed);
+
+    meter
+        .f64_observable_gauge("tokio.worker.utilization")
+        .with_description(
+            "The fraction of time the given worker thread was busy since the last collection",
+        )
+        .with_unit("1")
+        .with_callback(move |instrument| {
+            observe_runtimes(
+                "tokio.worker.utilization",
+                instrument,
+                move |instrument, runtime| {
+                    let (now, busy_durations) = busy_durations(&runtime.metrics);
+                    let (last_collection, last_busy_durations) =
+                        crate::recover_lock(runtime.last_busy_durations.lock(), "busy durations")
+                            .insert(callback, (now, busy_durations.clone()))
+                            .unwrap_or_else(|| runtime.registered_busy_durations.clone());
+
+                    let elapsed = now.saturating_duration_since(last_collection);
+                    if elapsed.is_zero() {
+                        return;
+                    }
+
+                    runtime.observe_workers(
+                        |worker_idx| {
+                            let busy = busy_durations[worker_idx]
+                                .saturating_sub(last_busy_durations[worker_idx]);
+
+                            // The busy duration is only updated when the worker parks, so it can
+                            // be slightly ahead of the wall-clock time
+                            (busy.as_secs_f64() / elapsed.as_secs_f64()).min(1.0)
+                        },
+                        f64::max,
+                        |value, labels| instrument.observe(value, labels),
+                    );
+                },
+            );
         })
         .build();
 }
@@ -267,17 +1886,44 @@ fn register_alive_tasks_gauge(meter: &Meter) {
         .with_description("The number of active tasks in the runtime")
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.alive_tasks", instrument, |instrument, runtime| {
+                if runtime.alive_tasks_as_up_down_counter {
+                    return;
+                }
+
                 instrument.observe(
                     runtime
                         .metrics
                         .num_alive_tasks()
                         .try_into()
-                        .unwrap_or(u64::MAX),
+                        .unwrap_or_else(|_| crate::overflowed("tokio.alive_tasks", u64::MAX)),
                     &runtime.labels,
                 );
-            }
+            });
+        })
+        .build();
+}
+
+fn register_alive_tasks_up_down_counter(meter: &Meter) {
+    meter
+        .i64_observable_up_down_counter("tokio.alive_tasks")
+        .with_description("The number of active tasks in the runtime")
+        .with_unit("{task}")
+        .with_callback(|instrument| {
+            observe_runtimes("tokio.alive_tasks", instrument, |instrument, runtime| {
+                if !runtime.alive_tasks_as_up_down_counter {
+                    return;
+                }
+
+                instrument.observe(
+                    runtime
+                        .metrics
+                        .num_alive_tasks()
+                        .try_into()
+                        .unwrap_or_else(|_| crate::overflowed("tokio.alive_tasks", i64::MAX)),
+                    &runtime.labels,
+                );
+            });
         })
         .build();
 }
@@ -293,17 +1939,22 @@ fn register_blocking_threads_gauge(meter: &Meter) {
         .with_description("The number of additional threads spawned by the runtime")
         .with_unit("{thread}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                instrument.observe(
-                    runtime
-                        .metrics
-                        .num_blocking_threads()
-                        .try_into()
-                        .unwrap_or(u64::MAX),
-                    &runtime.labels,
-                );
-            }
+            observe_runtimes(
+                "tokio.blocking_threads",
+                instrument,
+                |instrument, runtime| {
+                    instrument.observe(
+                        runtime
+                            .metrics
+                            .num_blocking_threads()
+                            .try_into()
+                            .unwrap_or_else(|_| {
+                                crate::overflowed("tokio.blocking_threads", u64::MAX)
+                            }),
+                        &runtime.labels,
+                    );
+                },
+            );
         })
         .build();
 }
@@ -317,16 +1968,15 @@ fn register_idle_blocking_threads_gauge(meter: &Meter) {
         )
         .with_unit("{thread}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.idle_blocking_threads", instrument, |instrument, runtime| {
                 instrument.observe(
                     runtime.metrics
                         .num_idle_blocking_threads()
                         .try_into()
-                        .unwrap_or(u64::MAX),
+                        .unwrap_or_else(|_| crate::overflowed("tokio.idle_blocking_threads", u64::MAX)),
                     &runtime.labels,
                 );
-            }
+            });
         })
         .build();
 }
@@ -338,10 +1988,17 @@ fn register_remote_schedules_counter(meter: &Meter) {
         .with_description("The number of tasks scheduled from outside the runtime")
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                instrument.observe(runtime.metrics.remote_schedule_count(), &runtime.labels);
-            }
+            observe_runtimes(
+                "tokio.remote_schedules",
+                instrument,
+                |instrument, runtime| {
+                    if !runtime.exports(MetricSet::Standard) || runtime.counters_sampled() {
+                        return;
+                    }
+
+                    instrument.observe(runtime.metrics.remote_schedule_count(), &runtime.labels);
+                },
+            );
         })
         .build();
 }
@@ -355,10 +2012,13 @@ fn register_budget_forced_yields_counter(meter: &Meter) {
         )
         .with_unit("{yield}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.budget_forced_yields", instrument, |instrument, runtime| {
+                if !runtime.exports(MetricSet::Standard) || runtime.counters_sampled() {
+                    return;
+                }
+
                 instrument.observe(runtime.metrics.budget_forced_yield_count(), &runtime.labels);
-            }
+            });
         })
         .build();
 }
@@ -367,7 +2027,7 @@ fn register_budget_forced_yields_counter(meter: &Meter) {
     tokio_unstable,
     not(target_family = "wasm"),
     target_has_atomic = "64",
-    feature = "net"
+    feature = "io-driver-metrics"
 ))]
 fn register_io_driver_fd_registrations_counter(meter: &Meter) {
     meter
@@ -377,10 +2037,13 @@ fn register_io_driver_fd_registrations_counter(meter: &Meter) {
         )
         .with_unit("{fd}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.io_driver.fd_registrations", instrument, |instrument, runtime| {
+                if !runtime.exports(MetricSet::Full) || runtime.counters_sampled() {
+                    return;
+                }
+
                 instrument.observe(runtime.metrics.io_driver_fd_registered_count(), &runtime.labels);
-            }
+            });
         })
         .build();
 }
@@ -389,7 +2052,7 @@ fn register_io_driver_fd_registrations_counter(meter: &Meter) {
     tokio_unstable,
     not(target_family = "wasm"),
     target_has_atomic = "64",
-    feature = "net"
+    feature = "io-driver-metrics"
 ))]
 fn register_io_driver_fd_deregistrations_counter(meter: &Meter) {
     meter
@@ -399,10 +2062,13 @@ fn register_io_driver_fd_deregistrations_counter(meter: &Meter) {
         )
         .with_unit("{fd}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.io_driver.fd_deregistrations", instrument, |instrument, runtime| {
+                if !runtime.exports(MetricSet::Full) || runtime.counters_sampled() {
+                    return;
+                }
+
                 instrument.observe(runtime.metrics.io_driver_fd_deregistered_count(), &runtime.labels);
-            }
+            });
         })
         .build();
 }
@@ -411,7 +2077,7 @@ fn register_io_driver_fd_deregistrations_counter(meter: &Meter) {
     tokio_unstable,
     not(target_family = "wasm"),
     target_has_atomic = "64",
-    feature = "net"
+    feature = "io-driver-metrics"
 ))]
 fn register_io_driver_fd_readies_counter(meter: &Meter) {
     meter
@@ -419,10 +2085,17 @@ fn register_io_driver_fd_readies_counter(meter: &Meter) {
         .with_description("The number of ready events processed by the runtime's I/O driver")
         .with_unit("{event}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                instrument.observe(runtime.metrics.io_driver_ready_count(), &runtime.labels);
-            }
+            observe_runtimes(
+                "tokio.io_driver.fd_readies",
+                instrument,
+                |instrument, runtime| {
+                    if !runtime.exports(MetricSet::Full) || runtime.counters_sampled() {
+                        return;
+                    }
+
+                    instrument.observe(runtime.metrics.io_driver_ready_count(), &runtime.labels);
+                },
+            );
         })
         .build();
 }
@@ -434,10 +2107,17 @@ fn register_spawned_tasks_count_counter(meter: &Meter) {
         .with_description("The number of tasks spawned in this runtime since it was created")
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                instrument.observe(runtime.metrics.spawned_tasks_count(), &runtime.labels);
-            }
+            observe_runtimes(
+                "tokio.spawned_tasks_count",
+                instrument,
+                |instrument, runtime| {
+                    if !runtime.exports(MetricSet::Standard) || runtime.counters_sampled() {
+                        return;
+                    }
+
+                    instrument.observe(runtime.metrics.spawned_tasks_count(), &runtime.labels);
+                },
+            );
         })
         .build();
 }
@@ -451,40 +2131,47 @@ fn register_blocking_queue_depth_gauge(meter: &Meter) {
         )
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
+            observe_runtimes("tokio.blocking_queue_depth", instrument, |instrument, runtime| {
                 instrument.observe(
                     runtime.metrics
                         .blocking_queue_depth()
                         .try_into()
-                        .unwrap_or(u64::MAX),
+                        .unwrap_or_else(|_| crate::overflowed("tokio.blocking_queue_depth", u64::MAX)),
                     &runtime.labels,
                 );
-            }
+            });
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_noops_counter(meter: &Meter) {
+    const DESCRIPTION: &str = "The number of times the given worker thread unparked but performed no work before parking again";
+
+    let observe = |instrument: &dyn AsyncInstrument<u64>, runtime: &TrackedRuntime| {
+        if runtime.counters_sampled() {
+            return;
+        }
+
+        runtime.observe_changed_workers(
+            "tokio.worker.noops",
+            |worker_idx| runtime.metrics.worker_noop_count(worker_idx),
+            u64::saturating_add,
+            |value, labels| instrument.observe(value, labels),
+        );
+    };
+
     meter
         .u64_observable_counter("tokio.worker.noops")
-        .with_description(
-            "The number of times the given worker thread unparked but performed no work before parking again",
-        )
+        .with_description(DESCRIPTION)
         .with_unit("{operation}")
-        .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(runtime.metrics.worker_noop_count(worker_idx), &labels[..]);
-                }
-            }
+        .with_callback(move |instrument| {
+            observe_runtimes("tokio.worker.noops", instrument, observe);
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_task_steals_counter(meter: &Meter) {
     meter
         .u64_observable_counter("tokio.worker.task_steals")
@@ -492,17 +2179,27 @@ fn register_worker_task_steals_counter(meter: &Meter) {
             "The number of tasks the given worker thread stole from another worker thread",
         )
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(runtime.metrics.worker_steal_count(worker_idx), &labels[..]);
-                }
-            }
+            observe_runtimes(
+                "tokio.worker.task_steals",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.is_current_thread || runtime.counters_sampled() {
+                        return;
+                    }
+
+                    runtime.observe_changed_workers(
+                        "tokio.worker.task_steals",
+                        |worker_idx| runtime.metrics.worker_steal_count(worker_idx),
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
+                    );
+                },
+            );
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_steal_operations_counter(meter: &Meter) {
     meter
         .u64_observable_counter("tokio.worker.steal_operations")
@@ -510,75 +2207,103 @@ fn register_worker_steal_operations_counter(meter: &Meter) {
             "The number of times the given worker thread stole tasks from another worker thread",
         )
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(
-                        runtime.metrics.worker_steal_operations(worker_idx),
-                        &labels[..],
+            observe_runtimes(
+                "tokio.worker.steal_operations",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.is_current_thread || runtime.counters_sampled() {
+                        return;
+                    }
+
+                    runtime.observe_changed_workers(
+                        "tokio.worker.steal_operations",
+                        |worker_idx| runtime.metrics.worker_steal_operations(worker_idx),
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
                     );
-                }
-            }
+                },
+            );
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_polls_counter(meter: &Meter) {
     meter
         .u64_observable_counter("tokio.worker.polls")
         .with_description("The number of tasks the given worker thread has polled")
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(runtime.metrics.worker_poll_count(worker_idx), &labels[..]);
+            observe_runtimes("tokio.worker.polls", instrument, |instrument, runtime| {
+                if runtime.counters_sampled() {
+                    return;
                 }
-            }
+
+                runtime.observe_changed_workers(
+                    "tokio.worker.polls",
+                    |worker_idx| runtime.metrics.worker_poll_count(worker_idx),
+                    u64::saturating_add,
+                    |value, labels| instrument.observe(value, labels),
+                );
+            });
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_local_schedules_counter(meter: &Meter) {
+    const DESCRIPTION: &str = "The number of tasks scheduled from **within** the runtime on the given worker's local queue";
+
+    let observe = |instrument: &dyn AsyncInstrument<u64>, runtime: &TrackedRuntime| {
+        if runtime.counters_sampled() {
+            return;
+        }
+
+        runtime.observe_changed_workers(
+            "tokio.worker.local_schedules",
+            |worker_idx| runtime.metrics.worker_local_schedule_count(worker_idx),
+            u64::saturating_add,
+            |value, labels| instrument.observe(value, labels),
+        );
+    };
+
     meter
         .u64_observable_counter("tokio.worker.local_schedules")
-        .with_description(
-            "The number of tasks scheduled from **within** the runtime on the given worker's local queue",
-        )
+        .with_description(DESCRIPTION)
         .with_unit("{task}")
-        .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(runtime.metrics.worker_local_schedule_count(worker_idx), &labels[..]);
-                }
-            }
+        .with_callback(move |instrument| {
+            observe_runtimes("tokio.worker.local_schedules", instrument, observe);
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_overflows_counter(meter: &Meter) {
     meter
         .u64_observable_counter("tokio.worker.overflows")
         .with_description("The number of times the given worker thread saturated its local queue")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(
-                        runtime.metrics.worker_overflow_count(worker_idx),
-                        &labels[..],
+            observe_runtimes(
+                "tokio.worker.overflows",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.is_current_thread || runtime.counters_sampled() {
+                        return;
+                    }
+
+                    runtime.observe_changed_workers(
+                        "tokio.worker.overflows",
+                        |worker_idx| runtime.metrics.worker_overflow_count(worker_idx),
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
                     );
-                }
-            }
+                },
+            );
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
 fn register_worker_local_queue_depth_gauge(meter: &Meter) {
     meter
         .u64_observable_gauge("tokio.worker.local_queue_depth")
@@ -587,68 +2312,265 @@ fn register_worker_local_queue_depth_gauge(meter: &Meter) {
         )
         .with_unit("{task}")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(
-                        runtime
-                            .metrics
-                            .worker_local_queue_depth(worker_idx)
-                            .try_into()
-                            .unwrap_or(u64::MAX),
-                        &labels[..],
+            observe_runtimes(
+                "tokio.worker.local_queue_depth",
+                instrument,
+                |instrument, runtime| {
+                    runtime.observe_workers(
+                        |worker_idx| {
+                            runtime
+                                .metrics
+                                .worker_local_queue_depth(worker_idx)
+                                .try_into()
+                                .unwrap_or_else(|_| {
+                                    crate::overflowed("tokio.worker.local_queue_depth", u64::MAX)
+                                })
+                        },
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
                     );
-                }
-            }
+                },
+            );
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
-fn register_worker_mean_poll_time_gauge(meter: &Meter) {
+/// The capacity of the local queue of each worker of a `multi_thread` runtime.
+///
+/// This is fixed in Tokio, but not exposed through its API.
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+const LOCAL_QUEUE_CAPACITY: usize = 256;
+
+#[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+fn register_worker_local_queue_saturation_gauge(meter: &Meter) {
     meter
-        .u64_observable_gauge("tokio.worker.mean_poll_time")
-        .with_description("The mean duration of task polls, in nanoseconds")
-        .with_unit("ns")
+        .f64_observable_gauge("tokio.worker.local_queue_saturation")
+        .with_description(
+            "The fraction of the given worker's local queue currently in use. \
+             When it reaches 1, half of the queue overflows to the global queue",
+        )
+        .with_unit("1")
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.workers_labels.iter().enumerate() {
-                    instrument.observe(
-                        runtime
-                            .metrics
-                            .worker_mean_poll_time(worker_idx)
-                            .as_nanos()
-                            .try_into()
-                            .unwrap_or(u64::MAX),
-                        &labels[..],
+            // The local queue of current_thread runtimes is unbounded
+            observe_runtimes(
+                "tokio.worker.local_queue_saturation",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.is_current_thread {
+                        return;
+                    }
+
+                    runtime.observe_workers(
+                        |worker_idx| {
+                            let depth = runtime.metrics.worker_local_queue_depth(worker_idx);
+                            // Both are at most 256, so this is exact
+                            #[expect(clippy::cast_precision_loss)]
+                            let saturation = depth.min(LOCAL_QUEUE_CAPACITY) as f64
+                                / LOCAL_QUEUE_CAPACITY as f64;
+                            saturation
+                        },
+                        f64::max,
+                        |value, labels| instrument.observe(value, labels),
                     );
-                }
-            }
+                },
+            );
         })
         .build();
 }
 
-#[cfg(tokio_unstable)]
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "worker-metrics"
+))]
+fn register_worker_mean_poll_time_gauge(meter: &Meter, unit: DurationUnit) {
+    let description = match unit {
+        DurationUnit::Nanoseconds => "The mean duration of task polls, in nanoseconds",
+        DurationUnit::Milliseconds => "The mean duration of task polls, in milliseconds",
+        DurationUnit::Seconds => "The mean duration of task polls, in seconds",
+    };
+
+    let runtimes_in_unit = move |runtime: &&TrackedRuntime| runtime.mean_poll_time_unit == unit;
+
+    if unit == DurationUnit::Nanoseconds {
+        meter
+            .u64_observable_gauge("tokio.worker.mean_poll_time")
+            .with_description(description)
+            .with_unit(unit.symbol())
+            .with_callback(move |instrument| {
+                observe_runtimes(
+                    "tokio.worker.mean_poll_time",
+                    instrument,
+                    move |instrument, runtime| {
+                        if !runtimes_in_unit(&runtime) {
+                            return;
+                        }
+
+                        runtime.observe_workers(
+                            |worker_idx| {
+                                let mean_poll_time =
+                                    runtime.metrics.worker_mean_poll_time(worker_idx);
+                                duration_as_u64(mean_poll_time, unit, "tokio.worker.mean_poll_time")
+                            },
+                            u64::max,
+                            |value, labels| instrument.observe(value, labels),
+                        );
+                    },
+                );
+            })
+            .build();
+    } else {
+        meter
+            .f64_observable_gauge("tokio.worker.mean_poll_time")
+            .with_description(description)
+            .with_unit(unit.symbol())
+            .with_callback(move |instrument| {
+                observe_runtimes(
+                    "tokio.worker.mean_poll_time",
+                    instrument,
+                    move |instrument, runtime| {
+                        if !runtimes_in_unit(&runtime) {
+                            return;
+                        }
+
+                        runtime.observe_workers(
+                            |worker_idx| {
+                                let mean_poll_time =
+                                    runtime.metrics.worker_mean_poll_time(worker_idx);
+                                duration_as_f64(mean_poll_time, unit)
+                            },
+                            f64::max,
+                            |value, labels| instrument.observe(value, labels),
+                        );
+                    },
+                );
+            })
+            .build();
+    }
+}
+
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "histograms"
+))]
 fn register_poll_time_histogram(meter: &Meter) {
     meter
         .u64_observable_gauge("tokio.worker.poll_time_bucket")
-        .with_description("An histogram of the poll time of tasks, in nanoseconds")
+        .with_description("A histogram of the poll time of tasks")
         // We don't set a unit here, as it would add it as a suffix to the metric name
         .with_callback(|instrument| {
-            let runtimes = RUNTIMES.read().unwrap();
-            for runtime in runtimes.iter() {
-                for (worker_idx, labels) in runtime.histogram_bucket_labels.iter().enumerate() {
-                    let mut sum = 0u64;
-                    for (bucket_idx, labels) in labels.iter().enumerate() {
-                        let count = runtime
-                            .metrics
-                            .poll_time_histogram_bucket_count(worker_idx, bucket_idx);
-                        sum += count;
-                        instrument.observe(sum, &labels[..]);
+            observe_runtimes(
+                "tokio.worker.poll_time_bucket",
+                instrument,
+                |instrument, runtime| {
+                    // The buckets are cumulative
+                    let mut sums: smallvec::SmallVec<[u64; 16]> =
+                        smallvec::smallvec![0; runtime.histogram_bucket_labels.len()];
+                    for bucket_idx in 0..runtime.histogram_bucket_values.len() {
+                        let mut series = runtime.histogram_bucket_labels.iter().zip(&mut sums);
+                        runtime.observe_workers(
+                            |worker_idx| {
+                                runtime
+                                    .metrics
+                                    .poll_time_histogram_bucket_count(worker_idx, bucket_idx)
+                            },
+                            u64::saturating_add,
+                            |count, _| {
+                                if let Some((labels, sum)) = series.next() {
+                                    *sum += count;
+                                    instrument.observe(*sum, &labels[bucket_idx]);
+                                }
+                            },
+                        );
                     }
-                }
-            }
+                },
+            );
+        })
+        .build();
+}
+
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "histograms"
+))]
+fn register_poll_time_count_counter(meter: &Meter) {
+    meter
+        .u64_observable_counter("tokio.worker.poll_time_count")
+        .with_description("The number of polls counted in the poll time histogram")
+        .with_callback(|instrument| {
+            observe_runtimes(
+                "tokio.worker.poll_time_count",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.histogram_bucket_labels.is_empty() {
+                        return;
+                    }
+
+                    runtime.observe_workers(
+                        |worker_idx| {
+                            (0..runtime.histogram_bucket_values.len())
+                                .map(|bucket_idx| {
+                                    runtime
+                                        .metrics
+                                        .poll_time_histogram_bucket_count(worker_idx, bucket_idx)
+                                })
+                                .sum()
+                        },
+                        u64::saturating_add,
+                        |value, labels| instrument.observe(value, labels),
+                    );
+                },
+            );
+        })
+        .build();
+}
+
+#[cfg(all(
+    tokio_unstable,
+    not(all(target_arch = "wasm32", target_os = "unknown")),
+    feature = "histograms"
+))]
+fn register_poll_time_sum_counter(meter: &Meter) {
+    meter
+        .f64_observable_counter("tokio.worker.poll_time_sum")
+        .with_description(
+            "An estimate of the total poll time counted in the poll time histogram, \
+             in the unit of its buckets",
+        )
+        // We don't set a unit here, as it would add it as a suffix to the metric name
+        .with_callback(|instrument| {
+            observe_runtimes(
+                "tokio.worker.poll_time_sum",
+                instrument,
+                |instrument, runtime| {
+                    if runtime.histogram_bucket_labels.is_empty() {
+                        return;
+                    }
+
+                    runtime.observe_workers(
+                        |worker_idx| {
+                            runtime
+                                .histogram_bucket_values
+                                .iter()
+                                .enumerate()
+                                .map(|(bucket_idx, value)| {
+                                    let count = runtime
+                                        .metrics
+                                        .poll_time_histogram_bucket_count(worker_idx, bucket_idx);
+                                    // This is an estimate anyway, precision loss is fine
+                                    #[expect(clippy::cast_precision_loss)]
+                                    let count = count as f64;
+                                    count * value
+                                })
+                                .sum()
+                        },
+                        |a, b| a + b,
+                        |value, labels| instrument.observe(value, labels),
+                    );
+                },
+            );
         })
         .build();
 }
@@ -0,0 +1,480 @@
+//! Alerts raised when the runtime metrics cross thresholds.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use opentelemetry::Key;
+use opentelemetry::KeyValue;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use tokio::runtime::RuntimeMetrics;
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use super::TrackedRuntime;
+
+/// An alert raised when a runtime metric stays above a threshold, see
+/// [`Config::with_alert`](crate::Config::with_alert).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use opentelemetry_instrumentation_tokio::Alert;
+///
+/// let alert = Alert::global_queue_depth_above(1000).sustained_for(Duration::from_secs(30));
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), expect(dead_code))]
+pub struct Alert {
+    condition: AlertCondition,
+    threshold: f64,
+    sustained_for: Duration,
+}
+
+/// The metric an [`Alert`] watches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AlertCondition {
+    GlobalQueueDepth,
+    AliveTasks,
+    BusyRatio,
+    BudgetForcedYields,
+    MeanPollTime,
+    SchedulerLatency,
+}
+
+impl AlertCondition {
+    /// The name of the condition, reported in the `tokio.alert.condition`
+    /// attribute.
+    fn name(self) -> &'static str {
+        match self {
+            Self::GlobalQueueDepth => "global_queue_depth",
+            Self::AliveTasks => "alive_tasks",
+            Self::BusyRatio => "busy_ratio",
+            Self::BudgetForcedYields => "budget_forced_yields",
+            Self::MeanPollTime => "mean_poll_time",
+            Self::SchedulerLatency => "scheduler_latency",
+        }
+    }
+}
+
+impl Alert {
+    #[expect(clippy::cast_precision_loss)]
+    fn new(condition: AlertCondition, threshold: usize) -> Self {
+        Self {
+            condition,
+            threshold: threshold as f64,
+            sustained_for: Duration::ZERO,
+        }
+    }
+
+    /// Alert when more than `max` tasks are in the global queue.
+    #[must_use]
+    pub fn global_queue_depth_above(max: usize) -> Self {
+        Self::new(AlertCondition::GlobalQueueDepth, max)
+    }
+
+    /// Alert when more than `max` tasks are alive.
+    #[must_use]
+    pub fn alive_tasks_above(max: usize) -> Self {
+        Self::new(AlertCondition::AliveTasks, max)
+    }
+
+    /// Alert when the workers were busy for more than the given fraction of
+    /// the time since the previous check, on average.
+    ///
+    /// This never fires on targets without 64-bit atomics, where the busy
+    /// duration of the workers is not available.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is not between 0 and 1.
+    #[must_use]
+    pub fn busy_ratio_above(max: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&max),
+            "the busy ratio threshold must be between 0 and 1"
+        );
+        Self {
+            condition: AlertCondition::BusyRatio,
+            threshold: max,
+            sustained_for: Duration::ZERO,
+        }
+    }
+
+    /// Alert when tasks are forced to yield after exhausting their budget more
+    /// than `max` times per second, which is a sign of tasks hogging the
+    /// workers.
+    ///
+    /// This never fires without `tokio_unstable`, where the number of forced
+    /// yields is not available.
+    #[must_use]
+    pub fn budget_forced_yields_above(max: f64) -> Self {
+        Self {
+            condition: AlertCondition::BudgetForcedYields,
+            threshold: max,
+            sustained_for: Duration::ZERO,
+        }
+    }
+
+    /// Alert when the mean poll time of a worker is above `max`, which is a
+    /// sign of tasks blocking the workers.
+    ///
+    /// The mean poll time of each worker is a moving average, and the alert
+    /// compares the highest one to the threshold. This never fires without
+    /// `tokio_unstable`, where the mean poll time is not available.
+    #[must_use]
+    pub fn mean_poll_time_above(max: Duration) -> Self {
+        Self {
+            condition: AlertCondition::MeanPollTime,
+            threshold: max.as_secs_f64(),
+            sustained_for: Duration::ZERO,
+        }
+    }
+
+    /// Alert when the scheduler latency measured by the scheduler probe is
+    /// above `max`.
+    ///
+    /// The alert compares the highest latency measured since the previous
+    /// check to the threshold, or how long the task of the probe has been
+    /// waiting to run if it didn't run yet. This never fires unless the probe
+    /// is enabled with
+    /// [`Config::with_scheduler_probe`](crate::Config::with_scheduler_probe).
+    #[must_use]
+    pub fn scheduler_latency_above(max: Duration) -> Self {
+        Self {
+            condition: AlertCondition::SchedulerLatency,
+            threshold: max.as_secs_f64(),
+            sustained_for: Duration::ZERO,
+        }
+    }
+
+    /// Only raise the alert once the threshold was crossed for at least the
+    /// given duration, instead of on the first check crossing it.
+    ///
+    /// The thresholds are checked at the sampling interval, see
+    /// [`Config::with_sampling_interval`](crate::Config::with_sampling_interval),
+    /// so shorter durations are rounded up to it.
+    #[must_use]
+    pub fn sustained_for(mut self, duration: Duration) -> Self {
+        self.sustained_for = duration;
+        self
+    }
+}
+
+/// An alert raised on a runtime, passed to the hook set with
+/// [`Config::on_anomaly`](crate::Config::on_anomaly).
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    condition: AlertCondition,
+    threshold: f64,
+    value: f64,
+    labels: Vec<KeyValue>,
+}
+
+impl Anomaly {
+    /// The name of the condition of the alert, e.g. `mean_poll_time`, as
+    /// reported in the `tokio.alert.condition` attribute.
+    #[must_use]
+    pub fn condition(&self) -> &'static str {
+        self.condition.name()
+    }
+
+    /// The threshold of the alert, in seconds for the durations.
+    #[must_use]
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// The value which crossed the threshold, in seconds for the durations.
+    #[must_use]
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    /// The labels of the runtime.
+    #[must_use]
+    pub fn labels(&self) -> &[KeyValue] {
+        &self.labels
+    }
+}
+
+/// The state of an [`Alert`] for a runtime.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct AlertState {
+    alert: Alert,
+
+    /// Since when the threshold is crossed, if it is
+    crossed_since: Option<Instant>,
+
+    /// Whether the alert was raised, and not resolved yet
+    raised: bool,
+}
+
+/// Checks the alerts of a runtime.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub(super) struct AlertChecker {
+    interval: Duration,
+    #[cfg(feature = "logs")]
+    logger: Option<crate::logs::AlertLogger>,
+    anomaly_hook: Option<crate::AnomalyHook>,
+    #[cfg(all(feature = "pprof", unix))]
+    profile_duration: Option<Duration>,
+    state: Mutex<CheckerState>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct CheckerState {
+    /// When the alerts should be checked next
+    next_check: Instant,
+
+    /// When the alerts were last checked
+    last_check: Instant,
+
+    /// The busy duration of each worker as of the last check
+    #[cfg(target_has_atomic = "64")]
+    last_busy_durations: Vec<Duration>,
+
+    /// The number of forced yields as of the last check
+    #[cfg(tokio_unstable)]
+    last_budget_forced_yields: u64,
+
+    alerts: Vec<AlertState>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl AlertChecker {
+    pub(super) fn new(
+        metrics: &RuntimeMetrics,
+        config: &crate::Config,
+        interval: Duration,
+    ) -> Option<Self> {
+        if config.alerts.is_empty() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let alerts = config
+            .alerts
+            .iter()
+            .map(|alert| AlertState {
+                alert: alert.clone(),
+                crossed_since: None,
+                raised: false,
+            })
+            .collect();
+
+        Some(Self {
+            interval,
+            #[cfg(feature = "logs")]
+            logger: config
+                .logger_provider
+                .as_ref()
+                .map(crate::logs::AlertLogger::new),
+            anomaly_hook: config.anomaly_hook.clone(),
+            #[cfg(all(feature = "pprof", unix))]
+            profile_duration: config.anomaly_profile_duration,
+            state: Mutex::new(CheckerState {
+                next_check: now + interval,
+                last_check: now,
+                #[cfg(target_has_atomic = "64")]
+                last_busy_durations: super::busy_durations(metrics).1,
+                #[cfg(tokio_unstable)]
+                last_budget_forced_yields: metrics.budget_forced_yield_count(),
+                alerts,
+            }),
+        })
+    }
+
+    /// Check the alerts of the runtime if it is due, returning when they
+    /// should be checked next.
+    pub(super) fn check_if_due(
+        &self,
+        runtime: &TrackedRuntime,
+        probes: Option<&super::probe::RuntimeProbes>,
+        now: Instant,
+    ) -> Instant {
+        let mut state = crate::recover_lock(self.state.lock(), "alerts");
+        if now < state.next_check {
+            return state.next_check;
+        }
+
+        let mut values = state.read_values(&runtime.metrics, now);
+        values.scheduler_latency = probes
+            .and_then(|probes| probes.take_scheduler_latency(now))
+            .map(|latency| latency.as_secs_f64());
+        for alert in &mut state.alerts {
+            let Some(value) = values.get(alert.alert.condition) else {
+                continue;
+            };
+
+            if value <= alert.alert.threshold {
+                alert.crossed_since = None;
+                if alert.raised {
+                    alert.raised = false;
+                    self.emit(runtime, &alert.alert, value, false);
+                }
+                continue;
+            }
+
+            let crossed_since = *alert.crossed_since.get_or_insert(now);
+            if !alert.raised
+                && now.saturating_duration_since(crossed_since) >= alert.alert.sustained_for
+            {
+                alert.raised = true;
+                self.emit(runtime, &alert.alert, value, true);
+            }
+        }
+
+        // Don't try to catch up on missed checks
+        state.next_check = (state.next_check + self.interval).max(now);
+        state.next_check
+    }
+
+    /// Report an alert being raised or resolved.
+    fn emit(&self, runtime: &TrackedRuntime, alert: &Alert, value: f64, raised: bool) {
+        let mut attributes = runtime.labels.to_vec();
+        attributes.extend([
+            KeyValue::new(
+                Key::from_static_str("tokio.alert.condition"),
+                alert.condition.name(),
+            ),
+            KeyValue::new(
+                Key::from_static_str("tokio.alert.threshold"),
+                alert.threshold,
+            ),
+            KeyValue::new(Key::from_static_str("tokio.alert.value"), value),
+        ]);
+
+        #[cfg(feature = "logs")]
+        if let Some(logger) = &self.logger {
+            if raised {
+                logger.alert_raised(&attributes);
+            } else {
+                logger.alert_resolved(&attributes);
+            }
+        }
+
+        #[cfg(all(feature = "pprof", unix))]
+        if raised && let (Some(logger), Some(duration)) = (&self.logger, self.profile_duration) {
+            crate::profile::capture(logger.clone(), duration, attributes.clone());
+        }
+
+        #[cfg(feature = "tracing")]
+        if raised {
+            tracing::warn!(
+                condition = alert.condition.name(),
+                threshold = alert.threshold,
+                value,
+                runtime = %super::describe_runtime(&runtime.labels),
+                "tokio runtime alert raised",
+            );
+        } else {
+            tracing::info!(
+                condition = alert.condition.name(),
+                threshold = alert.threshold,
+                value,
+                runtime = %super::describe_runtime(&runtime.labels),
+                "tokio runtime alert resolved",
+            );
+        }
+
+        if raised && let Some(hook) = &self.anomaly_hook {
+            hook.call(&Anomaly {
+                condition: alert.condition,
+                threshold: alert.threshold,
+                value,
+                labels: runtime.labels.to_vec(),
+            });
+        }
+
+        #[cfg(not(feature = "logs"))]
+        let _ = attributes;
+    }
+}
+
+/// The values of the watched metrics at a check.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct AlertValues {
+    global_queue_depth: f64,
+    alive_tasks: f64,
+    busy_ratio: Option<f64>,
+    budget_forced_yields: Option<f64>,
+    mean_poll_time: Option<f64>,
+    scheduler_latency: Option<f64>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl AlertValues {
+    fn get(&self, condition: AlertCondition) -> Option<f64> {
+        match condition {
+            AlertCondition::GlobalQueueDepth => Some(self.global_queue_depth),
+            AlertCondition::AliveTasks => Some(self.alive_tasks),
+            AlertCondition::BusyRatio => self.busy_ratio,
+            AlertCondition::BudgetForcedYields => self.budget_forced_yields,
+            AlertCondition::MeanPollTime => self.mean_poll_time,
+            AlertCondition::SchedulerLatency => self.scheduler_latency,
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+impl CheckerState {
+    /// Read the values of the watched metrics, computing the rates since the
+    /// last check.
+    #[expect(clippy::cast_precision_loss)]
+    fn read_values(&mut self, metrics: &super::cache::CachedMetrics, now: Instant) -> AlertValues {
+        let elapsed = now.saturating_duration_since(self.last_check);
+        self.last_check = now;
+
+        #[cfg(target_has_atomic = "64")]
+        let busy_ratio = {
+            let (_, busy_durations) = super::busy_durations(metrics);
+            let busy: Duration = busy_durations
+                .iter()
+                .zip(&self.last_busy_durations)
+                .map(|(busy, last_busy)| busy.saturating_sub(*last_busy))
+                .sum();
+            let workers = busy_durations.len();
+            self.last_busy_durations = busy_durations;
+
+            // The busy duration is only updated when the worker parks, so it
+            // can be slightly ahead of the wall-clock time
+            (!elapsed.is_zero() && workers > 0)
+                .then(|| (busy.as_secs_f64() / elapsed.as_secs_f64() / workers as f64).min(1.0))
+        };
+        #[cfg(not(target_has_atomic = "64"))]
+        let busy_ratio = None;
+
+        #[cfg(tokio_unstable)]
+        let budget_forced_yields = {
+            let count = metrics.budget_forced_yield_count();
+            let delta = count.saturating_sub(self.last_budget_forced_yields);
+            self.last_budget_forced_yields = count;
+            (!elapsed.is_zero()).then(|| delta as f64 / elapsed.as_secs_f64())
+        };
+        #[cfg(not(tokio_unstable))]
+        let budget_forced_yields = None;
+
+        #[cfg(tokio_unstable)]
+        let mean_poll_time = (0..metrics.num_workers())
+            .map(|worker_idx| metrics.worker_mean_poll_time(worker_idx))
+            .max()
+            .map(|mean_poll_time| mean_poll_time.as_secs_f64());
+        #[cfg(not(tokio_unstable))]
+        let mean_poll_time = None;
+
+        AlertValues {
+            global_queue_depth: metrics.global_queue_depth() as f64,
+            alive_tasks: metrics.num_alive_tasks() as f64,
+            busy_ratio,
+            budget_forced_yields,
+            mean_poll_time,
+            // Measured by the probes, see `AlertChecker::check_if_due`
+            scheduler_latency: None,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+//! Building runtimes which are observed right away.
+
+use std::io;
+
+use tokio::runtime::{Builder, Runtime};
+
+use super::ObservationGuard;
+use crate::Config;
+
+/// The names Tokio gives to the threads of a runtime by default, depending on
+/// its version.
+const DEFAULT_THREAD_NAMES: [&str; 2] = ["tokio-rt-worker", "tokio-runtime-worker"];
+
+/// Extension trait to build runtimes which are observed right away.
+pub trait RuntimeBuilderExt {
+    /// Build the runtime, and observe it with the given configuration.
+    ///
+    /// If the name of the threads of the runtime was set with
+    /// [`Builder::thread_name`] or [`Builder::thread_name_fn`], it is added
+    /// as the `tokio.thread.name_prefix` label, without any trailing number,
+    /// so that the metrics line up with the threads shown by e.g. `top -H`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime can't be built, or if it can't be
+    /// observed, in which case the error wraps an
+    /// [`ObserveError`](crate::ObserveError).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::{Config, RuntimeBuilderExt};
+    ///
+    /// let (runtime, _guard) = tokio::runtime::Builder::new_multi_thread()
+    ///     .thread_name("ingest")
+    ///     .enable_all()
+    ///     .build_observed(Config::new())?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    fn build_observed(&mut self, config: Config) -> io::Result<(Runtime, ObservationGuard)>;
+}
+
+impl RuntimeBuilderExt for Builder {
+    fn build_observed(&mut self, mut config: Config) -> io::Result<(Runtime, ObservationGuard)> {
+        let runtime = self.build()?;
+
+        if let Some(prefix) = thread_name_prefix(&runtime) {
+            config = config.with_label("tokio.thread.name_prefix", prefix);
+        }
+
+        let guard = config
+            .observe_runtime(runtime.handle())
+            .map_err(io::Error::other)?;
+        Ok((runtime, guard))
+    }
+}
+
+/// Get the prefix of the names of the threads of a runtime, if it was set.
+fn thread_name_prefix(runtime: &Runtime) -> Option<String> {
+    // The name of the threads can't be read back from the builder, but the
+    // threads of the blocking pool get the same names as the workers
+    let (tx, rx) = std::sync::mpsc::channel();
+    runtime.spawn_blocking(move || {
+        let _ = tx.send(std::thread::current().name().map(str::to_owned));
+    });
+    let name = rx.recv().ok().flatten()?;
+
+    let prefix = name
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end_matches(['-', '_', '.', ' ']);
+    (!prefix.is_empty() && !DEFAULT_THREAD_NAMES.contains(&prefix)).then(|| prefix.to_owned())
+}
@@ -0,0 +1,275 @@
+//! Caching of the runtime metrics across collections.
+//!
+//! With several metric readers, e.g. a Prometheus exporter and a periodic
+//! OTLP exporter, each of them runs the callbacks of the observable
+//! instruments, reading the runtime metrics again. With
+//! [`Config::with_collection_cache`](crate::Config::with_collection_cache),
+//! the values read from the runtime are kept for a short time, and reused by
+//! the collections happening in the meantime.
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::collections::HashMap;
+use std::ops::Deref;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::Instant;
+
+use tokio::runtime::RuntimeMetrics;
+
+/// The metrics of a runtime, with the values read on collection optionally
+/// cached.
+///
+/// This dereferences to the underlying [`RuntimeMetrics`], and shadows the
+/// methods reading values which change over time with cached variants. The
+/// other methods, e.g. [`RuntimeMetrics::num_workers`], go straight to the
+/// runtime.
+pub(super) struct CachedMetrics {
+    metrics: RuntimeMetrics,
+
+    /// The number of tasks spawned by this crate on the runtime, which are
+    /// not reported in the alive tasks
+    internal_tasks: AtomicUsize,
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    cache: Option<Cache>,
+}
+
+/// The values read from the runtime since the start of the current cache
+/// period.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct Cache {
+    staleness: Duration,
+    values: Mutex<CachedValues>,
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+struct CachedValues {
+    /// When the first value of the current period was read
+    read_at: Instant,
+
+    /// The values, by method and arguments
+    values: HashMap<(&'static str, [usize; 2]), u64>,
+}
+
+impl Deref for CachedMetrics {
+    type Target = RuntimeMetrics;
+
+    fn deref(&self) -> &Self::Target {
+        &self.metrics
+    }
+}
+
+impl CachedMetrics {
+    /// Wrap the metrics of a runtime, caching the values for the given
+    /// duration, if any.
+    pub(super) fn new(metrics: RuntimeMetrics, staleness: Option<Duration>) -> Self {
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        let _ = staleness;
+
+        Self {
+            metrics,
+            internal_tasks: AtomicUsize::new(0),
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            cache: staleness.map(|staleness| Cache {
+                staleness,
+                values: Mutex::new(CachedValues {
+                    read_at: Instant::now(),
+                    values: HashMap::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Read a value, or get it from the cache if it was read recently.
+    #[cfg_attr(
+        all(target_arch = "wasm32", target_os = "unknown"),
+        expect(clippy::unused_self)
+    )]
+    fn cached(&self, method: &'static str, args: [usize; 2], read: impl FnOnce() -> u64) -> u64 {
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Some(cache) = &self.cache {
+            let mut values = crate::recover_lock(cache.values.lock(), "metrics cache");
+            let now = Instant::now();
+            if now.saturating_duration_since(values.read_at) > cache.staleness {
+                values.read_at = now;
+                values.values.clear();
+            }
+
+            return *values.values.entry((method, args)).or_insert_with(read);
+        }
+
+        #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+        let _ = (method, args);
+
+        read()
+    }
+
+    /// Read a `usize` value through the cache.
+    fn cached_usize(
+        &self,
+        method: &'static str,
+        args: [usize; 2],
+        read: impl FnOnce() -> usize,
+    ) -> usize {
+        let value = self.cached(method, args, || u64::try_from(read()).unwrap_or(u64::MAX));
+        usize::try_from(value).unwrap_or(usize::MAX)
+    }
+
+    pub(super) fn num_alive_tasks(&self) -> usize {
+        self.cached_usize("num_alive_tasks", [0, 0], || {
+            self.metrics
+                .num_alive_tasks()
+                .saturating_sub(self.internal_tasks.load(Ordering::Relaxed))
+        })
+    }
+
+    /// Count a task spawned by this crate on the runtime, which is not
+    /// reported in the alive tasks.
+    pub(super) fn add_internal_task(&self) {
+        self.internal_tasks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Stop counting a task spawned by this crate on the runtime, once it is
+    /// dropped.
+    pub(super) fn remove_internal_task(&self) {
+        self.internal_tasks.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn global_queue_depth(&self) -> usize {
+        self.cached_usize("global_queue_depth", [0, 0], || {
+            self.metrics.global_queue_depth()
+        })
+    }
+
+    #[cfg(target_has_atomic = "64")]
+    pub(super) fn worker_park_count(&self, worker: usize) -> u64 {
+        self.cached("worker_park_count", [worker, 0], || {
+            self.metrics.worker_park_count(worker)
+        })
+    }
+
+    #[cfg(all(target_has_atomic = "64", feature = "worker-metrics"))]
+    pub(super) fn worker_park_unpark_count(&self, worker: usize) -> u64 {
+        self.cached("worker_park_unpark_count", [worker, 0], || {
+            self.metrics.worker_park_unpark_count(worker)
+        })
+    }
+
+    // Tokio can't measure time on wasm32-unknown-unknown
+    #[cfg(all(
+        target_has_atomic = "64",
+        not(all(target_arch = "wasm32", target_os = "unknown"))
+    ))]
+    pub(super) fn worker_total_busy_duration(&self, worker: usize) -> Duration {
+        let nanos = self.cached("worker_total_busy_duration", [worker, 0], || {
+            let busy_duration = self.metrics.worker_total_busy_duration(worker);
+            busy_duration.as_nanos().try_into().unwrap_or(u64::MAX)
+        });
+        Duration::from_nanos(nanos)
+    }
+}
+
+#[cfg(tokio_unstable)]
+impl CachedMetrics {
+    pub(super) fn num_blocking_threads(&self) -> usize {
+        self.cached_usize("num_blocking_threads", [0, 0], || {
+            self.metrics.num_blocking_threads()
+        })
+    }
+
+    pub(super) fn num_idle_blocking_threads(&self) -> usize {
+        self.cached_usize("num_idle_blocking_threads", [0, 0], || {
+            self.metrics.num_idle_blocking_threads()
+        })
+    }
+
+    pub(super) fn blocking_queue_depth(&self) -> usize {
+        self.cached_usize("blocking_queue_depth", [0, 0], || {
+            self.metrics.blocking_queue_depth()
+        })
+    }
+
+    pub(super) fn spawned_tasks_count(&self) -> u64 {
+        self.cached("spawned_tasks_count", [0, 0], || {
+            self.metrics.spawned_tasks_count()
+        })
+    }
+
+    pub(super) fn remote_schedule_count(&self) -> u64 {
+        self.cached("remote_schedule_count", [0, 0], || {
+            self.metrics.remote_schedule_count()
+        })
+    }
+
+    pub(super) fn budget_forced_yield_count(&self) -> u64 {
+        self.cached("budget_forced_yield_count", [0, 0], || {
+            self.metrics.budget_forced_yield_count()
+        })
+    }
+
+    #[cfg(feature = "worker-metrics")]
+    pub(super) fn worker_noop_count(&self, worker: usize) -> u64 {
+        self.cached("worker_noop_count", [worker, 0], || {
+            self.metrics.worker_noop_count(worker)
+        })
+    }
+
+    pub(super) fn worker_steal_count(&self, worker: usize) -> u64 {
+        self.cached("worker_steal_count", [worker, 0], || {
+            self.metrics.worker_steal_count(worker)
+        })
+    }
+
+    #[cfg(feature = "worker-metrics")]
+    pub(super) fn worker_steal_operations(&self, worker: usize) -> u64 {
+        self.cached("worker_steal_operations", [worker, 0], || {
+            self.metrics.worker_steal_operations(worker)
+        })
+    }
+
+    pub(super) fn worker_poll_count(&self, worker: usize) -> u64 {
+        self.cached("worker_poll_count", [worker, 0], || {
+            self.metrics.worker_poll_count(worker)
+        })
+    }
+
+    #[cfg(feature = "worker-metrics")]
+    pub(super) fn worker_local_schedule_count(&self, worker: usize) -> u64 {
+        self.cached("worker_local_schedule_count", [worker, 0], || {
+            self.metrics.worker_local_schedule_count(worker)
+        })
+    }
+
+    #[cfg(feature = "worker-metrics")]
+    pub(super) fn worker_overflow_count(&self, worker: usize) -> u64 {
+        self.cached("worker_overflow_count", [worker, 0], || {
+            self.metrics.worker_overflow_count(worker)
+        })
+    }
+
+    pub(super) fn worker_local_queue_depth(&self, worker: usize) -> usize {
+        self.cached_usize("worker_local_queue_depth", [worker, 0], || {
+            self.metrics.worker_local_queue_depth(worker)
+        })
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(super) fn poll_time_histogram_bucket_count(&self, worker: usize, bucket: usize) -> u64 {
+        self.cached("poll_time_histogram_bucket_count", [worker, bucket], || {
+            self.metrics
+                .poll_time_histogram_bucket_count(worker, bucket)
+        })
+    }
+
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub(super) fn worker_mean_poll_time(&self, worker: usize) -> Duration {
+        let nanos = self.cached("worker_mean_poll_time", [worker, 0], || {
+            let mean_poll_time = self.metrics.worker_mean_poll_time(worker);
+            mean_poll_time.as_nanos().try_into().unwrap_or(u64::MAX)
+        });
+        Duration::from_nanos(nanos)
+    }
+}
@@ -0,0 +1,90 @@
+//! Skipping the per-worker observations which didn't change since the
+//! previous collection.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A value observed for a per-worker metric, which can be compared with its
+/// previous observation.
+pub(super) trait Observation: Copy {
+    /// The bits of the value, equal for equal observations.
+    fn bits(self) -> u64;
+}
+
+impl Observation for u64 {
+    fn bits(self) -> u64 {
+        self
+    }
+}
+
+impl Observation for f64 {
+    fn bits(self) -> u64 {
+        self.to_bits()
+    }
+}
+
+/// The last observed values of the per-worker metrics of a runtime, see
+/// [`Config::with_change_detection`](crate::Config::with_change_detection).
+pub(super) struct ChangeDetection {
+    refresh_every: u32,
+    metrics: Mutex<HashMap<&'static str, MetricCache>>,
+}
+
+/// The last observed values of a per-worker metric.
+#[derive(Default)]
+struct MetricCache {
+    /// The number of collections since the last full refresh
+    collections: u32,
+
+    /// The bits of the last observed value, for each worker series
+    last_values: Vec<Option<u64>>,
+}
+
+impl ChangeDetection {
+    pub(super) fn new(refresh_every: u32) -> Self {
+        Self {
+            refresh_every,
+            metrics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run a collection of a per-worker metric, with a filter of the
+    /// observations which changed since the previous collection.
+    pub(super) fn collect<R>(
+        &self,
+        metric: &'static str,
+        collect: impl FnOnce(&mut ChangeFilter<'_>) -> R,
+    ) -> R {
+        let mut metrics = crate::recover_lock(self.metrics.lock(), "change detection");
+        let cache = metrics.entry(metric).or_default();
+        let refresh = cache.collections == 0;
+        cache.collections = (cache.collections + 1) % self.refresh_every;
+
+        collect(&mut ChangeFilter { cache, refresh })
+    }
+}
+
+/// Tells which observations of a per-worker metric changed since the previous
+/// collection, see [`ChangeDetection::collect`].
+pub(super) struct ChangeFilter<'a> {
+    cache: &'a mut MetricCache,
+
+    /// Whether all the observations are forwarded in this collection
+    refresh: bool,
+}
+
+impl ChangeFilter<'_> {
+    /// Whether the observation of the given series should be forwarded,
+    /// either because it changed, or on a full refresh.
+    pub(super) fn forward(&mut self, series_idx: usize, value: impl Observation) -> bool {
+        let last_values = &mut self.cache.last_values;
+        if last_values.len() <= series_idx {
+            last_values.resize(series_idx + 1, None);
+        }
+
+        let bits = Some(value.bits());
+        let changed = last_values[series_idx] != bits;
+        last_values[series_idx] = bits;
+        self.refresh || changed
+    }
+}
@@ -0,0 +1,74 @@
+//! Threads observing the tracked runtimes concurrently on collection.
+//!
+//! With [`Config::with_collection_threads`](crate::Config::with_collection_threads),
+//! the runtimes are split in chunks when there are many of them, and all the
+//! chunks but one are observed by the threads of this pool while the
+//! collecting thread observes the remaining one. The threads are spawned on
+//! the first collection needing them, and kept for the following ones.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::thread;
+
+/// The number of tracked runtimes from which they are observed concurrently.
+///
+/// Observing a runtime only takes a few reads of its metrics, so below this,
+/// handing the runtimes over to other threads costs more than it saves.
+pub(super) const PARALLEL_COLLECTION_THRESHOLD: usize = 16;
+
+/// Some work to run on one of the threads of the pool.
+pub(super) type Job = Box<dyn FnOnce() + Send>;
+
+/// The threads of the pool, taking their jobs from a shared queue.
+struct Pool {
+    jobs: Sender<Job>,
+    queue: Arc<Mutex<Receiver<Job>>>,
+    threads: Mutex<usize>,
+}
+
+static POOL: LazyLock<Pool> = LazyLock::new(|| {
+    let (jobs, queue) = mpsc::channel();
+    Pool {
+        jobs,
+        queue: Arc::new(Mutex::new(queue)),
+        threads: Mutex::new(0),
+    }
+});
+
+/// Spawn threads in the pool until it has the given number of threads,
+/// returning the number of threads it has, which is lower if some of them
+/// could not be spawned.
+pub(super) fn ensure_threads(count: usize) -> usize {
+    let mut threads = crate::recover_lock(POOL.threads.lock(), "collection threads");
+    while *threads < count {
+        let queue = Arc::clone(&POOL.queue);
+        let spawned = thread::Builder::new()
+            .name("otel-tokio-collect".to_owned())
+            .spawn(move || run(&queue));
+        if spawned.is_err() {
+            break;
+        }
+        *threads += 1;
+    }
+    *threads
+}
+
+/// Queue a job for the threads of the pool, which must have been spawned
+/// with [`ensure_threads`].
+pub(super) fn execute(job: Job) {
+    // The queue lives as long as the threads of the pool, which never stop
+    let _ = POOL.jobs.send(job);
+}
+
+/// Run the jobs of the pool, one after the other.
+fn run(queue: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = crate::recover_lock(queue.lock(), "collection queue").recv();
+        let Ok(job) = job else {
+            return;
+        };
+        // The panics of the runtimes are already caught while observing them,
+        // this only keeps the thread alive for the next jobs
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+    }
+}
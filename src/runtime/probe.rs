@@ -0,0 +1,293 @@
+//! Probes measuring how long a runtime takes to run the work it is given.
+//!
+//! The gauges exported for a runtime tell how much work is queued, but not
+//! how far behind the runtime is running. The probes periodically give the
+//! runtime some trivial work, and record how long it took to get to it.
+
+#[cfg(feature = "time")]
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use opentelemetry::KeyValue;
+use opentelemetry::metrics::{Histogram, Meter};
+use tokio::runtime::Handle;
+
+use super::TrackedRuntime;
+use crate::Config;
+
+/// How long the timer probe sleeps for.
+///
+/// The timer wheel has a granularity of a millisecond, so this is the
+/// shortest sleep which actually goes through the timer driver.
+#[cfg(feature = "time")]
+const TIMER_PROBE_SLEEP: Duration = Duration::from_millis(1);
+
+/// The lag of the event loop of `current_thread` runtimes, see
+/// [`monitor_event_loop`].
+#[cfg(feature = "time")]
+static EVENT_LOOP_LAG: LazyLock<Histogram<f64>> = LazyLock::new(|| {
+    crate::meter()
+        .f64_histogram("tokio.event_loop.lag")
+        .with_description(
+            "The time between a tick of the event loop monitor being due and it running",
+        )
+        .with_unit("s")
+        .build()
+});
+
+/// The latency histograms the probes record into.
+pub(super) struct ProbeInstruments {
+    scheduler: Histogram<f64>,
+    #[cfg(feature = "time")]
+    timer: Histogram<f64>,
+    blocking_pool: Histogram<f64>,
+}
+
+impl ProbeInstruments {
+    pub(super) fn new(meter: &Meter) -> Self {
+        Self {
+            scheduler: meter
+                .f64_histogram("tokio.scheduler.latency")
+                .with_description(
+                    "The time between spawning a task on the runtime and the task being polled",
+                )
+                .with_unit("s")
+                .build(),
+            #[cfg(feature = "time")]
+            timer: meter
+                .f64_histogram("tokio.timer.latency")
+                .with_description(
+                    "The time between the deadline of a sleep and the task sleeping resuming",
+                )
+                .with_unit("s")
+                .build(),
+            blocking_pool: meter
+                .f64_histogram("tokio.blocking_pool.latency")
+                .with_description(
+                    "The time between spawning a blocking closure on the runtime and the closure starting",
+                )
+                .with_unit("s")
+                .build(),
+        }
+    }
+}
+
+/// What a probe gives the runtime to do.
+#[derive(Debug, Clone, Copy)]
+enum ProbeKind {
+    /// A no-op task, see
+    /// [`Config::with_scheduler_probe`](crate::Config::with_scheduler_probe)
+    Scheduler,
+
+    /// A task sleeping, see
+    /// [`Config::with_timer_probe`](crate::Config::with_timer_probe)
+    #[cfg(feature = "time")]
+    Timer,
+
+    /// A no-op blocking closure, see
+    /// [`Config::with_blocking_pool_probe`](crate::Config::with_blocking_pool_probe)
+    BlockingPool,
+}
+
+/// The state of a probe for a runtime.
+struct Probe {
+    kind: ProbeKind,
+    interval: Duration,
+
+    /// When the probe should run next
+    next_run: Instant,
+
+    /// Whether the work given to the runtime by the previous probe didn't run
+    /// yet
+    pending: Arc<AtomicBool>,
+
+    /// When the work was last given to the runtime
+    launched_at: Instant,
+
+    /// The highest latency measured since it was last taken, see
+    /// [`RuntimeProbes::take_scheduler_latency`]
+    max_latency: Arc<Mutex<Option<Duration>>>,
+}
+
+/// Periodically probes a runtime.
+pub(super) struct RuntimeProbes {
+    handle: Handle,
+    probes: Mutex<Vec<Probe>>,
+}
+
+impl RuntimeProbes {
+    /// Create the probes of a runtime, if any are enabled.
+    pub(super) fn new(handle: &Handle, config: &Config) -> Option<Self> {
+        let now = Instant::now();
+        let probe = |kind, interval: Option<Duration>| {
+            interval.map(|interval| Probe {
+                kind,
+                interval,
+                next_run: now + interval,
+                pending: Arc::new(AtomicBool::new(false)),
+                launched_at: now,
+                max_latency: Arc::new(Mutex::new(None)),
+            })
+        };
+
+        let probes: Vec<Probe> = [
+            probe(ProbeKind::Scheduler, config.scheduler_probe_interval),
+            #[cfg(feature = "time")]
+            probe(ProbeKind::Timer, config.timer_probe_interval),
+            probe(ProbeKind::BlockingPool, config.blocking_pool_probe_interval),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        (!probes.is_empty()).then(|| Self {
+            handle: handle.clone(),
+            probes: Mutex::new(probes),
+        })
+    }
+
+    /// Run the probes of the runtime which are due, returning when the next
+    /// one is due.
+    pub(super) fn probe_if_due(
+        &self,
+        runtime: &TrackedRuntime,
+        now: Instant,
+        instruments: &ProbeInstruments,
+    ) -> Instant {
+        let mut probes = crate::recover_lock(self.probes.lock(), "probes");
+        let mut labels = None;
+
+        for probe in probes.iter_mut().filter(|probe| now >= probe.next_run) {
+            let labels = labels.get_or_insert_with(|| {
+                super::with_labels(&runtime.labels, &runtime.dynamic_labels()).into_owned()
+            });
+            self.launch(probe, instruments, labels.clone());
+
+            // Don't try to catch up on missed probes
+            probe.next_run = (probe.next_run + probe.interval).max(now);
+        }
+
+        probes
+            .iter()
+            .map(|probe| probe.next_run)
+            .min()
+            .unwrap_or(now)
+    }
+
+    /// The highest scheduler latency measured since the previous call, or how
+    /// long the task of the probe has been waiting to run if it is longer.
+    ///
+    /// This is `None` if the scheduler probe is disabled, or if it didn't
+    /// measure anything since the previous call.
+    pub(super) fn take_scheduler_latency(&self, now: Instant) -> Option<Duration> {
+        let probes = crate::recover_lock(self.probes.lock(), "probes");
+        let probe = probes
+            .iter()
+            .find(|probe| matches!(probe.kind, ProbeKind::Scheduler))?;
+
+        let measured = crate::recover_lock(probe.max_latency.lock(), "probe latency").take();
+        let waiting = probe
+            .pending
+            .load(Ordering::Acquire)
+            .then(|| now.saturating_duration_since(probe.launched_at));
+        measured.max(waiting)
+    }
+
+    /// Give the runtime the work of a probe, recording how long it took to
+    /// run.
+    ///
+    /// While the work given by the previous probe didn't run yet, nothing
+    /// else is given, so that a stalled runtime doesn't pile up probes. Its
+    /// latency is recorded once it eventually runs.
+    fn launch(&self, probe: &mut Probe, instruments: &ProbeInstruments, labels: Vec<KeyValue>) {
+        if probe.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        probe.launched_at = Instant::now();
+
+        match probe.kind {
+            ProbeKind::Scheduler => {
+                let record = recorder(probe, &instruments.scheduler, labels);
+                let spawned_at = Instant::now();
+                // The task is dropped without running if the runtime is shut
+                // down, in which case it isn't probed anymore
+                drop(
+                    self.handle
+                        .spawn(async move { record(spawned_at.elapsed()) }),
+                );
+            }
+            #[cfg(feature = "time")]
+            ProbeKind::Timer => {
+                let record = recorder(probe, &instruments.timer, labels);
+                drop(self.handle.spawn(async move {
+                    let deadline = Instant::now() + TIMER_PROBE_SLEEP;
+                    tokio::time::sleep_until(deadline.into()).await;
+                    record(deadline.elapsed());
+                }));
+            }
+            ProbeKind::BlockingPool => {
+                let record = recorder(probe, &instruments.blocking_pool, labels);
+                let spawned_at = Instant::now();
+                drop(
+                    self.handle
+                        .spawn_blocking(move || record(spawned_at.elapsed())),
+                );
+            }
+        }
+    }
+}
+
+/// Build the function recording the latency measured by a probe, and marking
+/// it as no longer pending.
+fn recorder(
+    probe: &Probe,
+    histogram: &Histogram<f64>,
+    labels: Vec<KeyValue>,
+) -> impl FnOnce(Duration) + Send + 'static {
+    let histogram = histogram.clone();
+    let pending = Arc::clone(&probe.pending);
+    let max_latency = Arc::clone(&probe.max_latency);
+    move |latency| {
+        histogram.record(latency.as_secs_f64(), &labels);
+        let mut max_latency = crate::recover_lock(max_latency.lock(), "probe latency");
+        *max_latency = (*max_latency).max(Some(latency));
+        pending.store(false, Ordering::Release);
+    }
+}
+
+/// Spawn a task ticking every `interval` on a `current_thread` runtime, and
+/// recording how late each tick runs, see
+/// [`Config::with_event_loop_lag`](crate::Config::with_event_loop_lag).
+///
+/// The task stops once the runtime isn't tracked anymore.
+#[cfg(feature = "time")]
+pub(super) fn monitor_event_loop(
+    handle: &Handle,
+    runtime: &Arc<TrackedRuntime>,
+    interval: Duration,
+) {
+    let runtime = Arc::downgrade(runtime);
+    drop(handle.spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        // After a stall, measure the next ticks from the end of the stall
+        // instead of reporting every missed tick
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately
+        ticks.tick().await;
+
+        loop {
+            let due = ticks.tick().await;
+            let lag = due.elapsed();
+
+            let Some(runtime) = runtime.upgrade() else {
+                break;
+            };
+            EVENT_LOOP_LAG.record(
+                lag.as_secs_f64(),
+                &super::with_labels(&runtime.labels, &runtime.dynamic_labels()),
+            );
+        }
+    }));
+}
@@ -0,0 +1,679 @@
+//! Background sampling of the runtime metrics.
+//!
+//! Most metrics are exported through observable instruments, read by the meter
+//! provider on collection. Histograms can't be observed this way, and
+//! observable counters only expose totals, so instead a background thread
+//! periodically samples the runtimes, computes deltas and records them into
+//! regular instruments.
+
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+#[cfg(tokio_unstable)]
+use opentelemetry::metrics::Histogram;
+use opentelemetry::metrics::{Counter, Meter};
+use tokio::runtime::{Handle, RuntimeMetrics};
+
+use super::TrackedRuntime;
+use crate::{Config, DurationUnit, MetricSet};
+
+/// The sampler thread, once started.
+static SAMPLER_THREAD: OnceLock<Thread> = OnceLock::new();
+
+/// How often the runtimes are sampled, unless set through
+/// [`Config::with_sampling_interval`].
+const DEFAULT_SAMPLING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The maximum number of polls recorded per worker for each sample.
+///
+/// Recording a value in a histogram has a cost, so when more polls happened
+/// since the last sample, the bucket counts are scaled down proportionally.
+/// This keeps the shape of the distribution, but not the absolute count of
+/// polls.
+#[cfg(tokio_unstable)]
+const MAX_SAMPLES_PER_WORKER: u64 = 1000;
+
+/// Start the sampler thread, or wake it up if it is already running so that
+/// it picks up newly tracked runtimes.
+///
+/// # Panics
+///
+/// Panics if the thread can't be spawned.
+pub(super) fn start() {
+    SAMPLER_THREAD
+        .get_or_init(|| {
+            thread::Builder::new()
+                .name("tokio-metrics-sampler".to_owned())
+                .spawn(run)
+                .expect("failed to spawn the metrics sampler thread")
+                .thread()
+                .clone()
+        })
+        .unpark();
+}
+
+/// The sampler loop.
+fn run() {
+    let instruments = SamplerInstruments::new();
+
+    loop {
+        let now = Instant::now();
+        let next_sample = super::read_runtimes()
+            .iter()
+            .filter_map(|runtime| {
+                let sampler = runtime.sampler.as_ref()?;
+                runtime
+                    .collect("sampler", || {
+                        sampler.sample_if_due(runtime, now, &instruments)
+                    })
+                    .flatten()
+            })
+            .min();
+
+        // Tracking a new runtime wakes the thread up
+        match next_sample {
+            Some(next_sample) => {
+                thread::park_timeout(next_sample.saturating_duration_since(Instant::now()));
+            }
+            None => thread::park(),
+        }
+    }
+}
+
+/// Where the value of a sampled counter comes from.
+#[derive(Clone, Copy)]
+enum CounterSource {
+    /// A runtime-level counter
+    #[cfg_attr(not(tokio_unstable), expect(dead_code))]
+    Runtime(fn(&RuntimeMetrics) -> u64),
+
+    /// A per-worker counter
+    #[cfg_attr(not(feature = "worker-metrics"), expect(dead_code))]
+    Worker(fn(&RuntimeMetrics, usize) -> u64),
+
+    /// A per-worker work stealing counter, which is always zero on
+    /// `current_thread` runtimes
+    #[cfg(tokio_unstable)]
+    #[cfg_attr(not(feature = "worker-metrics"), expect(dead_code))]
+    WorkStealing(fn(&RuntimeMetrics, usize) -> u64),
+}
+
+/// A counter sampled from the runtime metrics.
+///
+/// These mirror the observable counters registered in the parent module.
+struct SampledCounter {
+    name: &'static str,
+    description: &'static str,
+    unit: Option<&'static str>,
+    metric_set: MetricSet,
+    source: CounterSource,
+}
+
+const SAMPLED_COUNTERS: &[SampledCounter] = &[
+    #[cfg(all(target_has_atomic = "64", feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.park_count",
+        description: "The total number of times the given worker thread has parked",
+        unit: None,
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Worker(RuntimeMetrics::worker_park_count),
+    },
+    #[cfg(all(target_has_atomic = "64", feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.park_unpark_count",
+        description: "The total number of times the given worker thread has parked and unparked",
+        unit: None,
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Worker(RuntimeMetrics::worker_park_unpark_count),
+    },
+    #[cfg(tokio_unstable)]
+    SampledCounter {
+        name: "tokio.remote_schedules",
+        description: "The number of tasks scheduled from outside the runtime",
+        unit: Some("{task}"),
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Runtime(RuntimeMetrics::remote_schedule_count),
+    },
+    #[cfg(tokio_unstable)]
+    SampledCounter {
+        name: "tokio.budget_forced_yields",
+        description: "The number of times that tasks have been forced to yield back to the scheduler after exhausting their task budgets",
+        unit: Some("{yield}"),
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Runtime(RuntimeMetrics::budget_forced_yield_count),
+    },
+    #[cfg(all(
+        tokio_unstable,
+        not(target_family = "wasm"),
+        target_has_atomic = "64",
+        feature = "io-driver-metrics"
+    ))]
+    SampledCounter {
+        name: "tokio.io_driver.fd_registrations",
+        description: "The number of file descriptors that have been registered with the runtime's I/O driver",
+        unit: Some("{fd}"),
+        metric_set: MetricSet::Full,
+        source: CounterSource::Runtime(RuntimeMetrics::io_driver_fd_registered_count),
+    },
+    #[cfg(all(
+        tokio_unstable,
+        not(target_family = "wasm"),
+        target_has_atomic = "64",
+        feature = "io-driver-metrics"
+    ))]
+    SampledCounter {
+        name: "tokio.io_driver.fd_deregistrations",
+        description: "The number of file descriptors that have been deregistered by the runtime's I/O driver",
+        unit: Some("{fd}"),
+        metric_set: MetricSet::Full,
+        source: CounterSource::Runtime(RuntimeMetrics::io_driver_fd_deregistered_count),
+    },
+    #[cfg(all(
+        tokio_unstable,
+        not(target_family = "wasm"),
+        target_has_atomic = "64",
+        feature = "io-driver-metrics"
+    ))]
+    SampledCounter {
+        name: "tokio.io_driver.fd_readies",
+        description: "The number of ready events processed by the runtime's I/O driver",
+        unit: Some("{event}"),
+        metric_set: MetricSet::Full,
+        source: CounterSource::Runtime(RuntimeMetrics::io_driver_ready_count),
+    },
+    #[cfg(tokio_unstable)]
+    SampledCounter {
+        name: "tokio.spawned_tasks_count",
+        description: "The number of tasks spawned in this runtime since it was created",
+        unit: Some("{task}"),
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Runtime(RuntimeMetrics::spawned_tasks_count),
+    },
+    #[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.noops",
+        description: "The number of times the given worker thread unparked but performed no work before parking again",
+        unit: Some("{operation}"),
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Worker(RuntimeMetrics::worker_noop_count),
+    },
+    #[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.task_steals",
+        description: "The number of tasks the given worker thread stole from another worker thread",
+        unit: None,
+        metric_set: MetricSet::Standard,
+        source: CounterSource::WorkStealing(RuntimeMetrics::worker_steal_count),
+    },
+    #[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.steal_operations",
+        description: "The number of times the given worker thread stole tasks from another worker thread",
+        unit: None,
+        metric_set: MetricSet::Standard,
+        source: CounterSource::WorkStealing(RuntimeMetrics::worker_steal_operations),
+    },
+    #[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.polls",
+        description: "The number of tasks the given worker thread has polled",
+        unit: Some("{task}"),
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Worker(RuntimeMetrics::worker_poll_count),
+    },
+    #[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.local_schedules",
+        description: "The number of tasks scheduled from **within** the runtime on the given worker's local queue",
+        unit: Some("{task}"),
+        metric_set: MetricSet::Standard,
+        source: CounterSource::Worker(RuntimeMetrics::worker_local_schedule_count),
+    },
+    #[cfg(all(tokio_unstable, feature = "worker-metrics"))]
+    SampledCounter {
+        name: "tokio.worker.overflows",
+        description: "The number of times the given worker thread saturated its local queue",
+        unit: None,
+        metric_set: MetricSet::Standard,
+        source: CounterSource::WorkStealing(RuntimeMetrics::worker_overflow_count),
+    },
+];
+
+/// The instruments the sampler records into.
+struct SamplerInstruments {
+    /// One counter for each of [`SAMPLED_COUNTERS`]
+    counters: Vec<Counter<u64>>,
+
+    /// `tokio.worker.busy_duration`, in each of the units it can be reported
+    #[cfg(target_has_atomic = "64")]
+    busy_duration: BusyDurationCounters,
+
+    #[cfg(tokio_unstable)]
+    poll_time: Histogram<f64>,
+
+    probes: super::probe::ProbeInstruments,
+}
+
+impl SamplerInstruments {
+    fn new() -> Self {
+        // The counters are recorded under a separate instrumentation scope, as
+        // they share their names with the observable counters
+        let meter = crate::sampler_meter();
+        let counters = SAMPLED_COUNTERS
+            .iter()
+            .map(|counter| {
+                let builder = meter
+                    .u64_counter(counter.name)
+                    .with_description(counter.description);
+                match counter.unit {
+                    Some(unit) => builder.with_unit(unit).build(),
+                    None => builder.build(),
+                }
+            })
+            .collect();
+
+        Self {
+            counters,
+            #[cfg(target_has_atomic = "64")]
+            busy_duration: BusyDurationCounters::new(&meter),
+            #[cfg(tokio_unstable)]
+            poll_time: crate::meter()
+                .f64_histogram("tokio.worker.poll_time")
+                .with_description("The duration of task polls, sampled from the runtime histogram")
+                .with_unit("s")
+                .build(),
+            probes: super::probe::ProbeInstruments::new(&crate::meter()),
+        }
+    }
+}
+
+/// The `tokio.worker.busy_duration` counter, in each of the units it can be
+/// reported.
+#[cfg(target_has_atomic = "64")]
+struct BusyDurationCounters {
+    nanoseconds: Counter<u64>,
+    milliseconds: Counter<u64>,
+    seconds: Counter<f64>,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl BusyDurationCounters {
+    const DESCRIPTION: &str = "The amount of time the given worker thread has been busy";
+
+    fn new(meter: &Meter) -> Self {
+        let integer = |unit: DurationUnit| {
+            meter
+                .u64_counter("tokio.worker.busy_duration")
+                .with_description(Self::DESCRIPTION)
+                .with_unit(unit.symbol())
+                .build()
+        };
+
+        Self {
+            nanoseconds: integer(DurationUnit::Nanoseconds),
+            milliseconds: integer(DurationUnit::Milliseconds),
+            seconds: meter
+                .f64_counter("tokio.worker.busy_duration")
+                .with_description(Self::DESCRIPTION)
+                .with_unit(DurationUnit::Seconds.symbol())
+                .build(),
+        }
+    }
+
+    /// Record the increase of the busy duration of a worker.
+    fn record(
+        &self,
+        unit: DurationUnit,
+        last: Duration,
+        current: Duration,
+        labels: &[opentelemetry::KeyValue],
+    ) {
+        // Compute integer deltas on the converted totals, so that truncation
+        // errors don't accumulate
+        let integer_delta = || {
+            let metric = "tokio.worker.busy_duration";
+            super::duration_as_u64(current, unit, metric)
+                .saturating_sub(super::duration_as_u64(last, unit, metric))
+        };
+
+        match unit {
+            DurationUnit::Nanoseconds => self.nanoseconds.add(integer_delta(), labels),
+            DurationUnit::Milliseconds => self.milliseconds.add(integer_delta(), labels),
+            DurationUnit::Seconds => self
+                .seconds
+                .add(current.saturating_sub(last).as_secs_f64(), labels),
+        }
+    }
+}
+
+/// Periodically records the metrics of a runtime.
+pub(super) struct RuntimeSampler {
+    interval: Duration,
+
+    /// When the runtime should be sampled next
+    next_sample: Mutex<Instant>,
+
+    /// Records the counters, when enabled through
+    /// [`Config::with_sampling_interval`]
+    counters: Option<CounterSampler>,
+
+    /// Records the poll time histogram, when exported as a histogram
+    /// instrument
+    #[cfg(tokio_unstable)]
+    poll_time: Option<PollTimeSampler>,
+
+    /// Checks the alerts of the runtime, if any were set through
+    /// [`Config::with_alert`]
+    alerts: Option<super::alert::AlertChecker>,
+
+    /// Probes the runtime, when enabled through
+    /// [`Config::with_scheduler_probe`], `Config::with_timer_probe` or
+    /// [`Config::with_blocking_pool_probe`]
+    probes: Option<super::probe::RuntimeProbes>,
+
+    /// Logs a summary of the runtime, when enabled through
+    /// [`Config::with_summary_interval`]
+    #[cfg(feature = "tracing")]
+    summary: Option<super::summary::SummaryLogger>,
+}
+
+impl RuntimeSampler {
+    /// Create the sampler for a runtime, if any of its metrics are recorded by
+    /// the sampler.
+    ///
+    /// The sampler thread should be started with [`start`] once the runtime
+    /// is tracked.
+    pub(super) fn new(handle: &Handle, config: &Config) -> Option<Self> {
+        let metrics = &handle.metrics();
+        let num_workers = metrics.num_workers();
+        let counters = config
+            .sampling_interval
+            .map(|_| CounterSampler::new(num_workers));
+
+        #[cfg(tokio_unstable)]
+        let poll_time = (cfg!(feature = "histograms")
+            && metrics.poll_time_histogram_enabled()
+            && config.poll_time_histogram_mode == crate::PollTimeHistogramMode::Exponential
+            && config.metric_set == MetricSet::Full)
+            .then(|| PollTimeSampler::new(metrics, num_workers));
+
+        let interval = config
+            .sampling_interval
+            .unwrap_or(DEFAULT_SAMPLING_INTERVAL);
+
+        let alerts = super::alert::AlertChecker::new(metrics, config, interval);
+
+        let probes = super::probe::RuntimeProbes::new(handle, config);
+
+        #[cfg(feature = "tracing")]
+        let summary = config
+            .summary_interval
+            .map(|interval| super::summary::SummaryLogger::new(metrics, interval));
+
+        #[cfg(tokio_unstable)]
+        let enabled = counters.is_some() || poll_time.is_some();
+        #[cfg(not(tokio_unstable))]
+        let enabled = counters.is_some();
+        let enabled = enabled || alerts.is_some() || probes.is_some();
+        #[cfg(feature = "tracing")]
+        let enabled = enabled || summary.is_some();
+
+        enabled.then(|| Self {
+            interval,
+            next_sample: Mutex::new(Instant::now() + interval),
+            counters,
+            #[cfg(tokio_unstable)]
+            poll_time,
+            alerts,
+            probes,
+            #[cfg(feature = "tracing")]
+            summary,
+        })
+    }
+
+    /// Whether the counters of the runtime are recorded by the sampler, in
+    /// which case the observable counters should skip it.
+    #[cfg(any(tokio_unstable, feature = "worker-metrics"))]
+    pub(super) fn samples_counters(&self) -> bool {
+        self.counters.is_some()
+    }
+
+    /// Sample the runtime, check its alerts, probe it and log its summary if
+    /// they are due, returning when the sampler should look at it next.
+    fn sample_if_due(
+        &self,
+        runtime: &TrackedRuntime,
+        now: Instant,
+        instruments: &SamplerInstruments,
+    ) -> Option<Instant> {
+        let mut next_due = self.sample_metrics_if_due(runtime, now, instruments);
+        let mut schedule = |next: Instant| {
+            next_due = Some(next_due.map_or(next, |next_due| next_due.min(next)));
+        };
+
+        if let Some(alerts) = &self.alerts {
+            schedule(alerts.check_if_due(runtime, self.probes.as_ref(), now));
+        }
+
+        if let Some(probes) = &self.probes {
+            schedule(probes.probe_if_due(runtime, now, &instruments.probes));
+        }
+
+        #[cfg(feature = "tracing")]
+        if let Some(summary) = &self.summary {
+            schedule(summary.log_if_due(runtime, now));
+        }
+
+        next_due
+    }
+
+    /// Record the sampled metrics of the runtime if they are due, returning
+    /// when they should be sampled next, or `None` if no metric is sampled.
+    fn sample_metrics_if_due(
+        &self,
+        runtime: &TrackedRuntime,
+        now: Instant,
+        instruments: &SamplerInstruments,
+    ) -> Option<Instant> {
+        #[cfg(tokio_unstable)]
+        let sampled = self.counters.is_some() || self.poll_time.is_some();
+        #[cfg(not(tokio_unstable))]
+        let sampled = self.counters.is_some();
+        if !sampled {
+            return None;
+        }
+
+        let mut next_sample = crate::recover_lock(self.next_sample.lock(), "sampler schedule");
+        if now < *next_sample {
+            return Some(*next_sample);
+        }
+
+        if let Some(counters) = &self.counters {
+            counters.sample(runtime, instruments);
+        }
+
+        #[cfg(tokio_unstable)]
+        if let Some(poll_time) = &self.poll_time {
+            poll_time.sample(runtime, &instruments.poll_time);
+        }
+
+        // Don't try to catch up on missed samples
+        *next_sample = (*next_sample + self.interval).max(now);
+        Some(*next_sample)
+    }
+}
+
+/// Converts the runtime counters into deltas recorded in counter instruments.
+struct CounterSampler {
+    /// The values as of the last sample, for each of [`SAMPLED_COUNTERS`]: one
+    /// for runtime-level counters, and one per worker for per-worker counters
+    last_values: Mutex<Vec<Vec<u64>>>,
+
+    /// The busy duration of each worker as of the last sample
+    #[cfg(target_has_atomic = "64")]
+    last_busy_durations: Mutex<Vec<Duration>>,
+}
+
+impl CounterSampler {
+    fn new(num_workers: usize) -> Self {
+        // Start from zero, so that the first sample includes everything which
+        // happened before the runtime was tracked
+        let last_values = SAMPLED_COUNTERS
+            .iter()
+            .map(|counter| {
+                if matches!(counter.source, CounterSource::Runtime(_)) {
+                    vec![0]
+                } else {
+                    vec![0; num_workers]
+                }
+            })
+            .collect();
+
+        Self {
+            last_values: Mutex::new(last_values),
+            #[cfg(target_has_atomic = "64")]
+            last_busy_durations: Mutex::new(vec![Duration::ZERO; num_workers]),
+        }
+    }
+
+    /// Record the increments which happened since the last sample.
+    fn sample(&self, runtime: &TrackedRuntime, instruments: &SamplerInstruments) {
+        let dynamic_labels = runtime.dynamic_labels();
+
+        #[cfg(target_has_atomic = "64")]
+        if cfg!(feature = "worker-metrics") {
+            let mut last_busy_durations =
+                crate::recover_lock(self.last_busy_durations.lock(), "sampled busy durations");
+            for (worker_idx, last_busy_duration) in last_busy_durations.iter_mut().enumerate() {
+                let Some(labels) = runtime.worker_labels(worker_idx) else {
+                    break;
+                };
+
+                let busy_duration = runtime.metrics.worker_total_busy_duration(worker_idx);
+                instruments.busy_duration.record(
+                    runtime.busy_duration_unit,
+                    *last_busy_duration,
+                    busy_duration,
+                    &super::with_labels(labels, &dynamic_labels),
+                );
+                *last_busy_duration = busy_duration;
+            }
+        }
+
+        let mut last_values = crate::recover_lock(self.last_values.lock(), "sampled counters");
+
+        for ((definition, counter), last_values) in SAMPLED_COUNTERS
+            .iter()
+            .zip(&instruments.counters)
+            .zip(last_values.iter_mut())
+        {
+            let record = |last_value: &mut u64, value: u64, labels| {
+                let delta = value.saturating_sub(*last_value);
+                *last_value = value;
+                if delta > 0 {
+                    counter.add(delta, &super::with_labels(labels, &dynamic_labels));
+                }
+            };
+
+            if !runtime.exports(definition.metric_set) {
+                continue;
+            }
+
+            let read = match definition.source {
+                CounterSource::Runtime(read) => {
+                    record(&mut last_values[0], read(&runtime.metrics), &runtime.labels);
+                    continue;
+                }
+                CounterSource::Worker(read) => read,
+                #[cfg(tokio_unstable)]
+                CounterSource::WorkStealing(read) => {
+                    if runtime.is_current_thread {
+                        continue;
+                    }
+                    read
+                }
+            };
+
+            // Workers sharing a series add up to the same counter
+            for (worker_idx, last_value) in last_values.iter_mut().enumerate() {
+                let Some(labels) = runtime.worker_labels(worker_idx) else {
+                    break;
+                };
+                record(last_value, read(&runtime.metrics, worker_idx), labels);
+            }
+        }
+    }
+}
+
+/// Converts the runtime poll time histogram into samples recorded in a
+/// histogram instrument.
+#[cfg(tokio_unstable)]
+struct PollTimeSampler {
+    /// The value recorded for each bucket, in seconds
+    bucket_values: Vec<f64>,
+
+    /// The bucket counts as of the last sample, for each worker
+    last_counts: Mutex<Vec<Vec<u64>>>,
+}
+
+#[cfg(tokio_unstable)]
+impl PollTimeSampler {
+    fn new(metrics: &RuntimeMetrics, num_workers: usize) -> Self {
+        let num_buckets = metrics.poll_time_histogram_num_buckets();
+        let bucket_values = (0..num_buckets)
+            .map(|bucket_idx| super::poll_time_bucket_value(metrics, bucket_idx).as_secs_f64())
+            .collect();
+
+        Self {
+            bucket_values,
+            last_counts: Mutex::new(vec![vec![0; num_buckets]; num_workers]),
+        }
+    }
+
+    /// Record the polls which happened since the last sample.
+    fn sample(&self, runtime: &TrackedRuntime, histogram: &Histogram<f64>) {
+        let metrics = &runtime.metrics;
+        let dynamic_labels = runtime.dynamic_labels();
+        let mut last_counts = crate::recover_lock(self.last_counts.lock(), "sampled poll times");
+
+        for (worker_idx, last_counts) in last_counts.iter_mut().enumerate() {
+            // Workers sharing a series are recorded in the same histogram
+            let Some(labels) = runtime.worker_labels(worker_idx) else {
+                break;
+            };
+            let labels = super::with_labels(labels, &dynamic_labels);
+
+            let deltas: smallvec::SmallVec<[u64; 16]> = last_counts
+                .iter_mut()
+                .enumerate()
+                .map(|(bucket_idx, last_count)| {
+                    let count = metrics.poll_time_histogram_bucket_count(worker_idx, bucket_idx);
+                    let delta = count.saturating_sub(*last_count);
+                    *last_count = count;
+                    delta
+                })
+                .collect();
+
+            let total: u64 = deltas.iter().sum();
+            for (delta, value) in deltas.into_iter().zip(&self.bucket_values) {
+                let samples = if total > MAX_SAMPLES_PER_WORKER {
+                    scale(delta, MAX_SAMPLES_PER_WORKER, total)
+                } else {
+                    delta
+                };
+
+                for _ in 0..samples {
+                    histogram.record(*value, &labels);
+                }
+            }
+        }
+    }
+}
+
+/// Compute `value * numerator / denominator` without overflowing.
+#[cfg(tokio_unstable)]
+fn scale(value: u64, numerator: u64, denominator: u64) -> u64 {
+    let scaled = u128::from(value) * u128::from(numerator) / u128::from(denominator);
+    scaled.try_into().unwrap_or(u64::MAX)
+}
@@ -0,0 +1,327 @@
+//! Point-in-time snapshots of the tracked runtimes.
+
+use std::fmt::{self, Display, Write as _};
+use std::time::Duration;
+
+use opentelemetry::KeyValue;
+
+use super::TrackedRuntime;
+
+/// The state of a tracked runtime at a point in time, see [`snapshots`].
+///
+/// The values only available with `tokio_unstable`, or on targets with 64-bit
+/// atomics, are `None` otherwise.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "debug-http", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RuntimeSnapshot {
+    /// The name of the runtime, see [`crate::Config::named`].
+    pub name: Option<String>,
+
+    /// The labels of the runtime, as attached to its metrics.
+    #[cfg_attr(feature = "debug-http", serde(serialize_with = "serialize_labels"))]
+    pub labels: Vec<KeyValue>,
+
+    /// The number of worker threads.
+    pub workers: usize,
+
+    /// The number of tasks currently alive.
+    pub alive_tasks: usize,
+
+    /// The number of tasks currently scheduled in the global queue.
+    pub global_queue_depth: usize,
+
+    /// The number of additional threads spawned by the runtime.
+    pub blocking_threads: Option<usize>,
+
+    /// The number of idle threads spawned for `spawn_blocking` calls.
+    pub idle_blocking_threads: Option<usize>,
+
+    /// The number of tasks scheduled in the blocking thread pool.
+    pub blocking_queue_depth: Option<usize>,
+
+    /// The number of tasks spawned since the runtime was created.
+    pub spawned_tasks: Option<u64>,
+
+    /// The number of tasks scheduled from outside the runtime.
+    pub remote_schedules: Option<u64>,
+
+    /// The number of times tasks were forced to yield after exhausting their
+    /// budget.
+    pub budget_forced_yields: Option<u64>,
+
+    /// The state of each worker.
+    pub worker_snapshots: Vec<WorkerSnapshot>,
+}
+
+/// The state of a worker of a tracked runtime, see [`RuntimeSnapshot`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "debug-http", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct WorkerSnapshot {
+    /// The index of the worker.
+    pub index: usize,
+
+    /// The number of times the worker parked.
+    pub park_count: Option<u64>,
+
+    /// The time the worker spent busy since the runtime was created.
+    #[cfg_attr(
+        feature = "debug-http",
+        serde(serialize_with = "serialize_optional_seconds")
+    )]
+    pub busy_duration: Option<Duration>,
+
+    /// The number of tasks the worker polled.
+    pub polls: Option<u64>,
+
+    /// The number of tasks the worker stole from other workers.
+    pub steals: Option<u64>,
+
+    /// The number of tasks currently in the local queue of the worker.
+    pub local_queue_depth: Option<usize>,
+
+    /// The moving average of the poll time of the tasks on the worker.
+    #[cfg_attr(
+        feature = "debug-http",
+        serde(serialize_with = "serialize_optional_seconds")
+    )]
+    pub mean_poll_time: Option<Duration>,
+}
+
+impl TrackedRuntime {
+    /// Take a snapshot of this runtime.
+    pub(super) fn snapshot(&self) -> RuntimeSnapshot {
+        let metrics = &self.metrics;
+        #[cfg_attr(not(tokio_unstable), expect(unused_mut))]
+        let mut snapshot = RuntimeSnapshot {
+            name: super::runtime_name(&self.labels).map(ToString::to_string),
+            labels: self.labels.to_vec(),
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            global_queue_depth: metrics.global_queue_depth(),
+            blocking_threads: None,
+            idle_blocking_threads: None,
+            blocking_queue_depth: None,
+            spawned_tasks: None,
+            remote_schedules: None,
+            budget_forced_yields: None,
+            worker_snapshots: (0..metrics.num_workers())
+                .map(|index| self.worker_snapshot(index))
+                .collect(),
+        };
+
+        #[cfg(tokio_unstable)]
+        {
+            snapshot.blocking_threads = Some(metrics.num_blocking_threads());
+            snapshot.idle_blocking_threads = Some(metrics.num_idle_blocking_threads());
+            snapshot.blocking_queue_depth = Some(metrics.blocking_queue_depth());
+            snapshot.spawned_tasks = Some(metrics.spawned_tasks_count());
+            snapshot.remote_schedules = Some(metrics.remote_schedule_count());
+            snapshot.budget_forced_yields = Some(metrics.budget_forced_yield_count());
+        }
+
+        snapshot
+    }
+
+    /// Take a snapshot of the given worker of this runtime.
+    fn worker_snapshot(&self, index: usize) -> WorkerSnapshot {
+        #[cfg_attr(not(any(target_has_atomic = "64", tokio_unstable)), expect(unused_mut))]
+        let mut snapshot = WorkerSnapshot {
+            index,
+            park_count: None,
+            busy_duration: None,
+            polls: None,
+            steals: None,
+            local_queue_depth: None,
+            mean_poll_time: None,
+        };
+
+        #[cfg(target_has_atomic = "64")]
+        {
+            snapshot.park_count = Some(self.metrics.worker_park_count(index));
+
+            // Tokio can't measure time on wasm32-unknown-unknown
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            {
+                snapshot.busy_duration = Some(self.metrics.worker_total_busy_duration(index));
+            }
+        }
+
+        #[cfg(tokio_unstable)]
+        {
+            snapshot.polls = Some(self.metrics.worker_poll_count(index));
+            snapshot.steals = Some(self.metrics.worker_steal_count(index));
+            snapshot.local_queue_depth = Some(self.metrics.worker_local_queue_depth(index));
+
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            {
+                snapshot.mean_poll_time = Some(self.metrics.worker_mean_poll_time(index));
+            }
+        }
+
+        snapshot
+    }
+}
+
+/// Take a snapshot of each tracked runtime.
+///
+/// This reads the same values as the metrics, without going through an
+/// exporter, e.g. to inspect the runtimes while debugging.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// opentelemetry_instrumentation_tokio::observe_current_runtime()?;
+///
+/// for snapshot in opentelemetry_instrumentation_tokio::snapshots() {
+///     println!("{} tasks alive", snapshot.alive_tasks);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub fn snapshots() -> Vec<RuntimeSnapshot> {
+    super::read_runtimes()
+        .iter()
+        .filter_map(|runtime| runtime.collect("snapshot", || runtime.snapshot()))
+        .collect()
+}
+
+/// Format the snapshots of all the tracked runtimes as a human-readable table.
+///
+/// This is meant to be dumped to the logs, or printed from a REPL, while
+/// debugging an incident without an exporter at hand. Values which are not
+/// available on this build are shown as `-`. The format is not stable, and
+/// should not be parsed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// opentelemetry_instrumentation_tokio::observe_current_runtime()?;
+///
+/// eprintln!("{}", opentelemetry_instrumentation_tokio::report());
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub fn report() -> String {
+    let mut report = String::new();
+    for (index, snapshot) in snapshots().iter().enumerate() {
+        if index > 0 {
+            report.push('\n');
+        }
+        // Writing to a `String` never fails
+        let _ = write!(report, "{snapshot}");
+    }
+    report
+}
+
+/// Write an optional value, or `-` if it is not available.
+struct Optional<T>(Option<T>);
+
+impl<T: Display> Display for Optional<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => f.pad("-"),
+        }
+    }
+}
+
+/// Write an optional duration in seconds, or `-` if it is not available.
+struct Seconds(Option<Duration>);
+
+impl Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(duration) => f.pad(&format!("{:.6}s", duration.as_secs_f64())),
+            None => f.pad("-"),
+        }
+    }
+}
+
+impl Display for RuntimeSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("runtime")?;
+        if let Some(name) = &self.name {
+            write!(f, " {name}")?;
+        }
+        if self.labels.is_empty() {
+            f.write_str(" (no labels)")?;
+        }
+        for label in &self.labels {
+            write!(f, " {}={}", label.key, label.value)?;
+        }
+        writeln!(f)?;
+
+        let rows: [(&str, &dyn Display); 9] = [
+            ("workers", &self.workers),
+            ("alive tasks", &self.alive_tasks),
+            ("global queue depth", &self.global_queue_depth),
+            ("blocking threads", &Optional(self.blocking_threads)),
+            (
+                "idle blocking threads",
+                &Optional(self.idle_blocking_threads),
+            ),
+            ("blocking queue depth", &Optional(self.blocking_queue_depth)),
+            ("spawned tasks", &Optional(self.spawned_tasks)),
+            ("remote schedules", &Optional(self.remote_schedules)),
+            ("budget forced yields", &Optional(self.budget_forced_yields)),
+        ];
+        for (name, value) in rows {
+            writeln!(f, "  {name:<22} {value}")?;
+        }
+
+        writeln!(
+            f,
+            "  {:>6} {:>10} {:>14} {:>10} {:>10} {:>11} {:>14}",
+            "worker", "parks", "busy", "polls", "steals", "local queue", "mean poll"
+        )?;
+        for worker in &self.worker_snapshots {
+            writeln!(
+                f,
+                "  {:>6} {:>10} {:>14} {:>10} {:>10} {:>11} {:>14}",
+                worker.index,
+                Optional(worker.park_count),
+                Seconds(worker.busy_duration),
+                Optional(worker.polls),
+                Optional(worker.steals),
+                Optional(worker.local_queue_depth),
+                Seconds(worker.mean_poll_time),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Serialize labels as a JSON object.
+#[cfg(feature = "debug-http")]
+fn serialize_labels<S: serde::Serializer>(
+    labels: &[KeyValue],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(
+        labels
+            .iter()
+            .map(|label| (label.key.as_str(), label.value.to_string())),
+    )
+}
+
+/// Serialize an optional duration as a number of seconds.
+#[cfg(feature = "debug-http")]
+#[expect(clippy::ref_option)]
+fn serialize_optional_seconds<S: serde::Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+        None => serializer.serialize_none(),
+    }
+}
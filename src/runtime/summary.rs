@@ -0,0 +1,111 @@
+//! Periodic summary of the runtime health, logged through `tracing`.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::runtime::RuntimeMetrics;
+
+use super::TrackedRuntime;
+
+/// Logs a one-line summary of a runtime every `interval`.
+pub(super) struct SummaryLogger {
+    interval: Duration,
+    state: Mutex<SummaryState>,
+}
+
+struct SummaryState {
+    /// When the summary should be logged next
+    next_log: Instant,
+
+    /// When the busy durations were last read
+    #[cfg(target_has_atomic = "64")]
+    last_log: Instant,
+
+    /// The busy duration of each worker as of the last summary
+    #[cfg(target_has_atomic = "64")]
+    last_busy_durations: Vec<Duration>,
+}
+
+impl SummaryLogger {
+    pub(super) fn new(metrics: &RuntimeMetrics, interval: Duration) -> Self {
+        #[cfg(target_has_atomic = "64")]
+        let (now, last_busy_durations) = super::busy_durations(metrics);
+        #[cfg(not(target_has_atomic = "64"))]
+        let now = {
+            let _ = metrics;
+            Instant::now()
+        };
+
+        Self {
+            interval,
+            state: Mutex::new(SummaryState {
+                next_log: now + interval,
+                #[cfg(target_has_atomic = "64")]
+                last_log: now,
+                #[cfg(target_has_atomic = "64")]
+                last_busy_durations,
+            }),
+        }
+    }
+
+    /// Log the summary of the runtime if it is due, returning when it should
+    /// be logged next.
+    pub(super) fn log_if_due(&self, runtime: &TrackedRuntime, now: Instant) -> Instant {
+        let mut state = crate::recover_lock(self.state.lock(), "summary schedule");
+        if now < state.next_log {
+            return state.next_log;
+        }
+
+        let metrics = &runtime.metrics;
+        #[cfg(tokio_unstable)]
+        let local_queue_depth = Some(
+            (0..metrics.num_workers())
+                .map(|worker_idx| metrics.worker_local_queue_depth(worker_idx))
+                .sum::<usize>(),
+        );
+        #[cfg(not(tokio_unstable))]
+        let local_queue_depth: Option<usize> = None;
+
+        #[cfg(target_has_atomic = "64")]
+        let busiest_worker = {
+            let (now, busy_durations) = super::busy_durations(metrics);
+            let elapsed = now.saturating_duration_since(state.last_log);
+            let busiest = busy_durations
+                .iter()
+                .zip(&state.last_busy_durations)
+                .map(|(busy, last_busy)| busy.saturating_sub(*last_busy))
+                .enumerate()
+                .max_by_key(|(_, busy)| *busy)
+                .filter(|_| !elapsed.is_zero())
+                .map(|(worker_idx, busy)| {
+                    // The busy duration is only updated when the worker parks,
+                    // so it can be slightly ahead of the wall-clock time
+                    (
+                        worker_idx,
+                        (busy.as_secs_f64() / elapsed.as_secs_f64()).min(1.0),
+                    )
+                });
+
+            state.last_log = now;
+            state.last_busy_durations = busy_durations;
+            busiest
+        };
+        #[cfg(not(target_has_atomic = "64"))]
+        let busiest_worker: Option<(usize, f64)> = None;
+
+        tracing::info!(
+            runtime = %super::describe_runtime(&runtime.labels),
+            workers = metrics.num_workers(),
+            alive_tasks = metrics.num_alive_tasks(),
+            global_queue_depth = metrics.global_queue_depth(),
+            local_queue_depth,
+            busiest_worker = busiest_worker.map(|(worker_idx, _)| worker_idx),
+            busiest_worker_utilization = busiest_worker.map(|(_, utilization)| utilization),
+            "tokio runtime summary",
+        );
+
+        // Don't try to catch up on missed summaries
+        state.next_log = (state.next_log + self.interval).max(now);
+        state.next_log
+    }
+}
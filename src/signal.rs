@@ -0,0 +1,158 @@
+//! Instrumented signal handling.
+//!
+//! These mirror the functions in [`tokio::signal`], and count the signals
+//! received in `tokio.signal.received`, with a `tokio.signal.kind` attribute
+//! set to the name of the signal (e.g. `SIGTERM`, or `ctrl_c`), along with the
+//! labels of the runtime which received them (see
+//! [`crate::Config::observe_runtime`]).
+//!
+//! Unexpected `SIGHUP` or `SIGTERM` deliveries, or a shutdown signal received
+//! several times, show up in the metrics this way.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::signal;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! signal::ctrl_c().await?;
+//! // Shut down gracefully
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::sync::LazyLock;
+#[cfg(unix)]
+use std::task::{Context, Poll};
+
+use opentelemetry::metrics::Counter;
+use opentelemetry::{Key, KeyValue, Value};
+#[cfg(unix)]
+use tokio::signal::unix::{Signal, SignalKind};
+
+static SIGNALS_RECEIVED: LazyLock<Counter<u64>> = LazyLock::new(|| {
+    crate::meter()
+        .u64_counter("tokio.signal.received")
+        .with_description("The number of signals received")
+        .with_unit("{signal}")
+        .build()
+});
+
+/// Count a signal received, with the labels of the current runtime.
+fn record_signal(kind: &KeyValue) {
+    crate::runtime::with_current_runtime_labels(|labels| {
+        let mut labels = labels.to_vec();
+        labels.push(kind.clone());
+        SIGNALS_RECEIVED.add(1, &labels);
+    });
+}
+
+/// Build the `tokio.signal.kind` label.
+fn kind_label(kind: impl Into<Value>) -> KeyValue {
+    KeyValue::new(Key::from_static_str("tokio.signal.kind"), kind)
+}
+
+/// Wait for a "ctrl-c" notification, recording it.
+///
+/// See [`tokio::signal::ctrl_c`]. The signal is recorded with the `ctrl_c`
+/// kind.
+///
+/// # Errors
+///
+/// Returns an error if listening for the notification failed.
+pub async fn ctrl_c() -> io::Result<()> {
+    tokio::signal::ctrl_c().await?;
+    record_signal(&kind_label("ctrl_c"));
+    Ok(())
+}
+
+/// Listen for signals of the given kind, recording them as they are
+/// received.
+///
+/// See [`tokio::signal::unix::signal`].
+///
+/// # Errors
+///
+/// Returns an error if listening for the signal failed, e.g. if the signal
+/// can't be handled.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::signal;
+/// use tokio::signal::unix::SignalKind;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let mut hangups = signal::signal(SignalKind::hangup())?;
+/// while hangups.recv().await.is_some() {
+///     // Reload the configuration
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(unix)]
+pub fn signal(kind: SignalKind) -> io::Result<InstrumentedSignal> {
+    Ok(InstrumentedSignal {
+        inner: tokio::signal::unix::signal(kind)?,
+        kind: kind_label(signal_name(kind)),
+    })
+}
+
+/// The name of a signal, e.g. `SIGTERM`, or its number if it isn't one of
+/// the well-known ones.
+#[cfg(unix)]
+fn signal_name(kind: SignalKind) -> Value {
+    [
+        (SignalKind::alarm(), "SIGALRM"),
+        (SignalKind::child(), "SIGCHLD"),
+        (SignalKind::hangup(), "SIGHUP"),
+        (SignalKind::interrupt(), "SIGINT"),
+        (SignalKind::io(), "SIGIO"),
+        (SignalKind::pipe(), "SIGPIPE"),
+        (SignalKind::quit(), "SIGQUIT"),
+        (SignalKind::terminate(), "SIGTERM"),
+        (SignalKind::user_defined1(), "SIGUSR1"),
+        (SignalKind::user_defined2(), "SIGUSR2"),
+        (SignalKind::window_change(), "SIGWINCH"),
+    ]
+    .into_iter()
+    .find(|(known, _)| *known == kind)
+    .map_or_else(
+        || kind.as_raw_value().to_string().into(),
+        |(_, name)| name.into(),
+    )
+}
+
+/// A [`Signal`] listener recording the signals it receives.
+///
+/// Created by [`signal`].
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct InstrumentedSignal {
+    inner: Signal,
+    kind: KeyValue,
+}
+
+#[cfg(unix)]
+impl InstrumentedSignal {
+    /// Receive the next signal notification, recording it.
+    ///
+    /// See [`Signal::recv`].
+    pub async fn recv(&mut self) -> Option<()> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Poll to receive the next signal notification, recording it.
+    ///
+    /// See [`Signal::poll_recv`].
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<()>> {
+        let received = std::task::ready!(self.inner.poll_recv(cx));
+        if received.is_some() {
+            record_signal(&self.kind);
+        }
+        Poll::Ready(received)
+    }
+}
@@ -0,0 +1,162 @@
+//! Instrumentation for [`Stream`]s.
+//!
+//! The [`InstrumentedStreamExt::instrumented`] adapter records:
+//!
+//! - `tokio.stream.items` - Items yielded by the stream
+//! - `tokio.stream.item_interval` - Time between consecutive items, the first
+//!   one being measured from the stream creation
+//! - `tokio.stream.lifetime` - Time between the stream creation and it being
+//!   exhausted or dropped, with a `tokio.stream.exhausted` attribute telling
+//!   which one happened
+//!
+//! All are labelled with the stream name (`tokio.stream.name`).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures_core::Stream;
+//! use opentelemetry_instrumentation_tokio::stream::InstrumentedStreamExt;
+//!
+//! fn consume(events: impl Stream<Item = u32>) {
+//!     let events = events.instrumented("events");
+//!     // Consume the stream
+//! }
+//! ```
+
+use std::pin::Pin;
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use futures_core::Stream;
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+
+/// Instruments shared by all [`InstrumentedStream`]s.
+struct StreamInstruments {
+    items: Counter<u64>,
+    item_interval: Histogram<f64>,
+    lifetime: Histogram<f64>,
+}
+
+static STREAM_INSTRUMENTS: LazyLock<StreamInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    StreamInstruments {
+        items: meter
+            .u64_counter("tokio.stream.items")
+            .with_description("The number of items yielded by the stream")
+            .with_unit("{item}")
+            .build(),
+        item_interval: meter
+            .f64_histogram("tokio.stream.item_interval")
+            .with_description("The time between consecutive items yielded by the stream")
+            .with_unit("s")
+            .build(),
+        lifetime: meter
+            .f64_histogram("tokio.stream.lifetime")
+            .with_description("The time between the stream creation and its end")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// Extension trait to instrument [`Stream`]s.
+pub trait InstrumentedStreamExt: Stream + Sized {
+    /// Instrument this stream under the given name.
+    fn instrumented(self, name: impl Into<Value>) -> InstrumentedStream<Self> {
+        InstrumentedStream::new(name, self)
+    }
+}
+
+impl<S: Stream> InstrumentedStreamExt for S {}
+
+pin_project_lite::pin_project! {
+    /// A [`Stream`] recording the items it yields and its lifetime.
+    ///
+    /// Created by [`InstrumentedStreamExt::instrumented`].
+    #[derive(Debug)]
+    pub struct InstrumentedStream<S> {
+        #[pin]
+        inner: S,
+        labels: [KeyValue; 1],
+        created_at: Instant,
+        last_item_at: Instant,
+        exhausted: bool,
+    }
+
+    impl<S> PinnedDrop for InstrumentedStream<S> {
+        fn drop(this: Pin<&mut Self>) {
+            if !this.exhausted {
+                record_lifetime(&this.labels, this.created_at, false);
+            }
+        }
+    }
+}
+
+/// Record the lifetime of a stream which just ended.
+fn record_lifetime(labels: &[KeyValue; 1], created_at: Instant, exhausted: bool) {
+    let [name] = labels;
+    let labels = [
+        name.clone(),
+        KeyValue::new(Key::from_static_str("tokio.stream.exhausted"), exhausted),
+    ];
+    STREAM_INSTRUMENTS
+        .lifetime
+        .record(created_at.elapsed().as_secs_f64(), &labels);
+}
+
+impl<S> InstrumentedStream<S> {
+    /// Instrument a stream under the given name.
+    pub fn new(name: impl Into<Value>, stream: S) -> Self {
+        let now = Instant::now();
+        Self {
+            inner: stream,
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.stream.name"),
+                name.into(),
+            )],
+            created_at: now,
+            last_item_at: now,
+            exhausted: false,
+        }
+    }
+
+    /// Get a reference to the underlying stream.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: Stream> Stream for InstrumentedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        let item = std::task::ready!(this.inner.poll_next(cx));
+
+        match &item {
+            Some(_) => {
+                let now = Instant::now();
+                let instruments = &*STREAM_INSTRUMENTS;
+                instruments.items.add(1, this.labels);
+                instruments.item_interval.record(
+                    now.duration_since(*this.last_item_at).as_secs_f64(),
+                    this.labels,
+                );
+                *this.last_item_at = now;
+            }
+            None if !*this.exhausted => {
+                *this.exhausted = true;
+                record_lifetime(this.labels, *this.created_at, true);
+            }
+            None => {}
+        }
+
+        Poll::Ready(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,21 @@
+//! Instrumented synchronization primitives.
+//!
+//! These mirror the primitives in [`tokio::sync`], and record metrics about
+//! their usage under the same meter as the runtime metrics. Each primitive is
+//! given a name when created, which is attached to its metrics. Channels use
+//! the `tokio.channel.name` attribute for this.
+
+mod barrier;
+pub mod broadcast;
+pub mod mpsc;
+mod mutex;
+mod notify;
+pub mod oneshot;
+mod rwlock;
+mod semaphore;
+
+pub use self::barrier::InstrumentedBarrier;
+pub use self::mutex::{InstrumentedMutex, InstrumentedMutexGuard};
+pub use self::notify::InstrumentedNotify;
+pub use self::rwlock::InstrumentedRwLock;
+pub use self::semaphore::InstrumentedSemaphore;
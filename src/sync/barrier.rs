@@ -0,0 +1,117 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Histogram, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::{Barrier, BarrierWaitResult};
+
+/// Instruments shared by all [`InstrumentedBarrier`]s.
+struct BarrierInstruments {
+    waiters: UpDownCounter<i64>,
+    wait_duration: Histogram<f64>,
+}
+
+static BARRIER_INSTRUMENTS: LazyLock<BarrierInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    BarrierInstruments {
+        waiters: meter
+            .i64_up_down_counter("tokio.barrier.waiters")
+            .with_description("The number of tasks currently waiting at the barrier")
+            .with_unit("{waiter}")
+            .build(),
+        wait_duration: meter
+            .f64_histogram("tokio.barrier.wait_duration")
+            .with_description("The time tasks spent waiting at the barrier")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// Decrements the waiters count when the waiting future completes or is
+/// dropped.
+struct WaiterGuard<'a> {
+    labels: &'a [KeyValue],
+}
+
+impl<'a> WaiterGuard<'a> {
+    fn new(labels: &'a [KeyValue]) -> Self {
+        BARRIER_INSTRUMENTS.waiters.add(1, labels);
+        Self { labels }
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        BARRIER_INSTRUMENTS.waiters.add(-1, self.labels);
+    }
+}
+
+/// A [`Barrier`] recording the tasks waiting at it, and for how long.
+///
+/// Metrics are labelled with the name given on creation
+/// (`tokio.barrier.name`):
+///
+/// - `tokio.barrier.waiters` - Tasks currently waiting at the barrier
+/// - `tokio.barrier.wait_duration` - Time tasks spent waiting at the barrier
+///
+/// In a staged pipeline, waiters piling up at a barrier with long waits point
+/// to a stage lagging behind the others.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use opentelemetry_instrumentation_tokio::sync::InstrumentedBarrier;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let barrier = Arc::new(InstrumentedBarrier::new("stage", 4));
+///
+/// for _ in 0..4 {
+///     let barrier = barrier.clone();
+///     tokio::spawn(async move {
+///         // Run the first stage
+///         barrier.wait().await;
+///         // Run the second stage
+///     });
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedBarrier {
+    inner: Barrier,
+    labels: [KeyValue; 1],
+}
+
+impl InstrumentedBarrier {
+    /// Create a new instrumented [`Barrier`] with the given name, which
+    /// releases the tasks once `n` of them are waiting.
+    ///
+    /// See [`Barrier::new`].
+    #[must_use]
+    pub fn new(name: impl Into<Value>, n: usize) -> Self {
+        Self {
+            inner: Barrier::new(n),
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.barrier.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Wait for all the tasks to reach the barrier, recording the time spent
+    /// waiting.
+    ///
+    /// See [`Barrier::wait`].
+    pub async fn wait(&self) -> BarrierWaitResult {
+        let _guard = WaiterGuard::new(&self.labels);
+        let start = Instant::now();
+        let result = self.inner.wait().await;
+        BARRIER_INSTRUMENTS
+            .wait_duration
+            .record(start.elapsed().as_secs_f64(), &self.labels);
+        result
+    }
+}
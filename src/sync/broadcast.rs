@@ -0,0 +1,241 @@
+//! An instrumented [`tokio::sync::broadcast`] channel.
+//!
+//! The channel records:
+//!
+//! - `tokio.broadcast.messages_sent` - Messages sent
+//! - `tokio.broadcast.messages_lagged` - Messages dropped before a receiver
+//!   could see them, because it lagged behind the channel capacity
+//! - `tokio.broadcast.receiver_lag` - Messages queued for a receiver, recorded
+//!   each time it receives a message
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::sync::broadcast;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let (tx, mut rx) = broadcast::channel("events", 16);
+//!
+//! tokio::spawn(async move {
+//!     while let Ok(event) = rx.recv().await {
+//!         println!("got {event}");
+//!     }
+//! });
+//!
+//! tx.send(42).unwrap();
+//! # }
+//! ```
+
+use std::sync::{Arc, LazyLock};
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::broadcast::error::{RecvError, SendError, TryRecvError};
+
+/// Instruments shared by all broadcast channels.
+struct BroadcastInstruments {
+    messages_sent: Counter<u64>,
+    messages_lagged: Counter<u64>,
+    receiver_lag: Histogram<u64>,
+}
+
+static BROADCAST_INSTRUMENTS: LazyLock<BroadcastInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    BroadcastInstruments {
+        messages_sent: meter
+            .u64_counter("tokio.broadcast.messages_sent")
+            .with_description("The number of messages sent on the channel")
+            .with_unit("{message}")
+            .build(),
+        messages_lagged: meter
+            .u64_counter("tokio.broadcast.messages_lagged")
+            .with_description(
+                "The number of messages a receiver missed because it lagged behind the channel",
+            )
+            .with_unit("{message}")
+            .build(),
+        receiver_lag: meter
+            .u64_histogram("tokio.broadcast.receiver_lag")
+            .with_description("The number of messages queued for a receiver when it receives one")
+            .with_unit("{message}")
+            .build(),
+    }
+});
+
+/// Create an instrumented broadcast channel with the given name and capacity.
+///
+/// See [`tokio::sync::broadcast::channel`].
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero or too large.
+#[must_use]
+pub fn channel<T: Clone>(name: impl Into<Value>, capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let labels: Arc<[KeyValue]> = Arc::new([KeyValue::new(
+        Key::from_static_str("tokio.channel.name"),
+        name.into(),
+    )]);
+
+    let (tx, rx) = tokio::sync::broadcast::channel(capacity);
+    let tx = Sender {
+        inner: tx,
+        labels: labels.clone(),
+    };
+    let rx = Receiver { inner: rx, labels };
+    (tx, rx)
+}
+
+/// The sending half of an instrumented broadcast channel.
+///
+/// See [`tokio::sync::broadcast::Sender`].
+#[derive(Debug)]
+pub struct Sender<T> {
+    inner: tokio::sync::broadcast::Sender<T>,
+    labels: Arc<[KeyValue]>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            labels: self.labels.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Send a value to all active receivers, returning how many will see it.
+    ///
+    /// See [`tokio::sync::broadcast::Sender::send`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no active receivers.
+    pub fn send(&self, value: T) -> Result<usize, SendError<T>> {
+        let receivers = self.inner.send(value)?;
+        BROADCAST_INSTRUMENTS.messages_sent.add(1, &self.labels);
+        Ok(receivers)
+    }
+
+    /// Create a new receiver, which will see the values sent after this call.
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver {
+            inner: self.inner.subscribe(),
+            labels: self.labels.clone(),
+        }
+    }
+
+    /// Returns the number of queued values.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no queued values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the number of active receivers.
+    #[must_use]
+    pub fn receiver_count(&self) -> usize {
+        self.inner.receiver_count()
+    }
+}
+
+/// The receiving half of an instrumented broadcast channel.
+///
+/// See [`tokio::sync::broadcast::Receiver`].
+#[derive(Debug)]
+pub struct Receiver<T> {
+    inner: tokio::sync::broadcast::Receiver<T>,
+    labels: Arc<[KeyValue]>,
+}
+
+impl<T> Receiver<T> {
+    /// Record the number of messages this receiver missed.
+    fn record_lagged(&self, missed: u64) {
+        BROADCAST_INSTRUMENTS
+            .messages_lagged
+            .add(missed, &self.labels);
+    }
+
+    /// Record the number of messages queued for this receiver, after it
+    /// received one.
+    fn record_received(&self) {
+        BROADCAST_INSTRUMENTS.receiver_lag.record(
+            self.inner
+                .len()
+                .try_into()
+                .unwrap_or_else(|_| crate::overflowed("tokio.broadcast.receiver_lag", u64::MAX)),
+            &self.labels,
+        );
+    }
+
+    /// Returns the number of messages that were sent but not yet received by
+    /// this receiver.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no messages waiting for this receiver.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Create a new receiver, which will see the values sent after this call.
+    #[must_use]
+    pub fn resubscribe(&self) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            inner: self.inner.resubscribe(),
+            labels: self.labels.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Receiver<T> {
+    /// Receive the next value for this receiver.
+    ///
+    /// See [`tokio::sync::broadcast::Receiver::recv`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel is closed, or if this receiver lagged
+    /// behind. Lagged messages are recorded in the metrics.
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        let result = self.inner.recv().await;
+        match &result {
+            Ok(_) => self.record_received(),
+            Err(RecvError::Lagged(missed)) => self.record_lagged(*missed),
+            Err(RecvError::Closed) => {}
+        }
+        result
+    }
+
+    /// Try to receive the next value for this receiver, without waiting.
+    ///
+    /// See [`tokio::sync::broadcast::Receiver::try_recv`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel is empty or closed, or if this receiver
+    /// lagged behind. Lagged messages are recorded in the metrics.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let result = self.inner.try_recv();
+        match &result {
+            Ok(_) => self.record_received(),
+            Err(TryRecvError::Lagged(missed)) => self.record_lagged(*missed),
+            Err(TryRecvError::Empty | TryRecvError::Closed) => {}
+        }
+        result
+    }
+}
@@ -0,0 +1,741 @@
+//! Instrumented [`tokio::sync::mpsc`] channels.
+//!
+//! The bounded channel records:
+//!
+//! - `tokio.mpsc.capacity_utilization` - Fraction of the capacity of the
+//!   channel in use, between 0 and 1, recorded each time a message is sent or
+//!   received
+//! - `tokio.mpsc.full_time` - Time the channel spent at full capacity, observed
+//!   when the metrics are collected, so that a channel which stays full shows
+//!   up before it stops being full
+//! - `tokio.mpsc.send_wait_duration` - Time `Sender::send` spent waiting for
+//!   capacity in the channel, for the messages which were sent
+//!
+//! A channel spending time at full capacity applies backpressure to its
+//! senders, which usually means the receiver can't keep up. The send wait
+//! duration tells how much each send was slowed down by it.
+//!
+//! The unbounded channel never applies backpressure, so a receiver which
+//! can't keep up makes it grow until the process runs out of memory. Its
+//! length is observed when the metrics are collected, like the runtime
+//! metrics:
+//!
+//! - `tokio.mpsc.len` - Approximate number of messages in the channel
+//! - `tokio.mpsc.high_water_mark` - Highest number of messages the channel held
+//!   so far
+//!
+//! Both channels count the messages which failed to send because the receiver
+//! was dropped or closed in `tokio.mpsc.messages_dropped`, which would
+//! otherwise be lost silently, e.g. during shutdown.
+//!
+//! With the `tracing` feature, `UnboundedReceiver::warn_on_growth` emits a
+//! warning when the channel grows on several consecutive collections, as a
+//! canary for leaks through unbounded channels.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::sync::mpsc;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let (tx, mut rx) = mpsc::channel("jobs", 16);
+//!
+//! tokio::spawn(async move {
+//!     while let Some(job) = rx.recv().await {
+//!         println!("got {job}");
+//!     }
+//! });
+//!
+//! tx.send(42).await.unwrap();
+//! # }
+//! ```
+
+#[cfg(feature = "tracing")]
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, Once, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+use opentelemetry::metrics::{Counter, Gauge, Histogram, Meter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::mpsc::error::{SendError, TryRecvError, TrySendError};
+
+/// Instruments shared by all mpsc channels.
+struct MpscInstruments {
+    capacity_utilization: Gauge<f64>,
+    send_wait_duration: Histogram<f64>,
+    messages_dropped: Counter<u64>,
+}
+
+static MPSC_INSTRUMENTS: LazyLock<MpscInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    MpscInstruments {
+        capacity_utilization: meter
+            .f64_gauge("tokio.mpsc.capacity_utilization")
+            .with_description("The fraction of the capacity of the channel in use")
+            .with_unit("1")
+            .build(),
+        send_wait_duration: meter
+            .f64_histogram("tokio.mpsc.send_wait_duration")
+            .with_description("The time senders spent waiting for capacity in the channel")
+            .with_unit("s")
+            .build(),
+        messages_dropped: meter
+            .u64_counter("tokio.mpsc.messages_dropped")
+            .with_description(
+                "The number of messages which failed to send because the receiver was dropped or closed",
+            )
+            .with_unit("{message}")
+            .build(),
+    }
+});
+
+/// The time a channel spent at full capacity.
+#[derive(Debug, Default)]
+struct FullTime {
+    since: Option<Instant>,
+    total: Duration,
+}
+
+impl FullTime {
+    /// The time spent full so far, including the current period if the
+    /// channel is full.
+    fn elapsed(&self) -> Duration {
+        self.total + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+}
+
+/// The full time of the channels with the same name, reported as a single
+/// series.
+struct FullTimeSeries {
+    labels: [KeyValue; 1],
+
+    // The time spent full by the channels which were dropped, so that the
+    // series doesn't go backwards when they are
+    dropped: Duration,
+    channels: Vec<Arc<Mutex<FullTime>>>,
+}
+
+/// The full time of all the bounded channels, observed on collection.
+static FULL_TIME_SERIES: Mutex<Vec<FullTimeSeries>> = Mutex::new(Vec::new());
+
+fn register_full_time_counter(meter: &Meter) {
+    meter
+        .f64_observable_counter("tokio.mpsc.full_time")
+        .with_description("The time the channel spent at full capacity")
+        .with_unit("s")
+        .with_callback(|instrument| {
+            for series in crate::recover_lock(FULL_TIME_SERIES.lock(), "channel full time").iter() {
+                let elapsed = series
+                    .channels
+                    .iter()
+                    .fold(series.dropped, |elapsed, channel| {
+                        elapsed + crate::recover_lock(channel.lock(), "channel full time").elapsed()
+                    });
+                instrument.observe(elapsed.as_secs_f64(), &series.labels);
+            }
+        })
+        .build();
+}
+
+/// The state shared by both halves of a channel.
+#[derive(Debug)]
+struct Shared {
+    labels: [KeyValue; 1],
+    full_time: Arc<Mutex<FullTime>>,
+}
+
+impl Shared {
+    fn new(labels: [KeyValue; 1]) -> Self {
+        static INSTRUMENTS: Once = Once::new();
+        INSTRUMENTS.call_once(|| register_full_time_counter(&crate::meter()));
+
+        let full_time = Arc::default();
+        let mut all_series = crate::recover_lock(FULL_TIME_SERIES.lock(), "channel full time");
+        match all_series.iter_mut().find(|series| series.labels == labels) {
+            Some(series) => series.channels.push(Arc::clone(&full_time)),
+            None => all_series.push(FullTimeSeries {
+                labels: labels.clone(),
+                dropped: Duration::ZERO,
+                channels: vec![Arc::clone(&full_time)],
+            }),
+        }
+
+        Self { labels, full_time }
+    }
+
+    /// Record the number of messages in the channel, after sending or
+    /// receiving one.
+    fn record_len(&self, len: usize, max_capacity: usize) {
+        #[expect(clippy::cast_precision_loss)]
+        let utilization = len as f64 / max_capacity as f64;
+        MPSC_INSTRUMENTS
+            .capacity_utilization
+            .record(utilization, &self.labels);
+
+        let mut full_time = crate::recover_lock(self.full_time.lock(), "channel full time");
+        if len >= max_capacity {
+            full_time.since.get_or_insert_with(Instant::now);
+        } else if let Some(since) = full_time.since.take() {
+            full_time.total += since.elapsed();
+        }
+    }
+}
+
+impl Drop for Shared {
+    fn drop(&mut self) {
+        let mut all_series = crate::recover_lock(FULL_TIME_SERIES.lock(), "channel full time");
+        let Some(series) = all_series
+            .iter_mut()
+            .find(|series| series.labels == self.labels)
+        else {
+            return;
+        };
+        series
+            .channels
+            .retain(|channel| !Arc::ptr_eq(channel, &self.full_time));
+        series.dropped += crate::recover_lock(self.full_time.lock(), "channel full time").elapsed();
+    }
+}
+
+/// Create an instrumented bounded mpsc channel with the given name and
+/// capacity.
+///
+/// See [`tokio::sync::mpsc::channel`].
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+#[must_use]
+pub fn channel<T>(name: impl Into<Value>, capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let (tx, rx) = tokio::sync::mpsc::channel(capacity);
+    let shared = Arc::new(Shared::new([KeyValue::new(
+        Key::from_static_str("tokio.channel.name"),
+        name.into(),
+    )]));
+
+    let tx = Sender {
+        inner: tx,
+        shared: shared.clone(),
+    };
+    let rx = Receiver { inner: rx, shared };
+    (tx, rx)
+}
+
+/// The sending half of an instrumented mpsc channel.
+///
+/// See [`tokio::sync::mpsc::Sender`].
+#[derive(Debug)]
+pub struct Sender<T> {
+    inner: tokio::sync::mpsc::Sender<T>,
+    shared: Arc<Shared>,
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Sender<T> {
+    /// Record the number of messages in the channel after sending one.
+    fn record_sent(&self) {
+        let max_capacity = self.inner.max_capacity();
+        self.shared
+            .record_len(max_capacity - self.inner.capacity(), max_capacity);
+    }
+
+    /// Send a value, waiting until there is capacity, and recording the time
+    /// spent waiting once it is sent.
+    ///
+    /// See [`tokio::sync::mpsc::Sender::send`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped or closed, in which
+    /// case the message is counted as dropped.
+    pub async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let start = Instant::now();
+        if let Err(error) = self.inner.send(value).await {
+            MPSC_INSTRUMENTS
+                .messages_dropped
+                .add(1, &self.shared.labels);
+            return Err(error);
+        }
+        MPSC_INSTRUMENTS
+            .send_wait_duration
+            .record(start.elapsed().as_secs_f64(), &self.shared.labels);
+        self.record_sent();
+        Ok(())
+    }
+
+    /// Try to send a value, without waiting.
+    ///
+    /// See [`tokio::sync::mpsc::Sender::try_send`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the channel is full, or if the receiver was
+    /// dropped or closed, in which case the message is counted as dropped.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if let Err(error) = self.inner.try_send(value) {
+            if matches!(error, TrySendError::Closed(_)) {
+                MPSC_INSTRUMENTS
+                    .messages_dropped
+                    .add(1, &self.shared.labels);
+            }
+            return Err(error);
+        }
+        self.record_sent();
+        Ok(())
+    }
+
+    /// Wait for the receiver to be dropped or closed.
+    ///
+    /// See [`tokio::sync::mpsc::Sender::closed`].
+    pub async fn closed(&self) {
+        self.inner.closed().await;
+    }
+
+    /// Returns `true` if the receiver was dropped or closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Returns the number of messages which can be sent without waiting.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Returns the capacity the channel was created with.
+    #[must_use]
+    pub fn max_capacity(&self) -> usize {
+        self.inner.max_capacity()
+    }
+}
+
+/// The depth of the channel can be collected in the metrics by registering a
+/// sender with [`queue::register`](crate::queue::register).
+impl<T: Send> crate::queue::InstrumentedQueue for Sender<T> {
+    fn len(&self) -> usize {
+        self.inner.max_capacity() - self.inner.capacity()
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.inner.max_capacity())
+    }
+}
+
+/// The receiving half of an instrumented mpsc channel.
+///
+/// See [`tokio::sync::mpsc::Receiver`].
+#[derive(Debug)]
+pub struct Receiver<T> {
+    inner: tokio::sync::mpsc::Receiver<T>,
+    shared: Arc<Shared>,
+}
+
+impl<T> Receiver<T> {
+    /// Record the number of messages in the channel after receiving one.
+    fn record_received(&self) {
+        self.shared
+            .record_len(self.inner.len(), self.inner.max_capacity());
+    }
+
+    /// Receive the next value.
+    ///
+    /// See [`tokio::sync::mpsc::Receiver::recv`].
+    pub async fn recv(&mut self) -> Option<T> {
+        let value = self.inner.recv().await?;
+        self.record_received();
+        Some(value)
+    }
+
+    /// Try to receive the next value, without waiting.
+    ///
+    /// See [`tokio::sync::mpsc::Receiver::try_recv`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel is empty, or if it is closed and all
+    /// the values were received.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let value = self.inner.try_recv()?;
+        self.record_received();
+        Ok(value)
+    }
+
+    /// Close the channel, preventing any further message from being sent.
+    ///
+    /// See [`tokio::sync::mpsc::Receiver::close`].
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+
+    /// Returns `true` if the channel is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Returns the number of messages in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no messages in the channel.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the number of messages which can be sent without waiting.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Returns the capacity the channel was created with.
+    #[must_use]
+    pub fn max_capacity(&self) -> usize {
+        self.inner.max_capacity()
+    }
+}
+
+/// The state shared by both halves of an unbounded channel, and the registry
+/// of unbounded channels.
+#[derive(Debug)]
+struct UnboundedShared {
+    labels: [KeyValue; 1],
+    len: AtomicUsize,
+    high_water_mark: AtomicUsize,
+
+    /// The number of consecutive collections the channel must grow for to
+    /// emit a warning, or zero to never warn
+    #[cfg(feature = "tracing")]
+    warn_after: AtomicU32,
+
+    /// The length as of the last collection, and the number of consecutive
+    /// collections it grew, shared by all the metric readers
+    growth: Mutex<(usize, u32)>,
+}
+
+impl UnboundedShared {
+    /// Track the growth of the channel on collection, returning its length.
+    fn collect(&self) -> usize {
+        let len = self.len.load(Ordering::Relaxed);
+        let mut growth = crate::recover_lock(self.growth.lock(), "channel growth");
+        let (last_len, growing_for) = &mut *growth;
+        *growing_for = if len > *last_len {
+            growing_for.saturating_add(1)
+        } else {
+            0
+        };
+        *last_len = len;
+
+        #[cfg(feature = "tracing")]
+        {
+            let warn_after = self.warn_after.load(Ordering::Relaxed);
+            if warn_after > 0 && *growing_for == warn_after {
+                tracing::warn!(
+                    channel = %self.labels[0].value,
+                    len,
+                    collections = warn_after,
+                    "unbounded channel keeps growing",
+                );
+            }
+        }
+
+        len
+    }
+}
+
+/// All the unbounded channels, observed on collection.
+static UNBOUNDED_CHANNELS: RwLock<Vec<Weak<UnboundedShared>>> = RwLock::new(Vec::new());
+
+/// Call the given function with each live unbounded channel.
+fn for_each_unbounded_channel(mut f: impl FnMut(&UnboundedShared)) {
+    for channel in crate::recover_lock(UNBOUNDED_CHANNELS.read(), "unbounded channels").iter() {
+        if let Some(channel) = channel.upgrade() {
+            f(&channel);
+        }
+    }
+}
+
+fn register_unbounded_instruments(meter: &Meter) {
+    meter
+        .u64_observable_gauge("tokio.mpsc.len")
+        .with_description("The approximate number of messages in the unbounded channel")
+        .with_unit("{message}")
+        .with_callback(|instrument| {
+            for_each_unbounded_channel(|channel| {
+                instrument.observe(
+                    channel
+                        .collect()
+                        .try_into()
+                        .unwrap_or_else(|_| crate::overflowed("tokio.mpsc.len", u64::MAX)),
+                    &channel.labels,
+                );
+            });
+        })
+        .build();
+
+    meter
+        .u64_observable_gauge("tokio.mpsc.high_water_mark")
+        .with_description("The highest number of messages the unbounded channel held")
+        .with_unit("{message}")
+        .with_callback(|instrument| {
+            for_each_unbounded_channel(|channel| {
+                instrument.observe(
+                    channel
+                        .high_water_mark
+                        .load(Ordering::Relaxed)
+                        .try_into()
+                        .unwrap_or_else(|_| {
+                            crate::overflowed("tokio.mpsc.high_water_mark", u64::MAX)
+                        }),
+                    &channel.labels,
+                );
+            });
+        })
+        .build();
+}
+
+/// Create an instrumented unbounded mpsc channel with the given name.
+///
+/// See [`tokio::sync::mpsc::unbounded_channel`]. The channel is observed
+/// until both of its halves are dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::sync::mpsc;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (tx, mut rx) = mpsc::unbounded_channel("events");
+///
+/// tokio::spawn(async move {
+///     while let Some(event) = rx.recv().await {
+///         println!("got {event}");
+///     }
+/// });
+///
+/// tx.send(42).unwrap();
+/// # }
+/// ```
+#[must_use]
+pub fn unbounded_channel<T>(name: impl Into<Value>) -> (UnboundedSender<T>, UnboundedReceiver<T>) {
+    static INSTRUMENTS: Once = Once::new();
+    INSTRUMENTS.call_once(|| register_unbounded_instruments(&crate::meter()));
+
+    let shared = Arc::new(UnboundedShared {
+        labels: [KeyValue::new(
+            Key::from_static_str("tokio.channel.name"),
+            name.into(),
+        )],
+        len: AtomicUsize::new(0),
+        high_water_mark: AtomicUsize::new(0),
+        #[cfg(feature = "tracing")]
+        warn_after: AtomicU32::new(0),
+        growth: Mutex::new((0, 0)),
+    });
+
+    {
+        let mut channels = crate::recover_lock(UNBOUNDED_CHANNELS.write(), "unbounded channels");
+        // Clean up the channels which were dropped since the last one was
+        // created
+        channels.retain(|channel| channel.strong_count() > 0);
+        channels.push(Arc::downgrade(&shared));
+    }
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let tx = UnboundedSender {
+        inner: tx,
+        shared: shared.clone(),
+    };
+    let rx = UnboundedReceiver { inner: rx, shared };
+    (tx, rx)
+}
+
+/// The sending half of an instrumented unbounded mpsc channel.
+///
+/// See [`tokio::sync::mpsc::UnboundedSender`].
+#[derive(Debug)]
+pub struct UnboundedSender<T> {
+    inner: tokio::sync::mpsc::UnboundedSender<T>,
+    shared: Arc<UnboundedShared>,
+}
+
+impl<T> Clone for UnboundedSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> UnboundedSender<T> {
+    /// Send a value, without waiting.
+    ///
+    /// See [`tokio::sync::mpsc::UnboundedSender::send`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped or closed, in which
+    /// case the message is counted as dropped.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        // Count the message before sending it, so that the receiver never
+        // sees it before it is counted
+        let len = self.shared.len.fetch_add(1, Ordering::Relaxed) + 1;
+        if let Err(error) = self.inner.send(value) {
+            self.shared.len.fetch_sub(1, Ordering::Relaxed);
+            MPSC_INSTRUMENTS
+                .messages_dropped
+                .add(1, &self.shared.labels);
+            return Err(error);
+        }
+
+        self.shared
+            .high_water_mark
+            .fetch_max(len, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Wait for the receiver to be dropped or closed.
+    ///
+    /// See [`tokio::sync::mpsc::UnboundedSender::closed`].
+    pub async fn closed(&self) {
+        self.inner.closed().await;
+    }
+
+    /// Returns `true` if the receiver was dropped or closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+/// The depth of the channel can be collected in the metrics by registering a
+/// sender with [`queue::register`](crate::queue::register).
+impl<T: Send> crate::queue::InstrumentedQueue for UnboundedSender<T> {
+    fn len(&self) -> usize {
+        self.shared.len.load(Ordering::Relaxed)
+    }
+}
+
+/// The receiving half of an instrumented unbounded mpsc channel.
+///
+/// See [`tokio::sync::mpsc::UnboundedReceiver`].
+#[derive(Debug)]
+pub struct UnboundedReceiver<T> {
+    inner: tokio::sync::mpsc::UnboundedReceiver<T>,
+    shared: Arc<UnboundedShared>,
+}
+
+impl<T> UnboundedReceiver<T> {
+    /// Count a message out of the channel.
+    fn record_received(&self) {
+        self.shared.len.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Emit a warning through `tracing` when the channel grows on the given
+    /// number of consecutive collections, which usually means the receiver
+    /// can't keep up, or that it got stuck.
+    ///
+    /// The collections of all the metric readers are counted together, as
+    /// they can't be told apart. With several readers, e.g. a Prometheus
+    /// exporter and a periodic OTLP exporter, the channel must grow between
+    /// the collections of any two of them, so a shorter interval between two
+    /// collections makes it less likely to be seen growing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `collections` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::sync::mpsc;
+    ///
+    /// let (tx, rx) = mpsc::unbounded_channel::<String>("events");
+    /// rx.warn_on_growth(10);
+    /// ```
+    #[cfg(feature = "tracing")]
+    pub fn warn_on_growth(&self, collections: u32) {
+        assert!(
+            collections > 0,
+            "the number of collections must be non-zero"
+        );
+        self.shared.warn_after.store(collections, Ordering::Relaxed);
+    }
+
+    /// Receive the next value.
+    ///
+    /// See [`tokio::sync::mpsc::UnboundedReceiver::recv`].
+    pub async fn recv(&mut self) -> Option<T> {
+        let value = self.inner.recv().await?;
+        self.record_received();
+        Some(value)
+    }
+
+    /// Try to receive the next value, without waiting.
+    ///
+    /// See [`tokio::sync::mpsc::UnboundedReceiver::try_recv`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the channel is empty, or if it is closed and all
+    /// the values were received.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let value = self.inner.try_recv()?;
+        self.record_received();
+        Ok(value)
+    }
+
+    /// Close the channel, preventing any further message from being sent.
+    ///
+    /// See [`tokio::sync::mpsc::UnboundedReceiver::close`].
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+
+    /// Returns `true` if the channel is closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+
+    /// Returns the number of messages in the channel.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no messages in the channel.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<T> Drop for UnboundedReceiver<T> {
+    fn drop(&mut self) {
+        // The messages still in the channel are dropped with the receiver.
+        // Closing it first makes the senders count out the messages they send
+        // from now on themselves
+        self.inner.close();
+        self.shared
+            .len
+            .fetch_sub(self.inner.len(), Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,159 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::{Mutex, MutexGuard};
+
+/// Instruments shared by all [`InstrumentedMutex`]s.
+struct MutexInstruments {
+    acquisitions: Counter<u64>,
+    wait_duration: Histogram<f64>,
+    hold_duration: Histogram<f64>,
+}
+
+static MUTEX_INSTRUMENTS: LazyLock<MutexInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    MutexInstruments {
+        acquisitions: meter
+            .u64_counter("tokio.mutex.acquisitions")
+            .with_description("The number of times the mutex was locked")
+            .with_unit("{acquisition}")
+            .build(),
+        wait_duration: meter
+            .f64_histogram("tokio.mutex.wait_duration")
+            .with_description("The time tasks spent waiting to lock the mutex")
+            .with_unit("s")
+            .build(),
+        hold_duration: meter
+            .f64_histogram("tokio.mutex.hold_duration")
+            .with_description("The time the mutex was held for once locked")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// A [`Mutex`] recording how often it is locked, the time spent waiting for
+/// it, and how long it is held.
+///
+/// Metrics are labelled with the name given on creation (`tokio.mutex.name`):
+///
+/// - `tokio.mutex.acquisitions` - Times the mutex was locked
+/// - `tokio.mutex.wait_duration` - Time tasks spent waiting to lock the mutex
+/// - `tokio.mutex.hold_duration` - Time the mutex was held for, measured when
+///   the guard is dropped
+///
+/// As the guard of an async mutex can be held across `.await` points, long
+/// hold times are what make the tasks sharing it run one after the other.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use opentelemetry_instrumentation_tokio::sync::InstrumentedMutex;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let counter = Arc::new(InstrumentedMutex::new("counter", 0));
+///
+/// let task_counter = counter.clone();
+/// tokio::spawn(async move {
+///     *task_counter.lock().await += 1;
+/// });
+///
+/// *counter.lock().await += 1;
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedMutex<T> {
+    inner: Mutex<T>,
+    labels: [KeyValue; 1],
+}
+
+impl<T> InstrumentedMutex<T> {
+    /// Create a new instrumented [`Mutex`] with the given name, holding the
+    /// given value.
+    ///
+    /// See [`Mutex::new`].
+    #[must_use]
+    pub fn new(name: impl Into<Value>, value: T) -> Self {
+        Self {
+            inner: Mutex::new(value),
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.mutex.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Lock the mutex, recording the time spent waiting, and how long the
+    /// returned guard is held.
+    ///
+    /// See [`Mutex::lock`].
+    pub async fn lock(&self) -> InstrumentedMutexGuard<'_, T> {
+        let start = Instant::now();
+        let guard = self.inner.lock().await;
+        let acquired_at = Instant::now();
+
+        MUTEX_INSTRUMENTS
+            .wait_duration
+            .record((acquired_at - start).as_secs_f64(), &self.labels);
+        MUTEX_INSTRUMENTS.acquisitions.add(1, &self.labels);
+
+        InstrumentedMutexGuard {
+            guard,
+            acquired_at,
+            labels: &self.labels,
+        }
+    }
+
+    /// Get a mutable reference to the value, without locking.
+    ///
+    /// See [`Mutex::get_mut`].
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    /// Consume the mutex, returning the value.
+    ///
+    /// See [`Mutex::into_inner`].
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
+
+/// The guard of an [`InstrumentedMutex`], recording how long it was held
+/// when dropped.
+///
+/// This dereferences to the value of the mutex, as [`MutexGuard`] does.
+#[derive(Debug)]
+pub struct InstrumentedMutexGuard<'a, T> {
+    guard: MutexGuard<'a, T>,
+    acquired_at: Instant,
+    labels: &'a [KeyValue],
+}
+
+impl<T> Deref for InstrumentedMutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for InstrumentedMutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for InstrumentedMutexGuard<'_, T> {
+    fn drop(&mut self) {
+        MUTEX_INSTRUMENTS
+            .hold_duration
+            .record(self.acquired_at.elapsed().as_secs_f64(), self.labels);
+    }
+}
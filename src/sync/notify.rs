@@ -0,0 +1,144 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::Notify;
+
+/// Instruments shared by all [`InstrumentedNotify`]s.
+struct NotifyInstruments {
+    notifications: Counter<u64>,
+    waiters: UpDownCounter<i64>,
+    wait_duration: Histogram<f64>,
+}
+
+static NOTIFY_INSTRUMENTS: LazyLock<NotifyInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    NotifyInstruments {
+        notifications: meter
+            .u64_counter("tokio.notify.notifications")
+            .with_description("The number of notifications sent")
+            .with_unit("{notification}")
+            .build(),
+        waiters: meter
+            .i64_up_down_counter("tokio.notify.waiters")
+            .with_description("The number of tasks currently waiting for a notification")
+            .with_unit("{waiter}")
+            .build(),
+        wait_duration: meter
+            .f64_histogram("tokio.notify.wait_duration")
+            .with_description("The time tasks spent waiting for a notification")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// Decrements the waiters count when the waiting future completes or is
+/// dropped.
+struct WaiterGuard<'a> {
+    labels: &'a [KeyValue],
+}
+
+impl<'a> WaiterGuard<'a> {
+    fn new(labels: &'a [KeyValue]) -> Self {
+        NOTIFY_INSTRUMENTS.waiters.add(1, labels);
+        Self { labels }
+    }
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        NOTIFY_INSTRUMENTS.waiters.add(-1, self.labels);
+    }
+}
+
+/// A [`Notify`] recording notifications and the time spent waiting for them.
+///
+/// Metrics are labelled with the name given on creation
+/// (`tokio.notify.name`):
+///
+/// - `tokio.notify.notifications` - Notifications sent, with a
+///   `tokio.notify.method` attribute set to either `notify_one` or
+///   `notify_waiters`
+/// - `tokio.notify.waiters` - Tasks currently waiting for a notification
+/// - `tokio.notify.wait_duration` - Time tasks spent waiting for a notification
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::Arc;
+///
+/// use opentelemetry_instrumentation_tokio::sync::InstrumentedNotify;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let notify = Arc::new(InstrumentedNotify::new("config_reload"));
+///
+/// let waiter = notify.clone();
+/// tokio::spawn(async move {
+///     waiter.notified().await;
+///     // Reload the configuration
+/// });
+///
+/// notify.notify_one();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedNotify {
+    inner: Notify,
+    labels: [KeyValue; 1],
+}
+
+impl InstrumentedNotify {
+    /// Create a new instrumented [`Notify`] with the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>) -> Self {
+        Self {
+            inner: Notify::new(),
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.notify.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Record a notification sent with the given method.
+    fn record_notification(&self, method: &'static str) {
+        let [name] = &self.labels;
+        let labels = [
+            name.clone(),
+            KeyValue::new(Key::from_static_str("tokio.notify.method"), method),
+        ];
+        NOTIFY_INSTRUMENTS.notifications.add(1, &labels);
+    }
+
+    /// Wait for a notification, recording the time spent waiting.
+    ///
+    /// Unlike [`Notify::notified`], the waiter is only registered when the
+    /// returned future is first polled.
+    pub async fn notified(&self) {
+        let _guard = WaiterGuard::new(&self.labels);
+        let start = Instant::now();
+        self.inner.notified().await;
+        NOTIFY_INSTRUMENTS
+            .wait_duration
+            .record(start.elapsed().as_secs_f64(), &self.labels);
+    }
+
+    /// Notify the first waiting task, or store a permit for the next one.
+    ///
+    /// See [`Notify::notify_one`].
+    pub fn notify_one(&self) {
+        self.record_notification("notify_one");
+        self.inner.notify_one();
+    }
+
+    /// Notify all the waiting tasks.
+    ///
+    /// See [`Notify::notify_waiters`].
+    pub fn notify_waiters(&self) {
+        self.record_notification("notify_waiters");
+        self.inner.notify_waiters();
+    }
+}
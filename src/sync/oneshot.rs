@@ -0,0 +1,186 @@
+//! An instrumented [`tokio::sync::oneshot`] channel.
+//!
+//! The channel records:
+//!
+//! - `tokio.oneshot.senders_dropped` - Senders dropped without sending a value
+//! - `tokio.oneshot.receivers_dropped` - Receivers dropped before getting a
+//!   value or an error
+//!
+//! Both usually point to a lost reply: either the responder forgot to reply,
+//! or the requester stopped waiting for it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::sync::oneshot;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let (tx, rx) = oneshot::channel("reply");
+//!
+//! tokio::spawn(async move {
+//!     tx.send(42).unwrap();
+//! });
+//!
+//! let value = rx.await.unwrap();
+//! # }
+//! ```
+
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock};
+use std::task::{Context, Poll};
+
+use opentelemetry::metrics::Counter;
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::oneshot::error::{RecvError, TryRecvError};
+
+/// Instruments shared by all oneshot channels.
+struct OneshotInstruments {
+    senders_dropped: Counter<u64>,
+    receivers_dropped: Counter<u64>,
+}
+
+static ONESHOT_INSTRUMENTS: LazyLock<OneshotInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    OneshotInstruments {
+        senders_dropped: meter
+            .u64_counter("tokio.oneshot.senders_dropped")
+            .with_description("The number of senders dropped without sending a value")
+            .with_unit("{sender}")
+            .build(),
+        receivers_dropped: meter
+            .u64_counter("tokio.oneshot.receivers_dropped")
+            .with_description("The number of receivers dropped before completing")
+            .with_unit("{receiver}")
+            .build(),
+    }
+});
+
+/// Create an instrumented oneshot channel with the given name.
+///
+/// See [`tokio::sync::oneshot::channel`].
+#[must_use]
+pub fn channel<T>(name: impl Into<Value>) -> (Sender<T>, Receiver<T>) {
+    let labels: Arc<[KeyValue]> = Arc::new([KeyValue::new(
+        Key::from_static_str("tokio.channel.name"),
+        name.into(),
+    )]);
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = Sender {
+        inner: Some(tx),
+        labels: labels.clone(),
+    };
+    let rx = Receiver {
+        inner: rx,
+        labels,
+        completed: false,
+    };
+    (tx, rx)
+}
+
+/// The sending half of an instrumented oneshot channel.
+///
+/// See [`tokio::sync::oneshot::Sender`].
+#[derive(Debug)]
+pub struct Sender<T> {
+    // Only `None` once the value was sent
+    inner: Option<tokio::sync::oneshot::Sender<T>>,
+    labels: Arc<[KeyValue]>,
+}
+
+impl<T> Sender<T> {
+    /// Send a value to the receiver.
+    ///
+    /// See [`tokio::sync::oneshot::Sender::send`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the value back if the receiver was dropped.
+    pub fn send(mut self, value: T) -> Result<(), T> {
+        match self.inner.take() {
+            Some(inner) => inner.send(value),
+            None => Err(value),
+        }
+    }
+
+    /// Returns `true` if the receiver was dropped or closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner
+            .as_ref()
+            .is_none_or(tokio::sync::oneshot::Sender::is_closed)
+    }
+
+    /// Wait for the receiver to be dropped or closed.
+    ///
+    /// See [`tokio::sync::oneshot::Sender::closed`].
+    pub async fn closed(&mut self) {
+        if let Some(inner) = &mut self.inner {
+            inner.closed().await;
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            ONESHOT_INSTRUMENTS.senders_dropped.add(1, &self.labels);
+        }
+    }
+}
+
+/// The receiving half of an instrumented oneshot channel.
+///
+/// This is a future resolving to the sent value. See
+/// [`tokio::sync::oneshot::Receiver`].
+#[derive(Debug)]
+pub struct Receiver<T> {
+    inner: tokio::sync::oneshot::Receiver<T>,
+    labels: Arc<[KeyValue]>,
+    completed: bool,
+}
+
+impl<T> Receiver<T> {
+    /// Prevent the sender from sending a value.
+    ///
+    /// See [`tokio::sync::oneshot::Receiver::close`].
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+
+    /// Try to receive the value, without waiting.
+    ///
+    /// See [`tokio::sync::oneshot::Receiver::try_recv`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the value wasn't sent yet, or if the sender was
+    /// dropped.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let result = self.inner.try_recv();
+        if !matches!(result, Err(TryRecvError::Empty)) {
+            self.completed = true;
+        }
+        result
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, RecvError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let result = std::task::ready!(Pin::new(&mut self.inner).poll(cx));
+        self.completed = true;
+        Poll::Ready(result)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        if !self.completed {
+            ONESHOT_INSTRUMENTS.receivers_dropped.add(1, &self.labels);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Instruments shared by all [`InstrumentedRwLock`]s.
+struct RwLockInstruments {
+    acquisitions: Counter<u64>,
+    wait_duration: Histogram<f64>,
+}
+
+static RWLOCK_INSTRUMENTS: LazyLock<RwLockInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    RwLockInstruments {
+        acquisitions: meter
+            .u64_counter("tokio.rwlock.acquisitions")
+            .with_description("The number of times the lock was acquired")
+            .with_unit("{acquisition}")
+            .build(),
+        wait_duration: meter
+            .f64_histogram("tokio.rwlock.wait_duration")
+            .with_description("The time tasks spent waiting to acquire the lock")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// A [`RwLock`] recording how often it is acquired, and the time spent
+/// waiting for it, for reads and writes separately.
+///
+/// Metrics are labelled with the name given on creation
+/// (`tokio.rwlock.name`), and with a `tokio.rwlock.mode` attribute set to
+/// either `read` or `write`:
+///
+/// - `tokio.rwlock.acquisitions` - Times the lock was acquired
+/// - `tokio.rwlock.wait_duration` - Time tasks spent waiting to acquire the
+///   lock
+///
+/// Long waits for writes while reads go through quickly point to writers
+/// being starved by a steady flow of readers, whereas long waits for reads
+/// point to writers holding the lock for too long.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+///
+/// use opentelemetry_instrumentation_tokio::sync::InstrumentedRwLock;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let cache = Arc::new(InstrumentedRwLock::new("cache", HashMap::new()));
+///
+/// cache.write().await.insert("key", "value");
+/// assert_eq!(cache.read().await.get("key"), Some(&"value"));
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedRwLock<T> {
+    inner: RwLock<T>,
+    read_labels: [KeyValue; 2],
+    write_labels: [KeyValue; 2],
+}
+
+impl<T> InstrumentedRwLock<T> {
+    /// Create a new instrumented [`RwLock`] with the given name, holding the
+    /// given value.
+    ///
+    /// See [`RwLock::new`].
+    #[must_use]
+    pub fn new(name: impl Into<Value>, value: T) -> Self {
+        let name = KeyValue::new(Key::from_static_str("tokio.rwlock.name"), name.into());
+        let mode =
+            |mode: &'static str| KeyValue::new(Key::from_static_str("tokio.rwlock.mode"), mode);
+
+        Self {
+            inner: RwLock::new(value),
+            read_labels: [name.clone(), mode("read")],
+            write_labels: [name, mode("write")],
+        }
+    }
+
+    /// Record an acquisition of the lock, after waiting since `start`.
+    fn record_acquisition(start: Instant, labels: &[KeyValue]) {
+        RWLOCK_INSTRUMENTS
+            .wait_duration
+            .record(start.elapsed().as_secs_f64(), labels);
+        RWLOCK_INSTRUMENTS.acquisitions.add(1, labels);
+    }
+
+    /// Lock for reading, recording the time spent waiting.
+    ///
+    /// See [`RwLock::read`].
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        let start = Instant::now();
+        let guard = self.inner.read().await;
+        Self::record_acquisition(start, &self.read_labels);
+        guard
+    }
+
+    /// Lock for writing, recording the time spent waiting.
+    ///
+    /// See [`RwLock::write`].
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        let start = Instant::now();
+        let guard = self.inner.write().await;
+        Self::record_acquisition(start, &self.write_labels);
+        guard
+    }
+
+    /// Get a mutable reference to the value, without locking.
+    ///
+    /// See [`RwLock::get_mut`].
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut()
+    }
+
+    /// Consume the lock, returning the value.
+    ///
+    /// See [`RwLock::into_inner`].
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner()
+    }
+}
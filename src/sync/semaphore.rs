@@ -0,0 +1,232 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::sync::{AcquireError, Semaphore, SemaphorePermit, TryAcquireError};
+
+/// Instruments shared by all [`InstrumentedSemaphore`]s.
+struct SemaphoreInstruments {
+    acquisitions: Counter<u64>,
+    acquire_timeouts: Counter<u64>,
+    closed_acquires: Counter<u64>,
+    wait_duration: Histogram<f64>,
+}
+
+static SEMAPHORE_INSTRUMENTS: LazyLock<SemaphoreInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    SemaphoreInstruments {
+        acquisitions: meter
+            .u64_counter("tokio.semaphore.acquisitions")
+            .with_description("The number of times permits were acquired")
+            .with_unit("{acquisition}")
+            .build(),
+        acquire_timeouts: meter
+            .u64_counter("tokio.semaphore.acquire_timeouts")
+            .with_description("The number of acquires given up before getting the permits")
+            .with_unit("{acquire}")
+            .build(),
+        closed_acquires: meter
+            .u64_counter("tokio.semaphore.closed_acquires")
+            .with_description(
+                "The number of acquires which failed because the semaphore was closed",
+            )
+            .with_unit("{acquire}")
+            .build(),
+        wait_duration: meter
+            .f64_histogram("tokio.semaphore.wait_duration")
+            .with_description("The time tasks spent waiting to acquire permits")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// Counts the acquire as timed out if the acquiring future is dropped before
+/// it completes.
+struct AcquireGuard<'a> {
+    labels: &'a [KeyValue],
+    completed: bool,
+}
+
+impl<'a> AcquireGuard<'a> {
+    fn new(labels: &'a [KeyValue]) -> Self {
+        Self {
+            labels,
+            completed: false,
+        }
+    }
+
+    /// Record the outcome of the acquire, after waiting since `start`.
+    fn complete<T>(mut self, start: Instant, result: &Result<T, AcquireError>) {
+        self.completed = true;
+        match result {
+            Ok(_) => {
+                SEMAPHORE_INSTRUMENTS
+                    .wait_duration
+                    .record(start.elapsed().as_secs_f64(), self.labels);
+                SEMAPHORE_INSTRUMENTS.acquisitions.add(1, self.labels);
+            }
+            Err(_) => SEMAPHORE_INSTRUMENTS.closed_acquires.add(1, self.labels),
+        }
+    }
+}
+
+impl Drop for AcquireGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            SEMAPHORE_INSTRUMENTS.acquire_timeouts.add(1, self.labels);
+        }
+    }
+}
+
+/// A [`Semaphore`] recording the permits acquired, the time spent waiting for
+/// them, and the acquires which failed.
+///
+/// Metrics are labelled with the name given on creation
+/// (`tokio.semaphore.name`):
+///
+/// - `tokio.semaphore.acquisitions` - Times permits were acquired
+/// - `tokio.semaphore.wait_duration` - Time tasks spent waiting to acquire
+///   permits
+/// - `tokio.semaphore.acquire_timeouts` - Acquires given up before getting the
+///   permits, e.g. because they were wrapped in a [`tokio::time::timeout`]
+///   which elapsed
+/// - `tokio.semaphore.closed_acquires` - Acquires which failed because the
+///   semaphore was closed
+///
+/// When a semaphore limits concurrency, acquires timing out tell how often
+/// the work was turned away because all the permits were taken.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use opentelemetry_instrumentation_tokio::sync::InstrumentedSemaphore;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let connections = InstrumentedSemaphore::new("connections", 16);
+///
+/// match tokio::time::timeout(Duration::from_secs(1), connections.acquire()).await {
+///     Ok(Ok(_permit)) => {
+///         // Handle the connection
+///     }
+///     // Counted in `tokio.semaphore.closed_acquires`
+///     Ok(Err(_closed)) => {}
+///     // Counted in `tokio.semaphore.acquire_timeouts`
+///     Err(_elapsed) => {}
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedSemaphore {
+    inner: Semaphore,
+    labels: [KeyValue; 1],
+}
+
+impl InstrumentedSemaphore {
+    /// Create a new instrumented [`Semaphore`] with the given name and number
+    /// of permits.
+    ///
+    /// See [`Semaphore::new`].
+    #[must_use]
+    pub fn new(name: impl Into<Value>, permits: usize) -> Self {
+        Self {
+            inner: Semaphore::new(permits),
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.semaphore.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Acquire a permit, recording the time spent waiting.
+    ///
+    /// If the returned future is dropped before it completes, the acquire is
+    /// counted as timed out.
+    ///
+    /// See [`Semaphore::acquire`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the semaphore has been closed.
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, AcquireError> {
+        let guard = AcquireGuard::new(&self.labels);
+        let start = Instant::now();
+        let result = self.inner.acquire().await;
+        guard.complete(start, &result);
+        result
+    }
+
+    /// Acquire `n` permits, recording the time spent waiting.
+    ///
+    /// If the returned future is dropped before it completes, the acquire is
+    /// counted as timed out.
+    ///
+    /// See [`Semaphore::acquire_many`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the semaphore has been closed.
+    pub async fn acquire_many(&self, n: u32) -> Result<SemaphorePermit<'_>, AcquireError> {
+        let guard = AcquireGuard::new(&self.labels);
+        let start = Instant::now();
+        let result = self.inner.acquire_many(n).await;
+        guard.complete(start, &result);
+        result
+    }
+
+    /// Try to acquire a permit without waiting.
+    ///
+    /// Failing because no permits are available isn't counted as a timeout.
+    ///
+    /// See [`Semaphore::try_acquire`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the semaphore has been closed, or if there are no
+    /// permits available.
+    pub fn try_acquire(&self) -> Result<SemaphorePermit<'_>, TryAcquireError> {
+        let result = self.inner.try_acquire();
+        match result {
+            Ok(_) => SEMAPHORE_INSTRUMENTS.acquisitions.add(1, &self.labels),
+            Err(TryAcquireError::Closed) => {
+                SEMAPHORE_INSTRUMENTS.closed_acquires.add(1, &self.labels);
+            }
+            Err(TryAcquireError::NoPermits) => {}
+        }
+        result
+    }
+
+    /// The number of permits currently available.
+    ///
+    /// See [`Semaphore::available_permits`].
+    #[must_use]
+    pub fn available_permits(&self) -> usize {
+        self.inner.available_permits()
+    }
+
+    /// Add `n` permits to the semaphore.
+    ///
+    /// See [`Semaphore::add_permits`].
+    pub fn add_permits(&self, n: usize) {
+        self.inner.add_permits(n);
+    }
+
+    /// Close the semaphore, making the pending and future acquires fail.
+    ///
+    /// See [`Semaphore::close`].
+    pub fn close(&self) {
+        self.inner.close();
+    }
+
+    /// Whether the semaphore has been closed.
+    ///
+    /// See [`Semaphore::is_closed`].
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
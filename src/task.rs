@@ -0,0 +1,593 @@
+//! Instrumentation for tasks.
+//!
+//! Tasks spawned through the APIs of this module are wrapped in a future
+//! recording how many tasks are alive, how many were spawned, how long their
+//! polls take, and how they ended.
+//!
+//! Tasks spawned with [`spawn`] or a [`Builder`] are recorded with their name
+//! (`tokio.task.name`) and the labels of the runtime they run on:
+//!
+//! - `tokio.task.alive_tasks` - Tasks currently alive
+//! - `tokio.task.spawned_tasks` - Tasks spawned
+//! - `tokio.task.poll_duration` - Duration of the task polls
+//! - `tokio.task.polls` - Number of times each task was polled over its
+//!   lifetime
+//! - `tokio.task.duration` - Time between the task being spawned and it
+//!   completing
+//! - `tokio.task.first_poll_delay` - Time between the task being spawned and
+//!   its first poll
+//! - `tokio.task.poll_time` - Time spent polling the task
+//! - `tokio.task.scheduled_time` - Time spent waiting to be polled after being
+//!   woken (or spawned)
+//! - `tokio.task.idle_time` - Time spent waiting to be woken, e.g. on I/O or
+//!   timers
+//! - `tokio.task.completed_tasks` - Tasks which ran to completion
+//! - `tokio.task.cancelled_tasks` - Tasks dropped before completing, because
+//!   they were aborted or the runtime shut down
+//! - `tokio.task.panics` - Tasks which panicked
+//!
+//! Blocking closures run with [`spawn_blocking`] are recorded in
+//! `tokio.task.blocking_in_flight` while they execute. With the
+//! `rt-multi-thread` feature, closures run with [`block_in_place`] are
+//! recorded in `tokio.task.block_in_place.duration`, as they take over the
+//! worker they run on.
+//!
+//! With the `logs` feature, panics are also emitted as `tokio.task.panic` log
+//! records, with the panic message (`exception.message`), through the logger
+//! provider set with
+//! [`Config::with_logger_provider`](crate::Config::with_logger_provider), so
+//! that they don't go unnoticed when nobody awaits the [`JoinHandle`].
+//!
+//! With [`Config::with_task_sampling`](crate::Config::with_task_sampling),
+//! only a fraction of the tasks are recorded, with the ratio in the
+//! `tokio.task.sampling_ratio` label, and the others run as is.
+//!
+//! With `tokio_unstable`, `TaskHooks` records the polls of all the tasks of a
+//! runtime instead, through the task hooks of its builder.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() {
+//! let handle = opentelemetry_instrumentation_tokio::task::spawn("flush", async {
+//!     // Flush the buffers
+//! });
+//! handle.await.unwrap();
+//! # }
+//! ```
+
+#[cfg(feature = "logs")]
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, OnceLock};
+use std::task::{Context, Poll, Waker};
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram, Meter, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::task::JoinHandle;
+
+mod builder;
+#[cfg(tokio_unstable)]
+mod hooks;
+mod local_set;
+mod waker;
+
+pub use self::builder::Builder;
+#[cfg(tokio_unstable)]
+pub use self::hooks::TaskHooks;
+pub use self::local_set::InstrumentedLocalSet;
+use self::waker::TaskWaker;
+
+static TASK_INSTRUMENTS: LazyLock<TaskInstruments> =
+    LazyLock::new(|| TaskInstruments::new(&crate::meter(), "tokio.task"));
+
+/// A set of instruments recording the lifecycle of tasks.
+///
+/// Each API spawning instrumented tasks has its own set, registered under its
+/// own prefix.
+struct TaskInstruments {
+    alive_tasks: UpDownCounter<i64>,
+    spawned_tasks: Counter<u64>,
+    poll_duration: Histogram<f64>,
+    polls: Histogram<u64>,
+    duration: Histogram<f64>,
+    first_poll_delay: Histogram<f64>,
+    poll_time: Counter<f64>,
+    scheduled_time: Counter<f64>,
+    idle_time: Counter<f64>,
+    completed_tasks: Counter<u64>,
+    cancelled_tasks: Counter<u64>,
+    panics: Counter<u64>,
+}
+
+impl TaskInstruments {
+    /// Register the instruments with the given metric name prefix.
+    fn new(meter: &Meter, prefix: &str) -> Self {
+        Self {
+            alive_tasks: meter
+                .i64_up_down_counter(format!("{prefix}.alive_tasks"))
+                .with_description("The number of tasks currently alive")
+                .with_unit("{task}")
+                .build(),
+            spawned_tasks: meter
+                .u64_counter(format!("{prefix}.spawned_tasks"))
+                .with_description("The number of tasks spawned")
+                .with_unit("{task}")
+                .build(),
+            poll_duration: meter
+                .f64_histogram(format!("{prefix}.poll_duration"))
+                .with_description("The duration of task polls")
+                .with_unit("s")
+                .build(),
+            polls: meter
+                .u64_histogram(format!("{prefix}.polls"))
+                .with_description("The number of times tasks were polled over their lifetime")
+                .with_unit("{poll}")
+                .build(),
+            duration: meter
+                .f64_histogram(format!("{prefix}.duration"))
+                .with_description("The time between the task being spawned and it completing")
+                .with_unit("s")
+                .build(),
+            first_poll_delay: meter
+                .f64_histogram(format!("{prefix}.first_poll_delay"))
+                .with_description("The time between the task being spawned and its first poll")
+                .with_unit("s")
+                .build(),
+            poll_time: meter
+                .f64_counter(format!("{prefix}.poll_time"))
+                .with_description("The time spent polling tasks")
+                .with_unit("s")
+                .build(),
+            scheduled_time: meter
+                .f64_counter(format!("{prefix}.scheduled_time"))
+                .with_description("The time tasks spent waiting to be polled after being woken")
+                .with_unit("s")
+                .build(),
+            idle_time: meter
+                .f64_counter(format!("{prefix}.idle_time"))
+                .with_description("The time tasks spent waiting to be woken")
+                .with_unit("s")
+                .build(),
+            completed_tasks: meter
+                .u64_counter(format!("{prefix}.completed_tasks"))
+                .with_description("The number of tasks which ran to completion")
+                .with_unit("{task}")
+                .build(),
+            cancelled_tasks: meter
+                .u64_counter(format!("{prefix}.cancelled_tasks"))
+                .with_description(
+                    "The number of tasks dropped before completing, because they were aborted or the runtime shut down",
+                )
+                .with_unit("{task}")
+                .build(),
+            panics: meter
+                .u64_counter(format!("{prefix}.panics"))
+                .with_description("The number of tasks which panicked")
+                .with_unit("{task}")
+                .build(),
+        }
+    }
+}
+
+/// The sampler of the instrumented tasks, set by the first observed runtime
+/// configuring one, see
+/// [`Config::with_task_sampling`](crate::Config::with_task_sampling).
+static TASK_SAMPLER: OnceLock<TaskSampler> = OnceLock::new();
+
+/// Set the fraction of the tasks which are instrumented, unless it was
+/// already set, returning the fraction in use.
+pub(crate) fn set_sampling_ratio(ratio: f64) -> f64 {
+    TASK_SAMPLER.get_or_init(|| TaskSampler::new(ratio)).ratio
+}
+
+/// Picks the fraction of the tasks which are instrumented.
+///
+/// Each task is given a 64-bit hash, and is sampled if its hash is under a
+/// threshold proportional to the ratio.
+struct TaskSampler {
+    ratio: f64,
+    threshold: u64,
+
+    /// The `tokio.task.sampling_ratio` label of the sampled tasks
+    label: KeyValue,
+
+    /// The number of tasks spawned through the instrumented APIs so far
+    spawned: AtomicU64,
+}
+
+impl TaskSampler {
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss
+    )]
+    fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            // Saturates to `u64::MAX` for a ratio of 1
+            threshold: (ratio * u64::MAX as f64) as u64,
+            label: KeyValue::new(Key::from_static_str("tokio.task.sampling_ratio"), ratio),
+            spawned: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the task with the given hash is sampled.
+    fn samples(&self, hash: u64) -> bool {
+        hash <= self.threshold
+    }
+
+    /// Whether the next task spawned through the instrumented APIs is
+    /// sampled.
+    fn sample_next(&self) -> bool {
+        // Fibonacci hashing spreads consecutive numbers evenly, so that the
+        // sampled tasks are spread evenly too
+        let spawned = self.spawned.fetch_add(1, Ordering::Relaxed);
+        self.samples(spawned.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// A task which is instrumented if it was sampled.
+    #[project = SampledTaskProj]
+    enum SampledTask<F> {
+        Instrumented { #[pin] task: InstrumentedTask<F> },
+        Skipped { #[pin] future: F },
+    }
+}
+
+impl<F> SampledTask<F> {
+    /// Wrap a future about to be spawned, recording it as a new task with the
+    /// given labels if it is sampled.
+    fn new(
+        future: F,
+        instruments: &'static TaskInstruments,
+        labels: impl FnOnce() -> Arc<[KeyValue]>,
+    ) -> Self {
+        let labels = match TASK_SAMPLER.get() {
+            None => labels(),
+            Some(sampler) if sampler.sample_next() => {
+                [&labels()[..], std::slice::from_ref(&sampler.label)]
+                    .concat()
+                    .into()
+            }
+            Some(_) => return Self::Skipped { future },
+        };
+
+        Self::Instrumented {
+            task: InstrumentedTask::new(future, instruments, labels),
+        }
+    }
+}
+
+impl<F: Future> Future for SampledTask<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            SampledTaskProj::Instrumented { task } => task.poll(cx),
+            SampledTaskProj::Skipped { future } => future.poll(cx),
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// A future recording the lifecycle of the task running it.
+    struct InstrumentedTask<F> {
+        #[pin]
+        inner: F,
+        instruments: &'static TaskInstruments,
+        labels: Arc<[KeyValue]>,
+        spawned_at: Instant,
+        last_poll_end: Option<Instant>,
+        task_waker: Arc<TaskWaker>,
+        waker: Waker,
+        polls: u64,
+        completed: bool,
+        panicked: bool,
+    }
+
+    impl<F> PinnedDrop for InstrumentedTask<F> {
+        fn drop(this: Pin<&mut Self>) {
+            this.instruments.alive_tasks.add(-1, &this.labels);
+            this.instruments.polls.record(this.polls, &this.labels);
+
+            let outcome = if this.completed {
+                &this.instruments.completed_tasks
+            } else if this.panicked {
+                &this.instruments.panics
+            } else {
+                &this.instruments.cancelled_tasks
+            };
+            outcome.add(1, &this.labels);
+        }
+    }
+}
+
+/// Get the message of a panic from its payload.
+#[cfg(feature = "logs")]
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+impl<F> InstrumentedTask<F> {
+    /// Wrap a future about to be spawned, recording it as a new task.
+    fn new(inner: F, instruments: &'static TaskInstruments, labels: Arc<[KeyValue]>) -> Self {
+        instruments.spawned_tasks.add(1, &labels);
+        instruments.alive_tasks.add(1, &labels);
+        let task_waker = TaskWaker::new();
+        Self {
+            inner,
+            instruments,
+            labels,
+            spawned_at: Instant::now(),
+            last_poll_end: None,
+            waker: Waker::from(task_waker.clone()),
+            task_waker,
+            polls: 0,
+            completed: false,
+            panicked: false,
+        }
+    }
+}
+
+impl<F: Future> Future for InstrumentedTask<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let start = Instant::now();
+
+        // The task waits to be woken between the end of a poll and the
+        // wake-up, then waits to be polled. Polls without a wake-up going
+        // through our waker count as scheduled since the end of the last poll.
+        let woken_at = this.task_waker.take_woken_at();
+        if let (Some(woken_at), Some(last_poll_end)) = (woken_at, *this.last_poll_end) {
+            this.instruments.idle_time.add(
+                woken_at
+                    .saturating_duration_since(last_poll_end)
+                    .as_secs_f64(),
+                this.labels,
+            );
+        }
+        let scheduled_at = woken_at.or(*this.last_poll_end).unwrap_or_else(|| {
+            this.instruments.first_poll_delay.record(
+                start
+                    .saturating_duration_since(*this.spawned_at)
+                    .as_secs_f64(),
+                this.labels,
+            );
+            *this.spawned_at
+        });
+        this.instruments.scheduled_time.add(
+            start.saturating_duration_since(scheduled_at).as_secs_f64(),
+            this.labels,
+        );
+
+        *this.polls += 1;
+        this.task_waker.register(cx.waker());
+        let mut cx = Context::from_waker(this.waker);
+
+        // Catch the panic to record it, and resume it so that Tokio reports
+        // it through the `JoinHandle` as usual
+        let result = match std::panic::catch_unwind(AssertUnwindSafe(|| this.inner.poll(&mut cx))) {
+            Ok(result) => result,
+            Err(payload) => {
+                *this.panicked = true;
+                #[cfg(feature = "logs")]
+                crate::logs::task_panicked(this.labels, panic_message(&*payload));
+                std::panic::resume_unwind(payload);
+            }
+        };
+
+        let end = Instant::now();
+        let poll_duration = end.saturating_duration_since(start).as_secs_f64();
+        this.instruments
+            .poll_duration
+            .record(poll_duration, this.labels);
+        this.instruments.poll_time.add(poll_duration, this.labels);
+        *this.last_poll_end = Some(end);
+        if result.is_ready() {
+            *this.completed = true;
+            this.instruments
+                .duration
+                .record(this.spawned_at.elapsed().as_secs_f64(), this.labels);
+        }
+        result
+    }
+}
+
+/// Spawn a new task with the given name, recording it in the metrics.
+///
+/// See [`tokio::spawn`].
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// opentelemetry_instrumentation_tokio::task::spawn("flush", async {
+///     // Flush the buffers
+/// });
+/// # }
+/// ```
+pub fn spawn<F>(name: impl Into<Value>, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let handle = tokio::runtime::Handle::current();
+    let task = instrument_task(future, &handle, Some(name.into()));
+    handle.spawn(task)
+}
+
+static BLOCKING_IN_FLIGHT: LazyLock<UpDownCounter<i64>> = LazyLock::new(|| {
+    crate::meter()
+        .i64_up_down_counter("tokio.task.blocking_in_flight")
+        .with_description("The number of blocking closures currently executing")
+        .with_unit("{task}")
+        .build()
+});
+
+/// Decrements the in-flight blocking closures count when the closure returns
+/// or panics.
+struct BlockingGuard {
+    labels: crate::Attributes,
+}
+
+impl BlockingGuard {
+    fn new(labels: crate::Attributes) -> Self {
+        BLOCKING_IN_FLIGHT.add(1, &labels);
+        Self { labels }
+    }
+}
+
+impl Drop for BlockingGuard {
+    fn drop(&mut self) {
+        BLOCKING_IN_FLIGHT.add(-1, &self.labels);
+    }
+}
+
+/// Run a blocking closure with the given name on the blocking thread pool,
+/// recording it while it executes.
+///
+/// See [`tokio::task::spawn_blocking`]. The closures currently executing are
+/// recorded in `tokio.task.blocking_in_flight`, with their name
+/// (`tokio.task.name`) and the labels of the runtime. Unlike
+/// `tokio.blocking_queue_depth`, this doesn't include the closures waiting
+/// for a thread to run on.
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// let handle = opentelemetry_instrumentation_tokio::task::spawn_blocking("hash_password", || {
+///     // Hash the password
+/// });
+/// handle.await.unwrap();
+/// # }
+/// ```
+pub fn spawn_blocking<F, R>(name: impl Into<Value>, f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let handle = tokio::runtime::Handle::current();
+    let name = KeyValue::new(Key::from_static_str("tokio.task.name"), name.into());
+    let labels = crate::runtime::with_runtime_labels(&handle, |labels| {
+        let mut labels: crate::Attributes = labels.iter().cloned().collect();
+        labels.push(name);
+        labels
+    });
+
+    handle.spawn_blocking(move || {
+        let _guard = BlockingGuard::new(labels);
+        f()
+    })
+}
+
+#[cfg(feature = "rt-multi-thread")]
+static BLOCK_IN_PLACE_DURATION: LazyLock<Histogram<f64>> = LazyLock::new(|| {
+    crate::meter()
+        .f64_histogram("tokio.task.block_in_place.duration")
+        .with_description("The duration of the blocking closures run in place of a worker")
+        .with_unit("s")
+        .build()
+});
+
+/// Records the duration of a closure run in place when it returns or panics.
+#[cfg(feature = "rt-multi-thread")]
+struct BlockInPlaceGuard {
+    start: Instant,
+    labels: crate::Attributes,
+}
+
+#[cfg(feature = "rt-multi-thread")]
+impl Drop for BlockInPlaceGuard {
+    fn drop(&mut self) {
+        BLOCK_IN_PLACE_DURATION.record(self.start.elapsed().as_secs_f64(), &self.labels);
+    }
+}
+
+/// Run a blocking closure with the given name in place of the current worker,
+/// recording how long it blocks it.
+///
+/// See [`tokio::task::block_in_place`]. The worker running the closure hands
+/// its tasks over to another thread, and doesn't run any task until the
+/// closure returns, which otherwise only shows up in
+/// `tokio.worker.busy_duration`. The duration of each call is recorded in
+/// `tokio.task.block_in_place.duration`, with its name (`tokio.task.name`)
+/// and the labels of the runtime, so that the count of the histogram is the
+/// number of calls.
+///
+/// # Panics
+///
+/// Panics if called from a `current_thread` runtime.
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// opentelemetry_instrumentation_tokio::task::block_in_place("hash_password", || {
+///     // Hash the password
+/// });
+/// # }
+/// ```
+#[cfg(feature = "rt-multi-thread")]
+pub fn block_in_place<F, R>(name: impl Into<Value>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let name = KeyValue::new(Key::from_static_str("tokio.task.name"), name.into());
+    let labels = crate::runtime::with_current_runtime_labels(|labels| {
+        let mut labels: crate::Attributes = labels.iter().cloned().collect();
+        labels.push(name);
+        labels
+    });
+
+    tokio::task::block_in_place(move || {
+        let _guard = BlockInPlaceGuard {
+            start: Instant::now(),
+            labels,
+        };
+        f()
+    })
+}
+
+/// Wrap a future about to be spawned on the given runtime as a task with the
+/// given name.
+fn instrument_task<F>(
+    future: F,
+    handle: &tokio::runtime::Handle,
+    name: Option<Value>,
+) -> SampledTask<F> {
+    SampledTask::new(future, &TASK_INSTRUMENTS, || {
+        crate::runtime::with_runtime_labels(handle, |labels| {
+            let mut labels = labels.to_vec();
+            if let Some(name) = name {
+                labels.push(KeyValue::new(Key::from_static_str("tokio.task.name"), name));
+            }
+            Arc::from(labels)
+        })
+    })
+}
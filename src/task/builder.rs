@@ -0,0 +1,95 @@
+use std::io;
+
+use opentelemetry::Value;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+use super::instrument_task;
+
+/// A task builder recording the tasks it spawns in the metrics, under their
+/// name (`tokio.task.name`).
+///
+/// This mirrors [`tokio::task::Builder`]: with `tokio_unstable` and the
+/// `tokio-tracing` feature, the tasks are spawned through it, so that the name
+/// also shows up in tools like `tokio-console`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::task::Builder;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let handle = Builder::new().name("flush").spawn(async {
+///     // Flush the buffers
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Builder<'a> {
+    name: Option<&'a str>,
+}
+
+impl<'a> Builder<'a> {
+    /// Create a new task builder.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Assign a name to the task which will be spawned.
+    #[must_use]
+    pub fn name(&self, name: &'a str) -> Self {
+        Self { name: Some(name) }
+    }
+
+    /// Spawn a task with this builder's settings on the current runtime.
+    ///
+    /// See [`tokio::spawn`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the task failed to spawn, see
+    /// [`tokio::task::Builder::spawn`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a Tokio runtime.
+    pub fn spawn<F>(self, future: F) -> io::Result<JoinHandle<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.spawn_on(future, &Handle::current())
+    }
+
+    /// Spawn a task with this builder's settings on the given runtime.
+    ///
+    /// See [`Handle::spawn`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the task failed to spawn, see
+    /// [`tokio::task::Builder::spawn_on`].
+    pub fn spawn_on<F>(self, future: F, handle: &Handle) -> io::Result<JoinHandle<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let name = self.name.map(|name| Value::from(name.to_owned()));
+        let task = instrument_task(future, handle, name);
+
+        #[cfg(all(tokio_unstable, feature = "tokio-tracing"))]
+        {
+            let mut builder = tokio::task::Builder::new();
+            if let Some(name) = self.name {
+                builder = builder.name(name);
+            }
+            builder.spawn_on(task, handle)
+        }
+
+        #[cfg(not(all(tokio_unstable, feature = "tokio-tracing")))]
+        Ok(handle.spawn(task))
+    }
+}
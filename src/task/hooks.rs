@@ -0,0 +1,195 @@
+use std::cell::Cell;
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram};
+use opentelemetry::{Key, KeyValue};
+use tokio::runtime::{Builder, TaskMeta};
+
+/// Instruments recording all the tasks of a runtime through its hooks.
+struct HookInstruments {
+    spawned_tasks: Counter<u64>,
+    terminated_tasks: Counter<u64>,
+    polls: Counter<u64>,
+    poll_duration: Histogram<f64>,
+}
+
+static HOOK_INSTRUMENTS: LazyLock<HookInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    HookInstruments {
+        spawned_tasks: meter
+            .u64_counter("tokio.runtime.task.spawned_tasks")
+            .with_description("The number of tasks spawned on the runtime")
+            .with_unit("{task}")
+            .build(),
+        terminated_tasks: meter
+            .u64_counter("tokio.runtime.task.terminated_tasks")
+            .with_description("The number of tasks of the runtime which completed or were dropped")
+            .with_unit("{task}")
+            .build(),
+        polls: meter
+            .u64_counter("tokio.runtime.task.polls")
+            .with_description("The number of times the tasks of the runtime were polled")
+            .with_unit("{poll}")
+            .build(),
+        poll_duration: meter
+            .f64_histogram("tokio.runtime.task.poll_duration")
+            .with_description("The duration of the polls of the tasks of the runtime")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+thread_local! {
+    /// When the task being polled on this thread started its poll.
+    ///
+    /// Polls don't nest on a thread, except when a task drives another
+    /// runtime, in which case the outer poll isn't recorded.
+    static POLL_START: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Hooks recording the polls of all the tasks of a runtime, without wrapping
+/// them in the instrumented APIs of this module.
+///
+/// Once installed on a runtime [`Builder`], the tasks of the runtime are
+/// recorded with the labels of the runtime, once it is observed:
+///
+/// - `tokio.runtime.task.spawned_tasks` - Tasks spawned
+/// - `tokio.runtime.task.terminated_tasks` - Tasks which completed or were
+///   dropped
+/// - `tokio.runtime.task.polls` - Number of task polls
+/// - `tokio.runtime.task.poll_duration` - Duration of the task polls
+///
+/// This relies on the task hooks of Tokio, which are only available with
+/// `tokio_unstable`. Recording every poll has a cost, of a few clock reads
+/// and a lookup of the runtime labels. The tasks spawned from outside the
+/// runtime, e.g. through a [`Handle`](tokio::runtime::Handle), are counted
+/// without the runtime labels.
+///
+/// With [`Self::with_spawn_location`], the metrics are also labelled with the
+/// location the tasks were spawned at, to find the hot spawn sites without
+/// naming the tasks.
+///
+/// With [`Config::with_task_sampling`](crate::Config::with_task_sampling),
+/// only a fraction of the tasks are recorded, picked from their ID, with the
+/// ratio in the `tokio.task.sampling_ratio` label.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::Config;
+/// use opentelemetry_instrumentation_tokio::task::TaskHooks;
+///
+/// let mut builder = tokio::runtime::Builder::new_multi_thread();
+/// TaskHooks::new()
+///     .with_spawn_location(true)
+///     .install(&mut builder);
+/// let runtime = builder.enable_all().build()?;
+/// let _guard = Config::new().observe_runtime(runtime.handle())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TaskHooks {
+    spawn_location: bool,
+}
+
+impl TaskHooks {
+    /// Create the hooks with the default settings.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Label the metrics with the location the tasks were spawned at, in the
+    /// `code.filepath` and `code.lineno` attributes.
+    ///
+    /// This adds a series per spawn site, so it should only be enabled when
+    /// the number of spawn sites is reasonable.
+    #[must_use]
+    pub fn with_spawn_location(mut self, enabled: bool) -> Self {
+        self.spawn_location = enabled;
+        self
+    }
+
+    /// Install the hooks on the given runtime builder.
+    ///
+    /// Tokio only keeps one hook of each kind, so this replaces the task
+    /// spawn, task termination and poll hooks previously set on the builder.
+    pub fn install(self, builder: &mut Builder) -> &mut Builder {
+        let spawn_location = self.spawn_location;
+        builder
+            .on_task_spawn(move |meta| {
+                if !is_sampled(meta) {
+                    return;
+                }
+                with_task_labels(meta, spawn_location, |labels| {
+                    HOOK_INSTRUMENTS.spawned_tasks.add(1, labels);
+                });
+            })
+            .on_task_terminate(move |meta| {
+                if !is_sampled(meta) {
+                    return;
+                }
+                with_task_labels(meta, spawn_location, |labels| {
+                    HOOK_INSTRUMENTS.terminated_tasks.add(1, labels);
+                });
+            })
+            .on_before_task_poll(|meta| {
+                if is_sampled(meta) {
+                    POLL_START.set(Some(Instant::now()));
+                }
+            })
+            .on_after_task_poll(move |meta| {
+                let Some(start) = POLL_START.take() else {
+                    return;
+                };
+                let poll_duration = start.elapsed().as_secs_f64();
+
+                with_task_labels(meta, spawn_location, |labels| {
+                    HOOK_INSTRUMENTS.polls.add(1, labels);
+                    HOOK_INSTRUMENTS.poll_duration.record(poll_duration, labels);
+                });
+            })
+    }
+}
+
+/// Whether the given task is sampled, see [`super::TaskSampler`].
+fn is_sampled(meta: &TaskMeta<'_>) -> bool {
+    super::TASK_SAMPLER.get().is_none_or(|sampler| {
+        let hash = BuildHasherDefault::<DefaultHasher>::default().hash_one(meta.id());
+        sampler.samples(hash)
+    })
+}
+
+/// Run the given closure with the labels of the current runtime, the sampling
+/// ratio, and the location the task was spawned at if enabled.
+fn with_task_labels<R>(
+    meta: &TaskMeta<'_>,
+    spawn_location: bool,
+    f: impl FnOnce(&[KeyValue]) -> R,
+) -> R {
+    let sampler = super::TASK_SAMPLER.get();
+    crate::runtime::with_current_runtime_labels(|labels| {
+        if !spawn_location && sampler.is_none() {
+            return f(labels);
+        }
+
+        let mut attributes: crate::Attributes = labels.iter().cloned().collect();
+        if let Some(sampler) = sampler {
+            attributes.push(sampler.label.clone());
+        }
+        if spawn_location {
+            let location = meta.spawned_at();
+            attributes.extend([
+                KeyValue::new(Key::from_static_str("code.filepath"), location.file()),
+                KeyValue::new(
+                    Key::from_static_str("code.lineno"),
+                    i64::from(location.line()),
+                ),
+            ]);
+        }
+        f(&attributes)
+    })
+}
@@ -0,0 +1,119 @@
+use std::pin::Pin;
+use std::sync::{Arc, LazyLock};
+use std::task::{Context, Poll};
+
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::task::{JoinHandle, LocalSet};
+
+use super::{SampledTask, TaskInstruments};
+
+static LOCAL_SET_INSTRUMENTS: LazyLock<TaskInstruments> =
+    LazyLock::new(|| TaskInstruments::new(&crate::meter(), "tokio.localset"));
+
+/// A [`LocalSet`] recording metrics about the tasks spawned on it.
+///
+/// Tasks spawned with [`Self::spawn_local`] are recorded with the name of the
+/// set (`tokio.localset.name`):
+///
+/// - `tokio.localset.alive_tasks` - Tasks currently alive
+/// - `tokio.localset.spawned_tasks` - Tasks spawned
+/// - `tokio.localset.poll_duration` - Duration of the task polls
+/// - `tokio.localset.polls` - Number of times each task was polled
+/// - `tokio.localset.duration` - Time between the task being spawned and it
+///   completing
+/// - `tokio.localset.first_poll_delay` - Time between the task being spawned
+///   and its first poll
+/// - `tokio.localset.poll_time`, `tokio.localset.scheduled_time` and
+///   `tokio.localset.idle_time` - Time spent polling the tasks, waiting to be
+///   polled, and waiting to be woken
+/// - `tokio.localset.completed_tasks` - Tasks which ran to completion
+/// - `tokio.localset.cancelled_tasks` - Tasks dropped before completing
+/// - `tokio.localset.panics` - Tasks which panicked
+///
+/// Tasks spawned directly with [`tokio::task::spawn_local`] are not recorded.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::task::InstrumentedLocalSet;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let local = InstrumentedLocalSet::new("ui");
+///
+/// local
+///     .run_until(async {
+///         local.spawn_local(async {
+///             // Work with `!Send` values
+///         });
+///     })
+///     .await;
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedLocalSet {
+    inner: LocalSet,
+    labels: Arc<[KeyValue]>,
+}
+
+impl InstrumentedLocalSet {
+    /// Create a new instrumented [`LocalSet`] with the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>) -> Self {
+        Self {
+            inner: LocalSet::new(),
+            labels: Arc::new([KeyValue::new(
+                Key::from_static_str("tokio.localset.name"),
+                name.into(),
+            )]),
+        }
+    }
+
+    /// Spawn a `!Send` future on this set, recording it in the metrics.
+    ///
+    /// See [`LocalSet::spawn_local`].
+    pub fn spawn_local<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + 'static,
+        F::Output: 'static,
+    {
+        let task = SampledTask::new(future, &LOCAL_SET_INSTRUMENTS, || self.labels.clone());
+        self.inner.spawn_local(task)
+    }
+
+    /// Run a future to completion on the given runtime, driving the tasks of
+    /// this set.
+    ///
+    /// See [`LocalSet::block_on`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called from within an asynchronous execution context.
+    pub fn block_on<F: Future>(&self, rt: &tokio::runtime::Runtime, future: F) -> F::Output {
+        self.inner.block_on(rt, future)
+    }
+
+    /// Run a future to completion, driving the tasks of this set.
+    ///
+    /// See [`LocalSet::run_until`].
+    pub async fn run_until<F: Future>(&self, future: F) -> F::Output {
+        self.inner.run_until(future).await
+    }
+
+    /// Get a reference to the underlying [`LocalSet`].
+    ///
+    /// Tasks spawned directly on the underlying set are not recorded.
+    #[must_use]
+    pub fn get_ref(&self) -> &LocalSet {
+        &self.inner
+    }
+}
+
+/// Awaiting the set runs all its tasks to completion.
+impl Future for InstrumentedLocalSet {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.inner).poll(cx)
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::{Arc, Mutex};
+use std::task::{Wake, Waker};
+use std::time::Instant;
+
+/// A waker recording when the task it belongs to was woken, and forwarding
+/// the wake-ups to the waker of the runtime.
+pub(super) struct TaskWaker {
+    woken_at: Mutex<Option<Instant>>,
+    inner: Mutex<Option<Waker>>,
+}
+
+impl TaskWaker {
+    pub(super) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            woken_at: Mutex::new(None),
+            inner: Mutex::new(None),
+        })
+    }
+
+    /// Take the time at which the task was first woken since the last call.
+    pub(super) fn take_woken_at(&self) -> Option<Instant> {
+        crate::recover_lock(self.woken_at.lock(), "task wake time").take()
+    }
+
+    /// Set the waker of the runtime to forward the wake-ups to.
+    pub(super) fn register(&self, waker: &Waker) {
+        let mut inner = crate::recover_lock(self.inner.lock(), "task waker");
+        if !inner.as_ref().is_some_and(|inner| inner.will_wake(waker)) {
+            *inner = Some(waker.clone());
+        }
+    }
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        crate::recover_lock(self.woken_at.lock(), "task wake time")
+            .get_or_insert_with(Instant::now);
+
+        // Don't hold the lock while waking, in case the runtime polls the task
+        // right away
+        let inner = crate::recover_lock(self.inner.lock(), "task waker").clone();
+        if let Some(inner) = inner {
+            inner.wake();
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Helpers to check the metrics recorded by this crate in tests.
+//!
+//! [`TestMetrics::install`] sets a meter provider exporting to memory as the
+//! global meter provider, and [`TestMetrics::collect`] runs a collection,
+//! returning a [`Collection`] to check the values of the metrics with
+//! [`Collection::assert_metric`].
+//!
+//! This lets applications write integration tests checking that their
+//! runtimes are observed with the expected labels.
+//!
+//! # Examples
+//!
+//! ```
+//! use opentelemetry::KeyValue;
+//! use opentelemetry_instrumentation_tokio::Config;
+//! use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let metrics = TestMetrics::install();
+//! Config::new().named("api").observe_current_runtime().unwrap();
+//!
+//! metrics.collect().assert_metric(
+//!     "tokio.workers",
+//!     &[KeyValue::new("tokio.runtime.name", "api")],
+//!     1.0,
+//! );
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::metrics::data::{
+    AggregatedMetrics, Metric, MetricData, ResourceMetrics, ScopeMetrics,
+};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+
+static TEST_METRICS: OnceLock<TestMetrics> = OnceLock::new();
+
+/// A meter provider exporting to memory, installed as the global meter
+/// provider by [`Self::install`].
+#[derive(Debug)]
+pub struct TestMetrics {
+    provider: SdkMeterProvider,
+    exporter: InMemoryMetricExporter,
+
+    // Collections read the exporter after flushing the provider to it, so
+    // concurrent ones would see each other's exports
+    collecting: Mutex<()>,
+}
+
+impl TestMetrics {
+    /// Install a meter provider exporting to memory as the global meter
+    /// provider, the first time this is called.
+    ///
+    /// The instruments of this crate are bound to the global meter provider
+    /// set when the first runtime is observed, so this should be called
+    /// before observing any runtime, and is shared by all the tests of the
+    /// same binary. Since the metrics are cumulative, tests sharing it should
+    /// tell their runtimes apart with labels, e.g. with
+    /// [`crate::Config::named`].
+    #[must_use]
+    pub fn install() -> &'static Self {
+        TEST_METRICS.get_or_init(|| {
+            let exporter = InMemoryMetricExporter::default();
+            let provider = SdkMeterProvider::builder()
+                .with_reader(PeriodicReader::builder(exporter.clone()).build())
+                .build();
+            opentelemetry::global::set_meter_provider(provider.clone());
+
+            Self {
+                provider,
+                exporter,
+                collecting: Mutex::new(()),
+            }
+        })
+    }
+
+    /// The meter provider exporting to memory, e.g. to record other metrics.
+    #[must_use]
+    pub fn provider(&self) -> &SdkMeterProvider {
+        &self.provider
+    }
+
+    /// Run a collection, returning the metrics collected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the collection failed.
+    #[must_use]
+    pub fn collect(&self) -> Collection {
+        let _collecting = crate::recover_lock(self.collecting.lock(), "test metrics collection");
+        self.exporter.reset();
+        self.provider
+            .force_flush()
+            .expect("failed to collect the metrics");
+        let resource_metrics = self
+            .exporter
+            .get_finished_metrics()
+            .expect("failed to read the collected metrics");
+
+        Collection(resource_metrics)
+    }
+}
+
+/// The metrics collected by [`TestMetrics::collect`].
+pub struct Collection(Vec<ResourceMetrics>);
+
+impl fmt::Debug for Collection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.metric_names()).finish()
+    }
+}
+
+impl Collection {
+    /// The names of the metrics collected.
+    pub fn metric_names(&self) -> impl Iterator<Item = &str> {
+        self.0
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .map(Metric::name)
+    }
+
+    /// The value of a metric, summed over the series carrying all the given
+    /// labels, or `None` if there are none.
+    ///
+    /// The other labels of the series are ignored, so that e.g. the value of a
+    /// per-worker metric can be checked for a whole runtime. Histograms count
+    /// the values they recorded.
+    #[must_use]
+    pub fn value(&self, name: &str, labels: &[KeyValue]) -> Option<f64> {
+        let mut matching = self
+            .data_points(name)
+            .into_iter()
+            .filter(|(attributes, _)| labels.iter().all(|label| attributes.contains(label)))
+            .map(|(_, value)| value)
+            .peekable();
+        matching.peek()?;
+        Some(matching.sum())
+    }
+
+    /// Assert that a metric has the given value, summed over the series
+    /// carrying all the given labels, see [`Self::value`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no series of the metric carry the labels, or if their value
+    /// is different, listing the series collected for this metric.
+    #[track_caller]
+    pub fn assert_metric(&self, name: &str, labels: &[KeyValue], value: f64) {
+        let actual = self.value(name, labels);
+        assert!(
+            actual == Some(value),
+            "expected {name} to be {value} with the labels {labels:?}, found {actual:?} in {:?}",
+            self.data_points(name),
+        );
+    }
+
+    /// The attributes and values of the data points of a metric.
+    fn data_points(&self, name: &str) -> Vec<(Vec<KeyValue>, f64)> {
+        self.0
+            .iter()
+            .flat_map(ResourceMetrics::scope_metrics)
+            .flat_map(ScopeMetrics::metrics)
+            .filter(|metric| metric.name() == name)
+            .flat_map(|metric| match metric.data() {
+                AggregatedMetrics::F64(data) => data_points(data, |value| value),
+                #[expect(clippy::cast_precision_loss)]
+                AggregatedMetrics::U64(data) => data_points(data, |value| value as f64),
+                #[expect(clippy::cast_precision_loss)]
+                AggregatedMetrics::I64(data) => data_points(data, |value| value as f64),
+            })
+            .collect()
+    }
+}
+
+/// The attributes and values of some metric data, converted to `f64`.
+#[expect(clippy::cast_precision_loss)]
+fn data_points<T: Copy>(
+    data: &MetricData<T>,
+    to_f64: impl Fn(T) -> f64,
+) -> Vec<(Vec<KeyValue>, f64)> {
+    match data {
+        MetricData::Gauge(gauge) => gauge
+            .data_points()
+            .map(|point| (point.attributes().cloned().collect(), to_f64(point.value())))
+            .collect(),
+        MetricData::Sum(sum) => sum
+            .data_points()
+            .map(|point| (point.attributes().cloned().collect(), to_f64(point.value())))
+            .collect(),
+        MetricData::Histogram(histogram) => histogram
+            .data_points()
+            .map(|point| (point.attributes().cloned().collect(), point.count() as f64))
+            .collect(),
+        MetricData::ExponentialHistogram(histogram) => histogram
+            .data_points()
+            .map(|point| (point.attributes().cloned().collect(), point.count() as f64))
+            .collect(),
+    }
+}
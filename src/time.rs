@@ -0,0 +1,371 @@
+//! Instrumented timer utilities.
+//!
+//! These mirror the functions and types in [`tokio::time`], and record how
+//! well the timer driver and the scheduler keep up with them.
+//!
+//! The sleeps and timeouts currently pending are also counted in
+//! `tokio.timer.pending`, labelled with the labels of the runtime they were
+//! first polled on, as a rough measure of the load on the timer driver.
+
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::time::error::Elapsed;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+/// Instruments shared by all the timer utilities.
+struct TimeInstruments {
+    sleep_overshoot: Histogram<f64>,
+    interval_ticks: Counter<u64>,
+    interval_missed_ticks: Counter<u64>,
+    interval_tick_drift: Histogram<f64>,
+    timeouts: Counter<u64>,
+    timeout_duration: Histogram<f64>,
+    pending_timers: UpDownCounter<i64>,
+}
+
+static TIME_INSTRUMENTS: LazyLock<TimeInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    TimeInstruments {
+        sleep_overshoot: meter
+            .f64_histogram("tokio.sleep.overshoot")
+            .with_description(
+                "The time between the deadline of a sleep and the task actually resuming",
+            )
+            .with_unit("s")
+            .build(),
+        interval_ticks: meter
+            .u64_counter("tokio.interval.ticks")
+            .with_description("The number of ticks delivered by the interval")
+            .with_unit("{tick}")
+            .build(),
+        interval_missed_ticks: meter
+            .u64_counter("tokio.interval.missed_ticks")
+            .with_description(
+                "The number of ticks which were not delivered before the next one was due",
+            )
+            .with_unit("{tick}")
+            .build(),
+        interval_tick_drift: meter
+            .f64_histogram("tokio.interval.tick_drift")
+            .with_description("The time between a tick being scheduled and being delivered")
+            .with_unit("s")
+            .build(),
+        timeouts: meter
+            .u64_counter("tokio.timeout.completions")
+            .with_description(
+                "The number of futures run with a timeout which completed or timed out",
+            )
+            .with_unit("{future}")
+            .build(),
+        timeout_duration: meter
+            .f64_histogram("tokio.timeout.duration")
+            .with_description("The time spent running futures with a timeout")
+            .with_unit("s")
+            .build(),
+        pending_timers: meter
+            .i64_up_down_counter("tokio.timer.pending")
+            .with_description("The number of instrumented sleeps and timeouts currently pending")
+            .with_unit("{timer}")
+            .build(),
+    }
+});
+
+/// Counts a sleep or timeout as pending until it completes or is dropped.
+///
+/// The labels of the runtime are kept, so that the count goes back down under
+/// the same labels even if the future moves to another runtime.
+struct PendingTimer {
+    labels: Vec<KeyValue>,
+}
+
+impl PendingTimer {
+    fn new() -> Self {
+        let labels = crate::runtime::with_current_runtime_labels(<[KeyValue]>::to_vec);
+        TIME_INSTRUMENTS.pending_timers.add(1, &labels);
+        Self { labels }
+    }
+}
+
+impl Drop for PendingTimer {
+    fn drop(&mut self) {
+        TIME_INSTRUMENTS.pending_timers.add(-1, &self.labels);
+    }
+}
+
+/// Wait until `duration` has elapsed, recording how late the task resumed.
+///
+/// This behaves like [`tokio::time::sleep`], and records the difference
+/// between the requested and actual wake time in the `tokio.sleep.overshoot`
+/// histogram. This is a direct measure of the lag introduced by the timer
+/// driver and the scheduler.
+///
+/// The histogram is labelled with the labels of the runtime the sleep ran on,
+/// when it can be identified (see [`crate::Config::observe_runtime`]).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// opentelemetry_instrumentation_tokio::time::sleep(Duration::from_millis(100)).await;
+/// # }
+/// ```
+pub async fn sleep(duration: Duration) {
+    sleep_until(Instant::now() + duration).await;
+}
+
+/// Wait until `deadline` is reached, recording how late the task resumed.
+///
+/// This behaves like [`tokio::time::sleep_until`], and records the overshoot
+/// like [`sleep`] does.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use tokio::time::Instant;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let deadline = Instant::now() + Duration::from_millis(100);
+/// opentelemetry_instrumentation_tokio::time::sleep_until(deadline).await;
+/// # }
+/// ```
+pub async fn sleep_until(deadline: Instant) {
+    let pending = PendingTimer::new();
+    tokio::time::sleep_until(deadline).await;
+    drop(pending);
+
+    let overshoot = Instant::now().saturating_duration_since(deadline);
+    crate::runtime::with_current_runtime_labels(|labels| {
+        TIME_INSTRUMENTS
+            .sleep_overshoot
+            .record(overshoot.as_secs_f64(), labels);
+    });
+}
+
+/// Create an [`InstrumentedInterval`] yielding every `period`, with the first
+/// tick completing immediately.
+///
+/// See [`tokio::time::interval`].
+///
+/// # Panics
+///
+/// Panics if `period` is zero.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut interval =
+///     opentelemetry_instrumentation_tokio::time::interval("cleanup", Duration::from_secs(10));
+///
+/// loop {
+///     interval.tick().await;
+///     // Run the periodic job
+/// }
+/// # }
+/// ```
+#[must_use]
+pub fn interval(name: impl Into<Value>, period: Duration) -> InstrumentedInterval {
+    InstrumentedInterval::new(name, tokio::time::interval(period))
+}
+
+/// Create an [`InstrumentedInterval`] yielding every `period`, with the first
+/// tick completing at `start`.
+///
+/// See [`tokio::time::interval_at`].
+///
+/// # Panics
+///
+/// Panics if `period` is zero.
+#[must_use]
+pub fn interval_at(
+    name: impl Into<Value>,
+    start: Instant,
+    period: Duration,
+) -> InstrumentedInterval {
+    InstrumentedInterval::new(name, tokio::time::interval_at(start, period))
+}
+
+/// An [`Interval`] recording whether its ticks are delivered on time.
+///
+/// Metrics are labelled with the interval name (`tokio.interval.name`):
+///
+/// - `tokio.interval.ticks` - Ticks delivered
+/// - `tokio.interval.missed_ticks` - Ticks which were not delivered before the
+///   next one was due. With [`MissedTickBehavior::Burst`], those are the late
+///   ticks delivered to catch up; with the other behaviors, those are the ticks
+///   which were skipped.
+/// - `tokio.interval.tick_drift` - Time between a tick being scheduled and
+///   being delivered
+///
+/// A steady stream of missed ticks is a sign of the periodic job or the
+/// runtime being starved.
+#[derive(Debug)]
+pub struct InstrumentedInterval {
+    inner: Interval,
+    labels: Vec<KeyValue>,
+}
+
+impl InstrumentedInterval {
+    /// Instrument an existing [`Interval`] under the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>, interval: Interval) -> Self {
+        Self {
+            inner: interval,
+            labels: vec![KeyValue::new(
+                Key::from_static_str("tokio.interval.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Complete when the next instant in the interval has been reached.
+    ///
+    /// See [`Interval::tick`].
+    pub async fn tick(&mut self) -> Instant {
+        std::future::poll_fn(|cx| self.poll_tick(cx)).await
+    }
+
+    /// Poll for the next instant in the interval to be reached.
+    ///
+    /// See [`Interval::poll_tick`].
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Instant> {
+        let scheduled = std::task::ready!(self.inner.poll_tick(cx));
+        self.record_tick(scheduled);
+        Poll::Ready(scheduled)
+    }
+
+    /// Record a tick scheduled at the given instant being delivered now.
+    fn record_tick(&self, scheduled: Instant) {
+        let drift = Instant::now().saturating_duration_since(scheduled);
+        let period = self.inner.period();
+
+        let missed = match self.inner.missed_tick_behavior() {
+            // Missed ticks are all delivered late, count each of them once
+            MissedTickBehavior::Burst => u64::from(drift >= period),
+            // The ticks due while this one was late are not delivered at all
+            MissedTickBehavior::Delay | MissedTickBehavior::Skip => {
+                let periods = drift.as_nanos() / period.as_nanos();
+                periods
+                    .try_into()
+                    .unwrap_or_else(|_| crate::overflowed("tokio.interval.missed_ticks", u64::MAX))
+            }
+        };
+
+        let instruments = &*TIME_INSTRUMENTS;
+        instruments.interval_ticks.add(1, &self.labels);
+        if missed > 0 {
+            instruments.interval_missed_ticks.add(missed, &self.labels);
+        }
+        instruments
+            .interval_tick_drift
+            .record(drift.as_secs_f64(), &self.labels);
+    }
+
+    /// Reset the interval to complete one period after the current time.
+    ///
+    /// See [`Interval::reset`].
+    pub fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    /// Returns the [`MissedTickBehavior`] strategy currently being used.
+    #[must_use]
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.inner.missed_tick_behavior()
+    }
+
+    /// Set the [`MissedTickBehavior`] strategy that should be used.
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.inner.set_missed_tick_behavior(behavior);
+    }
+
+    /// Returns the period of the interval.
+    #[must_use]
+    pub fn period(&self) -> Duration {
+        self.inner.period()
+    }
+
+    /// Consume this wrapper, returning the underlying [`Interval`].
+    #[must_use]
+    pub fn into_inner(self) -> Interval {
+        self.inner
+    }
+}
+
+/// Require a future to complete before `duration` has elapsed, recording
+/// whether it did.
+///
+/// This behaves like [`tokio::time::timeout`], and records:
+///
+/// - `tokio.timeout.completions` - Futures which completed or timed out, with a
+///   `tokio.timeout.outcome` attribute set to either `completed` or `timed_out`
+/// - `tokio.timeout.duration` - Time spent running the future, with the same
+///   attributes
+///
+/// Both are labelled with the given name (`tokio.timeout.name`). Nothing is
+/// recorded if the returned future is dropped before completing.
+///
+/// # Errors
+///
+/// Returns [`Elapsed`] if the future did not complete in time.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use opentelemetry_instrumentation_tokio::time::timeout_instrumented;
+///
+/// # async fn fetch_user() {}
+/// # #[tokio::main]
+/// # async fn main() {
+/// match timeout_instrumented("fetch_user", Duration::from_millis(200), fetch_user()).await {
+///     Ok(user) => { /* ... */ }
+///     Err(_elapsed) => { /* ... */ }
+/// }
+/// # }
+/// ```
+pub async fn timeout_instrumented<F: IntoFuture>(
+    name: impl Into<Value>,
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    let name = KeyValue::new(Key::from_static_str("tokio.timeout.name"), name.into());
+    let pending = PendingTimer::new();
+    let start = Instant::now();
+    let result = tokio::time::timeout(duration, future).await;
+    let elapsed = start.elapsed();
+    drop(pending);
+
+    let outcome = if result.is_ok() {
+        "completed"
+    } else {
+        "timed_out"
+    };
+    let labels = [
+        name,
+        KeyValue::new(Key::from_static_str("tokio.timeout.outcome"), outcome),
+    ];
+
+    let instruments = &*TIME_INSTRUMENTS;
+    instruments.timeouts.add(1, &labels);
+    instruments
+        .timeout_duration
+        .record(elapsed.as_secs_f64(), &labels);
+
+    result
+}
@@ -0,0 +1,167 @@
+//! A [`tower`](https://docs.rs/tower) layer recording requests with the labels
+//! of the runtime serving them.
+//!
+//! Services wrapped with [`RuntimeLabelsLayer`] record:
+//!
+//! - `tokio.service.request_duration` - Time spent serving requests, labelled
+//!   with the service name (`tokio.service.name`), the labels of the runtime
+//!   the request was received on, and a `tokio.service.outcome` attribute set
+//!   to either `ok` or `error`
+//!
+//! This lets the request latency be split by the runtime which served it, when
+//! an application runs several of them. The runtime labels can also be added
+//! to the active span, see [`RuntimeLabelsLayer::with_span_attributes`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::tower::RuntimeLabelsLayer;
+//! use tower_layer::Layer;
+//! # use tower_service::Service;
+//! # fn wrap<S: Service<()>>(service: S) {
+//! let service = RuntimeLabelsLayer::new("api").layer(service);
+//! # }
+//! ```
+
+use std::pin::Pin;
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use opentelemetry::metrics::Histogram;
+use opentelemetry::trace::get_active_span;
+use opentelemetry::{Key, KeyValue, Value};
+use tower_layer::Layer;
+use tower_service::Service;
+
+static REQUEST_DURATION: LazyLock<Histogram<f64>> = LazyLock::new(|| {
+    crate::meter()
+        .f64_histogram("tokio.service.request_duration")
+        .with_description("The time spent serving requests")
+        .with_unit("s")
+        .build()
+});
+
+/// A [`Layer`] recording the requests of the services it wraps with the
+/// labels of the runtime serving them.
+///
+/// See the [module documentation](self) for the recorded metrics.
+#[derive(Debug, Clone)]
+pub struct RuntimeLabelsLayer {
+    name: KeyValue,
+    span_attributes: bool,
+}
+
+impl RuntimeLabelsLayer {
+    /// Create a new layer, labelling the requests with the given service
+    /// name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>) -> Self {
+        Self {
+            name: KeyValue::new(Key::from_static_str("tokio.service.name"), name.into()),
+            span_attributes: false,
+        }
+    }
+
+    /// Also add the runtime labels as attributes of the active span when a
+    /// request is received.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use opentelemetry_instrumentation_tokio::tower::RuntimeLabelsLayer;
+    ///
+    /// let layer = RuntimeLabelsLayer::new("api").with_span_attributes();
+    /// ```
+    #[must_use]
+    pub fn with_span_attributes(mut self) -> Self {
+        self.span_attributes = true;
+        self
+    }
+}
+
+impl<S> Layer<S> for RuntimeLabelsLayer {
+    type Service = RuntimeLabelsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RuntimeLabelsService {
+            inner,
+            name: self.name.clone(),
+            span_attributes: self.span_attributes,
+        }
+    }
+}
+
+/// A [`Service`] recording its requests with the labels of the runtime
+/// serving them.
+///
+/// See [`RuntimeLabelsLayer`].
+#[derive(Debug, Clone)]
+pub struct RuntimeLabelsService<S> {
+    inner: S,
+    name: KeyValue,
+    span_attributes: bool,
+}
+
+impl<S, Request> Service<Request> for RuntimeLabelsService<S>
+where
+    S: Service<Request>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let labels = crate::runtime::with_current_runtime_labels(|labels| {
+            if self.span_attributes && !labels.is_empty() {
+                get_active_span(|span| span.set_attributes(labels.iter().cloned()));
+            }
+
+            let mut labels: crate::Attributes = labels.iter().cloned().collect();
+            labels.push(self.name.clone());
+            labels
+        });
+
+        ResponseFuture {
+            inner: self.inner.call(request),
+            start: Instant::now(),
+            labels,
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    /// The response future of [`RuntimeLabelsService`].
+    #[derive(Debug)]
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        start: Instant,
+        labels: crate::Attributes,
+    }
+}
+
+impl<F, Response, Error> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response, Error>>,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let result = std::task::ready!(this.inner.poll(cx));
+
+        let outcome = if result.is_ok() { "ok" } else { "error" };
+        this.labels.push(KeyValue::new(
+            Key::from_static_str("tokio.service.outcome"),
+            outcome,
+        ));
+        REQUEST_DURATION.record(this.start.elapsed().as_secs_f64(), this.labels);
+
+        Poll::Ready(result)
+    }
+}
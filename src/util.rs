@@ -0,0 +1,15 @@
+//! Instrumented [`tokio_util`] utilities.
+//!
+//! These mirror the utilities in [`tokio_util`], and record metrics about
+//! their usage under the same meter as the runtime metrics. Each utility is
+//! given a name when created, which is attached to its metrics.
+
+mod cancellation_token;
+mod codec;
+#[cfg(feature = "time")]
+mod delay_queue;
+
+pub use self::cancellation_token::InstrumentedCancellationToken;
+pub use self::codec::{InstrumentedCodec, framed_read, framed_write};
+#[cfg(feature = "time")]
+pub use self::delay_queue::InstrumentedDelayQueue;
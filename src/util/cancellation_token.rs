@@ -0,0 +1,217 @@
+use std::sync::{Arc, LazyLock, OnceLock};
+use std::time::Instant;
+
+use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio_util::sync::CancellationToken;
+
+/// Instruments shared by all [`InstrumentedCancellationToken`]s.
+struct CancellationTokenInstruments {
+    cancellations: Counter<u64>,
+    tokens: UpDownCounter<i64>,
+    observation_delay: Histogram<f64>,
+}
+
+static CANCELLATION_TOKEN_INSTRUMENTS: LazyLock<CancellationTokenInstruments> =
+    LazyLock::new(|| {
+        let meter = crate::meter();
+
+        CancellationTokenInstruments {
+            cancellations: meter
+                .u64_counter("tokio.cancellation_token.cancellations")
+                .with_description("The number of cancellations issued")
+                .with_unit("{cancellation}")
+                .build(),
+            tokens: meter
+                .i64_up_down_counter("tokio.cancellation_token.tokens")
+                .with_description("The number of tokens currently alive")
+                .with_unit("{token}")
+                .build(),
+            observation_delay: meter
+                .f64_histogram("tokio.cancellation_token.observation_delay")
+                .with_description(
+                    "The time between a cancellation being issued and a token observing it",
+                )
+                .with_unit("s")
+                .build(),
+        }
+    });
+
+/// A node in a tree of tokens, remembering when it was cancelled.
+#[derive(Debug)]
+struct Node {
+    cancelled_at: OnceLock<Instant>,
+    parent: Option<Arc<Node>>,
+}
+
+impl Node {
+    /// Get the time at which this node, or one of its ancestors, was first
+    /// cancelled.
+    fn cancelled_at(&self) -> Option<Instant> {
+        let mut cancelled_at = self.cancelled_at.get().copied();
+        let mut parent = self.parent.as_deref();
+        while let Some(node) = parent {
+            if let Some(at) = node.cancelled_at.get().copied() {
+                cancelled_at = Some(cancelled_at.map_or(at, |current| current.min(at)));
+            }
+            parent = node.parent.as_deref();
+        }
+        cancelled_at
+    }
+}
+
+/// A [`CancellationToken`] recording cancellations and how long they take to
+/// be observed.
+///
+/// Metrics are labelled with the name given on creation
+/// (`tokio.cancellation_token.name`), which child tokens inherit:
+///
+/// - `tokio.cancellation_token.cancellations` - Cancellations issued with
+///   [`cancel`](Self::cancel)
+/// - `tokio.cancellation_token.tokens` - Tokens currently alive, counting each
+///   clone and child token
+/// - `tokio.cancellation_token.observation_delay` - Time between a cancellation
+///   being issued, on the token or one of its parents, and the token observing
+///   it through [`cancelled`](Self::cancelled) or
+///   [`run_until_cancelled`](Self::run_until_cancelled)
+///
+/// A long observation delay during a graceful shutdown points to tasks which
+/// don't check for the cancellation often enough.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::util::InstrumentedCancellationToken;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let token = InstrumentedCancellationToken::new("shutdown");
+///
+/// let child = token.child_token();
+/// tokio::spawn(async move {
+///     child.cancelled().await;
+///     // Stop the worker
+/// });
+///
+/// token.cancel();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedCancellationToken {
+    inner: CancellationToken,
+    node: Arc<Node>,
+    labels: Arc<[KeyValue]>,
+}
+
+impl InstrumentedCancellationToken {
+    /// Create a new instrumented [`CancellationToken`] with the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>) -> Self {
+        let labels: Arc<[KeyValue]> = Arc::new([KeyValue::new(
+            Key::from_static_str("tokio.cancellation_token.name"),
+            name.into(),
+        )]);
+        let node = Arc::new(Node {
+            cancelled_at: OnceLock::new(),
+            parent: None,
+        });
+        Self::from_parts(CancellationToken::new(), node, labels)
+    }
+
+    /// Wrap a token sharing the given cancellation node, recording it as alive.
+    fn from_parts(inner: CancellationToken, node: Arc<Node>, labels: Arc<[KeyValue]>) -> Self {
+        CANCELLATION_TOKEN_INSTRUMENTS.tokens.add(1, &labels);
+        Self {
+            inner,
+            node,
+            labels,
+        }
+    }
+
+    /// Create a child token, which gets cancelled with this one, under the
+    /// same name.
+    ///
+    /// See [`CancellationToken::child_token`].
+    #[must_use]
+    pub fn child_token(&self) -> Self {
+        let node = Arc::new(Node {
+            cancelled_at: OnceLock::new(),
+            parent: Some(self.node.clone()),
+        });
+        Self::from_parts(self.inner.child_token(), node, self.labels.clone())
+    }
+
+    /// Cancel the token and all its child tokens, recording the cancellation.
+    ///
+    /// See [`CancellationToken::cancel`].
+    pub fn cancel(&self) {
+        if !self.inner.is_cancelled() && self.node.cancelled_at.set(Instant::now()).is_ok() {
+            CANCELLATION_TOKEN_INSTRUMENTS
+                .cancellations
+                .add(1, &self.labels);
+        }
+        self.inner.cancel();
+    }
+
+    /// Returns `true` if the token was cancelled.
+    ///
+    /// See [`CancellationToken::is_cancelled`].
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.is_cancelled()
+    }
+
+    /// Wait for the token to be cancelled, recording how long after the
+    /// cancellation it was observed.
+    ///
+    /// See [`CancellationToken::cancelled`].
+    pub async fn cancelled(&self) {
+        self.inner.cancelled().await;
+        self.record_observed();
+    }
+
+    /// Run a future until it completes or the token is cancelled, returning
+    /// `None` in the latter case.
+    ///
+    /// See [`CancellationToken::run_until_cancelled`].
+    pub async fn run_until_cancelled<F: Future>(&self, future: F) -> Option<F::Output> {
+        let result = self.inner.run_until_cancelled(future).await;
+        if result.is_none() {
+            self.record_observed();
+        }
+        result
+    }
+
+    /// Record the time since the cancellation of this token or one of its
+    /// parents.
+    fn record_observed(&self) {
+        // Tokens cancelled through the inner token are not timed
+        let Some(cancelled_at) = self.node.cancelled_at() else {
+            return;
+        };
+
+        CANCELLATION_TOKEN_INSTRUMENTS
+            .observation_delay
+            .record(cancelled_at.elapsed().as_secs_f64(), &self.labels);
+    }
+
+    /// Get a clone of the underlying [`CancellationToken`].
+    ///
+    /// Cancellations issued through it are not recorded.
+    #[must_use]
+    pub fn into_inner(self) -> CancellationToken {
+        self.inner.clone()
+    }
+}
+
+impl Clone for InstrumentedCancellationToken {
+    fn clone(&self) -> Self {
+        Self::from_parts(self.inner.clone(), self.node.clone(), self.labels.clone())
+    }
+}
+
+impl Drop for InstrumentedCancellationToken {
+    fn drop(&mut self) {
+        CANCELLATION_TOKEN_INSTRUMENTS.tokens.add(-1, &self.labels);
+    }
+}
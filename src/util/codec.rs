@@ -0,0 +1,215 @@
+use std::sync::LazyLock;
+
+use bytes::BytesMut;
+use opentelemetry::metrics::Counter;
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite};
+
+/// Instruments shared by all [`InstrumentedCodec`]s.
+struct CodecInstruments {
+    frames_decoded: Counter<u64>,
+    frames_encoded: Counter<u64>,
+    decode_errors: Counter<u64>,
+    bytes_decoded: Counter<u64>,
+    bytes_encoded: Counter<u64>,
+}
+
+static CODEC_INSTRUMENTS: LazyLock<CodecInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    CodecInstruments {
+        frames_decoded: meter
+            .u64_counter("tokio.codec.frames_decoded")
+            .with_description("The number of frames decoded")
+            .with_unit("{frame}")
+            .build(),
+        frames_encoded: meter
+            .u64_counter("tokio.codec.frames_encoded")
+            .with_description("The number of frames encoded")
+            .with_unit("{frame}")
+            .build(),
+        decode_errors: meter
+            .u64_counter("tokio.codec.decode_errors")
+            .with_description("The number of errors returned while decoding frames")
+            .with_unit("{error}")
+            .build(),
+        bytes_decoded: meter
+            .u64_counter("tokio.codec.bytes_decoded")
+            .with_description("The number of bytes consumed while decoding frames")
+            .with_unit("By")
+            .build(),
+        bytes_encoded: meter
+            .u64_counter("tokio.codec.bytes_encoded")
+            .with_description("The number of bytes produced while encoding frames")
+            .with_unit("By")
+            .build(),
+    }
+});
+
+/// Convert a number of bytes to the value of a bytes counter.
+fn bytes(len: usize, metric: &'static str) -> u64 {
+    len.try_into()
+        .unwrap_or_else(|_| crate::overflowed(metric, u64::MAX))
+}
+
+/// A codec recording the frames and bytes going through the codec it wraps.
+///
+/// Metrics are labelled with the name given on creation (`tokio.codec.name`):
+///
+/// - `tokio.codec.frames_decoded` - Frames decoded
+/// - `tokio.codec.frames_encoded` - Frames encoded
+/// - `tokio.codec.decode_errors` - Errors returned while decoding frames
+/// - `tokio.codec.bytes_decoded` - Bytes consumed while decoding frames
+/// - `tokio.codec.bytes_encoded` - Bytes produced while encoding frames
+///
+/// It can be used with any of the [`tokio_util::codec`] adapters, and
+/// [`framed_read`] and [`framed_write`] are provided as shortcuts.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::util::InstrumentedCodec;
+/// use tokio_util::codec::{Framed, LinesCodec};
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let stream = tokio::net::TcpStream::connect("127.0.0.1:6379").await?;
+/// let framed = Framed::new(stream, InstrumentedCodec::new("lines", LinesCodec::new()));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InstrumentedCodec<C> {
+    inner: C,
+    labels: [KeyValue; 1],
+}
+
+impl<C> InstrumentedCodec<C> {
+    /// Instrument a codec under the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>, codec: C) -> Self {
+        Self {
+            inner: codec,
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.codec.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Returns a reference to the underlying codec.
+    #[must_use]
+    pub fn get_ref(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying codec.
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// Consume this wrapper, returning the underlying codec.
+    #[must_use]
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    /// Record the outcome of decoding from a buffer which held `len` bytes
+    /// before.
+    fn record_decode<T, E>(&self, len: usize, src: &BytesMut, result: &Result<Option<T>, E>) {
+        let instruments = &*CODEC_INSTRUMENTS;
+        let consumed = len.saturating_sub(src.len());
+        if consumed > 0 {
+            instruments
+                .bytes_decoded
+                .add(bytes(consumed, "tokio.codec.bytes_decoded"), &self.labels);
+        }
+
+        match result {
+            Ok(Some(_)) => instruments.frames_decoded.add(1, &self.labels),
+            Ok(None) => {}
+            Err(_) => instruments.decode_errors.add(1, &self.labels),
+        }
+    }
+}
+
+impl<C: Decoder> Decoder for InstrumentedCodec<C> {
+    type Item = C::Item;
+    type Error = C::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let len = src.len();
+        let result = self.inner.decode(src);
+        self.record_decode(len, src, &result);
+        result
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let len = buf.len();
+        let result = self.inner.decode_eof(buf);
+        self.record_decode(len, buf, &result);
+        result
+    }
+}
+
+impl<I, C: Encoder<I>> Encoder<I> for InstrumentedCodec<C> {
+    type Error = C::Error;
+
+    fn encode(&mut self, item: I, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let len = dst.len();
+        self.inner.encode(item, dst)?;
+
+        let instruments = &*CODEC_INSTRUMENTS;
+        instruments.frames_encoded.add(1, &self.labels);
+        instruments.bytes_encoded.add(
+            bytes(dst.len().saturating_sub(len), "tokio.codec.bytes_encoded"),
+            &self.labels,
+        );
+        Ok(())
+    }
+}
+
+/// Create a [`FramedRead`] decoding frames from `io` with an
+/// [`InstrumentedCodec`] wrapping `decoder`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::util::framed_read;
+/// use tokio_util::codec::LinesCodec;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let stream = tokio::net::TcpStream::connect("127.0.0.1:6379").await?;
+/// let lines = framed_read("lines", stream, LinesCodec::new());
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub fn framed_read<T, D>(
+    name: impl Into<Value>,
+    io: T,
+    decoder: D,
+) -> FramedRead<T, InstrumentedCodec<D>>
+where
+    T: AsyncRead,
+    D: Decoder,
+{
+    FramedRead::new(io, InstrumentedCodec::new(name, decoder))
+}
+
+/// Create a [`FramedWrite`] encoding frames to `io` with an
+/// [`InstrumentedCodec`] wrapping `encoder`.
+#[must_use]
+pub fn framed_write<T, E>(
+    name: impl Into<Value>,
+    io: T,
+    encoder: E,
+) -> FramedWrite<T, InstrumentedCodec<E>>
+where
+    T: AsyncWrite,
+{
+    FramedWrite::new(io, InstrumentedCodec::new(name, encoder))
+}
@@ -0,0 +1,267 @@
+use std::sync::LazyLock;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
+use opentelemetry::{Key, KeyValue, Value};
+use tokio::time::Instant;
+use tokio_util::time::DelayQueue;
+use tokio_util::time::delay_queue::{self, Expired};
+
+/// Instruments shared by all [`InstrumentedDelayQueue`]s.
+struct DelayQueueInstruments {
+    items: UpDownCounter<i64>,
+    inserted: Counter<u64>,
+    expired: Counter<u64>,
+    expiry_lag: Histogram<f64>,
+}
+
+static DELAY_QUEUE_INSTRUMENTS: LazyLock<DelayQueueInstruments> = LazyLock::new(|| {
+    let meter = crate::meter();
+
+    DelayQueueInstruments {
+        items: meter
+            .i64_up_down_counter("tokio.delay_queue.items")
+            .with_description("The number of items currently in the queue")
+            .with_unit("{item}")
+            .build(),
+        inserted: meter
+            .u64_counter("tokio.delay_queue.inserted")
+            .with_description("The number of items inserted in the queue")
+            .with_unit("{item}")
+            .build(),
+        expired: meter
+            .u64_counter("tokio.delay_queue.expired")
+            .with_description("The number of items yielded by the queue after expiring")
+            .with_unit("{item}")
+            .build(),
+        expiry_lag: meter
+            .f64_histogram("tokio.delay_queue.expiry_lag")
+            .with_description("The time between the deadline of an item and it being yielded")
+            .with_unit("s")
+            .build(),
+    }
+});
+
+/// Convert a queue length to the value of the items up-down counter.
+fn items(len: usize) -> i64 {
+    len.try_into()
+        .unwrap_or_else(|_| crate::overflowed("tokio.delay_queue.items", i64::MAX))
+}
+
+/// A [`DelayQueue`] recording its depth, and how late its items expire.
+///
+/// Metrics are labelled with the name given on creation
+/// (`tokio.delay_queue.name`):
+///
+/// - `tokio.delay_queue.items` - Items currently in the queue
+/// - `tokio.delay_queue.inserted` - Items inserted in the queue
+/// - `tokio.delay_queue.expired` - Items yielded by the queue after expiring
+/// - `tokio.delay_queue.expiry_lag` - Time between the deadline of an item and
+///   it being yielded, which grows when the task draining the queue can't keep
+///   up
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use opentelemetry_instrumentation_tokio::util::InstrumentedDelayQueue;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut queue = InstrumentedDelayQueue::new("retries");
+/// queue.insert("request", Duration::from_secs(1));
+///
+/// while let Some(expired) = queue.expired().await {
+///     // Retry the request
+///     let request = expired.into_inner();
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InstrumentedDelayQueue<T> {
+    inner: DelayQueue<T>,
+    labels: [KeyValue; 1],
+}
+
+impl<T> InstrumentedDelayQueue<T> {
+    /// Create a new, empty, instrumented [`DelayQueue`] with the given name.
+    #[must_use]
+    pub fn new(name: impl Into<Value>) -> Self {
+        Self::with_capacity(name, 0)
+    }
+
+    /// Create a new, empty, instrumented [`DelayQueue`] with the given name
+    /// and capacity.
+    ///
+    /// See [`DelayQueue::with_capacity`].
+    #[must_use]
+    pub fn with_capacity(name: impl Into<Value>, capacity: usize) -> Self {
+        Self {
+            inner: DelayQueue::with_capacity(capacity),
+            labels: [KeyValue::new(
+                Key::from_static_str("tokio.delay_queue.name"),
+                name.into(),
+            )],
+        }
+    }
+
+    /// Record an item inserted in the queue.
+    fn record_inserted(&self) {
+        let instruments = &*DELAY_QUEUE_INSTRUMENTS;
+        instruments.inserted.add(1, &self.labels);
+        instruments.items.add(1, &self.labels);
+    }
+
+    /// Record an item removed from the queue, without expiring.
+    fn record_removed(&self) {
+        DELAY_QUEUE_INSTRUMENTS.items.add(-1, &self.labels);
+    }
+
+    /// Insert a value in the queue, to be yielded at `when`.
+    ///
+    /// See [`DelayQueue::insert_at`].
+    pub fn insert_at(&mut self, value: T, when: Instant) -> delay_queue::Key {
+        let key = self.inner.insert_at(value, when);
+        self.record_inserted();
+        key
+    }
+
+    /// Insert a value in the queue, to be yielded after `timeout`.
+    ///
+    /// See [`DelayQueue::insert`].
+    pub fn insert(&mut self, value: T, timeout: Duration) -> delay_queue::Key {
+        let key = self.inner.insert(value, timeout);
+        self.record_inserted();
+        key
+    }
+
+    /// Poll for the next expired item, recording how late it expired.
+    ///
+    /// See [`DelayQueue::poll_expired`].
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<Option<Expired<T>>> {
+        let expired = std::task::ready!(self.inner.poll_expired(cx));
+        if let Some(expired) = &expired {
+            let lag = Instant::now().saturating_duration_since(expired.deadline());
+            let instruments = &*DELAY_QUEUE_INSTRUMENTS;
+            instruments.items.add(-1, &self.labels);
+            instruments.expired.add(1, &self.labels);
+            instruments
+                .expiry_lag
+                .record(lag.as_secs_f64(), &self.labels);
+        }
+        Poll::Ready(expired)
+    }
+
+    /// Wait for the next expired item, recording how late it expired.
+    ///
+    /// Returns `None` if the queue is empty.
+    pub async fn expired(&mut self) -> Option<Expired<T>> {
+        std::future::poll_fn(|cx| self.poll_expired(cx)).await
+    }
+
+    /// Remove the item associated with `key` from the queue.
+    ///
+    /// See [`DelayQueue::remove`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not contained in the queue.
+    pub fn remove(&mut self, key: &delay_queue::Key) -> Expired<T> {
+        let expired = self.inner.remove(key);
+        self.record_removed();
+        expired
+    }
+
+    /// Remove the item associated with `key` from the queue, if it is in it.
+    ///
+    /// See [`DelayQueue::try_remove`].
+    pub fn try_remove(&mut self, key: &delay_queue::Key) -> Option<Expired<T>> {
+        let expired = self.inner.try_remove(key)?;
+        self.record_removed();
+        Some(expired)
+    }
+
+    /// Set the item associated with `key` to expire at `when`.
+    ///
+    /// See [`DelayQueue::reset_at`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not contained in the queue.
+    pub fn reset_at(&mut self, key: &delay_queue::Key, when: Instant) {
+        self.inner.reset_at(key, when);
+    }
+
+    /// Set the item associated with `key` to expire after `timeout`.
+    ///
+    /// See [`DelayQueue::reset`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not contained in the queue.
+    pub fn reset(&mut self, key: &delay_queue::Key, timeout: Duration) {
+        self.inner.reset(key, timeout);
+    }
+
+    /// Returns the deadline of the item associated with `key`.
+    ///
+    /// See [`DelayQueue::deadline`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not contained in the queue.
+    #[must_use]
+    pub fn deadline(&self, key: &delay_queue::Key) -> Instant {
+        self.inner.deadline(key)
+    }
+
+    /// Returns the key of the next item to expire, if any.
+    #[must_use]
+    pub fn peek(&self) -> Option<delay_queue::Key> {
+        self.inner.peek()
+    }
+
+    /// Remove all the items from the queue.
+    pub fn clear(&mut self) {
+        DELAY_QUEUE_INSTRUMENTS
+            .items
+            .add(-items(self.inner.len()), &self.labels);
+        self.inner.clear();
+    }
+
+    /// Returns the number of items in the queue.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns `true` if there are no items in the queue.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the number of items the queue can hold without reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Reserve capacity for at least `additional` more items.
+    ///
+    /// See [`DelayQueue::reserve`].
+    pub fn reserve(&mut self, additional: usize) {
+        self.inner.reserve(additional);
+    }
+}
+
+impl<T> Drop for InstrumentedDelayQueue<T> {
+    fn drop(&mut self) {
+        // The items still in the queue are dropped with it
+        DELAY_QUEUE_INSTRUMENTS
+            .items
+            .add(-items(self.inner.len()), &self.labels);
+    }
+}
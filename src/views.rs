@@ -0,0 +1,292 @@
+//! Pre-built [views](opentelemetry_sdk::metrics::MeterProviderBuilder::with_view)
+//! for the instruments of this crate.
+//!
+//! Views are set when building the [`SdkMeterProvider`], and allow dropping,
+//! renaming or re-bucketing instruments:
+//!
+//! - [`drop_per_worker_metrics`] - Drops the `tokio.worker.*` metrics
+//! - [`drop_metrics`] - Drops the given metrics
+//! - [`rename`] - Renames the metrics, e.g. to follow a house style
+//! - [`prometheus_names`] - Renames the metrics following the Prometheus
+//!   conventions
+//! - [`histogram_boundaries`] - Sets the bucket boundaries of a histogram
+//! - [`exponential_histogram`] - Uses an exponential aggregation for a
+//!   histogram
+//!
+//! The views only apply to the instruments of this crate. When several views
+//! match an instrument, each of them produces a separate stream, so they
+//! should be used for distinct instruments.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_tokio::views;
+//! use opentelemetry_sdk::metrics::SdkMeterProvider;
+//!
+//! let provider = SdkMeterProvider::builder()
+//!     .with_view(views::drop_per_worker_metrics())
+//!     .with_view(views::exponential_histogram("tokio.fs.operation.duration", 160, 20))
+//!     .build();
+//! opentelemetry::global::set_meter_provider(provider);
+//! ```
+//!
+//! [`SdkMeterProvider`]: opentelemetry_sdk::metrics::SdkMeterProvider
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use opentelemetry_sdk::metrics::{Aggregation, Instrument, InstrumentKind, Stream};
+
+/// Check whether an instrument was created by this crate.
+///
+/// The instruments are created after the scope is set, so an unset scope means
+/// that it isn't one of them.
+fn is_own_instrument(instrument: &Instrument) -> bool {
+    crate::SCOPE
+        .get()
+        .is_some_and(|scope| instrument.scope().name().starts_with(scope.name()))
+}
+
+/// Build a view dropping the instruments matched by the given predicate.
+fn drop_matching(
+    predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+) -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    move |instrument| {
+        if !is_own_instrument(instrument) || !predicate(instrument.name()) {
+            return None;
+        }
+
+        Stream::builder()
+            .with_aggregation(Aggregation::Drop)
+            .build()
+            .ok()
+    }
+}
+
+/// Build a view changing the aggregation of the given histogram.
+fn aggregate_histogram(
+    name: Cow<'static, str>,
+    aggregation: Aggregation,
+) -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    move |instrument| {
+        if !is_own_instrument(instrument)
+            || instrument.kind() != InstrumentKind::Histogram
+            || instrument.name() != name
+        {
+            return None;
+        }
+
+        Stream::builder()
+            .with_aggregation(aggregation.clone())
+            .build()
+            .ok()
+    }
+}
+
+/// Drop the per-worker metrics (`tokio.worker.*`), which have one series per
+/// worker thread.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::views;
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_view(views::drop_per_worker_metrics())
+///     .build();
+/// ```
+pub fn drop_per_worker_metrics() -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    drop_matching(|name| name.starts_with("tokio.worker."))
+}
+
+/// Drop the metrics with the given names.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::views;
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_view(views::drop_metrics([
+///         "tokio.io_driver.fd_registrations",
+///         "tokio.io_driver.fd_deregistrations",
+///     ]))
+///     .build();
+/// ```
+pub fn drop_metrics(
+    names: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+) -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    let names: HashSet<Cow<'static, str>> = names.into_iter().map(Into::into).collect();
+    drop_matching(move |name| names.contains(name))
+}
+
+/// Rename the metrics, e.g. to follow a house style.
+///
+/// The function gets the name of each metric, and returns its new name, or
+/// `None` to keep it. Metrics given an invalid name are kept as they are.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::views;
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_view(views::rename(|name| Some(format!("acme.{name}"))))
+///     .build();
+/// ```
+pub fn rename(
+    f: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+) -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    move |instrument| {
+        if !is_own_instrument(instrument) {
+            return None;
+        }
+
+        Stream::builder()
+            .with_name(f(instrument.name())?)
+            .build()
+            .ok()
+    }
+}
+
+/// Rename the metrics following the Prometheus conventions, so that they look
+/// idiomatic when exported through a Prometheus exporter.
+///
+/// The dots are replaced with underscores, the unit is appended to the name,
+/// and counters get a `_total` suffix, e.g. `tokio.worker.busy_duration`
+/// in milliseconds becomes `tokio_worker_busy_duration_milliseconds_total`,
+/// except the `_count` and `_sum` counters of the poll time histogram.
+/// Annotations like `{task}` are not units, and are left out.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::views;
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_view(views::prometheus_names())
+///     .build();
+/// ```
+pub fn prometheus_names() -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    |instrument| {
+        if !is_own_instrument(instrument) {
+            return None;
+        }
+
+        Stream::builder()
+            .with_name(prometheus_name(
+                instrument.name(),
+                instrument.unit(),
+                instrument.kind(),
+            ))
+            .build()
+            .ok()
+    }
+}
+
+/// Build the Prometheus name of a metric, see [`prometheus_names`].
+fn prometheus_name(name: &str, unit: &str, kind: InstrumentKind) -> String {
+    let mut prometheus_name = name.replace('.', "_");
+
+    let unit = match unit {
+        "ns" => Some("nanoseconds"),
+        "ms" => Some("milliseconds"),
+        "s" => Some("seconds"),
+        "By" => Some("bytes"),
+        // Dimensionless gauges are ratios, e.g. `tokio.worker.utilization`
+        "1" if matches!(
+            kind,
+            InstrumentKind::Gauge | InstrumentKind::ObservableGauge
+        ) =>
+        {
+            Some("ratio")
+        }
+        _ => None,
+    };
+    if let Some(unit) = unit
+        && !prometheus_name.ends_with(unit)
+    {
+        prometheus_name.push('_');
+        prometheus_name.push_str(unit);
+    }
+
+    // The count and sum of the poll time histogram go along with its buckets,
+    // and keep the names Prometheus expects for them
+    let histogram_series = matches!(
+        name,
+        "tokio.worker.poll_time_count" | "tokio.worker.poll_time_sum"
+    );
+    if matches!(
+        kind,
+        InstrumentKind::Counter | InstrumentKind::ObservableCounter
+    ) && !histogram_series
+    {
+        prometheus_name.push_str("_total");
+    }
+
+    prometheus_name
+}
+
+/// Record the given histogram with explicit bucket boundaries.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::views;
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_view(views::histogram_boundaries(
+///         "tokio.timeout.duration",
+///         vec![0.001, 0.01, 0.1, 1.0, 10.0],
+///     ))
+///     .build();
+/// ```
+pub fn histogram_boundaries(
+    name: impl Into<Cow<'static, str>>,
+    boundaries: Vec<f64>,
+) -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    aggregate_histogram(
+        name.into(),
+        Aggregation::ExplicitBucketHistogram {
+            boundaries,
+            record_min_max: true,
+        },
+    )
+}
+
+/// Record the given histogram with an exponential aggregation, using at most
+/// `max_size` buckets, and a scale of at most `max_scale`.
+///
+/// Exponential histograms adapt to the range of recorded values, and can be
+/// merged across series, which works well for durations such as
+/// `tokio.worker.poll_time`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use opentelemetry_instrumentation_tokio::views;
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+///
+/// let provider = SdkMeterProvider::builder()
+///     .with_view(views::exponential_histogram("tokio.worker.poll_time", 160, 20))
+///     .build();
+/// ```
+pub fn exponential_histogram(
+    name: impl Into<Cow<'static, str>>,
+    max_size: u32,
+    max_scale: i8,
+) -> impl Fn(&Instrument) -> Option<Stream> + Send + Sync + 'static {
+    aggregate_histogram(
+        name.into(),
+        Aggregation::Base2ExponentialHistogram {
+            max_size,
+            max_scale,
+            record_min_max: true,
+        },
+    )
+}
@@ -0,0 +1,196 @@
+//! Alerts raised on the runtimes, checked through the anomaly hook and the
+//! emitted log records.
+
+use std::future;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::{Alert, Config};
+use tokio::runtime::{Builder, Runtime};
+
+/// A runtime with a task which never completes, raising
+/// `Alert::alive_tasks_above(0)`.
+fn busy_runtime() -> Runtime {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    runtime.spawn(future::pending::<()>());
+    runtime
+}
+
+fn name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.runtime.name", name)
+}
+
+/// Wait for an event with the given name to be exported, returning it.
+#[cfg(feature = "logs")]
+fn wait_for_event(
+    exporter: &opentelemetry_sdk::logs::InMemoryLogExporter,
+    event_name: &str,
+) -> opentelemetry_sdk::logs::SdkLogRecord {
+    use std::thread;
+    use std::time::Instant;
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let logs = exporter.get_emitted_logs().unwrap();
+        if let Some(log) = logs
+            .into_iter()
+            .find(|log| log.record.event_name() == Some(event_name))
+        {
+            return log.record;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "no {event_name} event was emitted"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// The value of an attribute of a log record.
+#[cfg(feature = "logs")]
+fn attribute(
+    record: &opentelemetry_sdk::logs::SdkLogRecord,
+    key: &str,
+) -> Option<opentelemetry::logs::AnyValue> {
+    record
+        .attributes_iter()
+        .find(|(name, _)| name.as_str() == key)
+        .map(|(_, value)| value.clone())
+}
+
+#[test]
+fn anomaly_hook() {
+    let runtime = busy_runtime();
+    let (anomalies, received) = mpsc::channel();
+
+    let _guard = Config::new()
+        .named("anomaly")
+        .with_sampling_interval(Duration::from_millis(10))
+        .with_alert(Alert::alive_tasks_above(0))
+        .with_alert(Alert::global_queue_depth_above(1000))
+        .on_anomaly(move |anomaly| {
+            let _ = anomalies.send(anomaly.clone());
+        })
+        .observe_runtime(runtime.handle())
+        .unwrap();
+
+    let anomaly = received.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(anomaly.condition(), "alive_tasks");
+    assert_eq!(anomaly.threshold(), 0.0);
+    assert_eq!(anomaly.value(), 1.0);
+    assert!(anomaly.labels().contains(&name("anomaly")));
+
+    // The alert stays raised, so the hook isn't called again
+    assert!(received.recv_timeout(Duration::from_millis(100)).is_err());
+}
+
+#[cfg(feature = "logs")]
+#[test]
+fn alert_events() {
+    use opentelemetry::logs::{AnyValue, Severity};
+    use opentelemetry_sdk::logs::{InMemoryLogExporter, SdkLoggerProvider};
+
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let exporter = InMemoryLogExporter::default();
+    let logger_provider = SdkLoggerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+
+    let _guard = Config::new()
+        .named("alerting")
+        .with_logger_provider(logger_provider)
+        .with_sampling_interval(Duration::from_millis(10))
+        .with_alert(Alert::global_queue_depth_above(2))
+        .with_alert(Alert::global_queue_depth_above(0).sustained_for(Duration::from_secs(3600)))
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    // Run the task watching for the shutdown of the runtime, spawned when it
+    // was observed
+    runtime.block_on(tokio::task::yield_now());
+
+    // The tasks wait in the global queue until the runtime is driven
+    for _ in 0..3 {
+        runtime.spawn(async {});
+    }
+    let raised = wait_for_event(&exporter, "tokio.runtime.alert");
+    assert_eq!(raised.severity_number(), Some(Severity::Warn));
+    assert_eq!(
+        attribute(&raised, "tokio.runtime.name"),
+        Some(AnyValue::String("alerting".into()))
+    );
+    assert_eq!(
+        attribute(&raised, "tokio.alert.condition"),
+        Some(AnyValue::String("global_queue_depth".into()))
+    );
+    assert_eq!(
+        attribute(&raised, "tokio.alert.threshold"),
+        Some(AnyValue::Double(2.0))
+    );
+    assert_eq!(
+        attribute(&raised, "tokio.alert.value"),
+        Some(AnyValue::Double(3.0))
+    );
+
+    runtime.block_on(tokio::task::yield_now());
+    let resolved = wait_for_event(&exporter, "tokio.runtime.alert_resolved");
+    assert_eq!(resolved.severity_number(), Some(Severity::Info));
+    assert_eq!(
+        attribute(&resolved, "tokio.alert.value"),
+        Some(AnyValue::Double(0.0))
+    );
+
+    // The sustained alert didn't last long enough to be raised
+    let raised = exporter
+        .get_emitted_logs()
+        .unwrap()
+        .into_iter()
+        .filter(|log| log.record.event_name() == Some("tokio.runtime.alert"))
+        .count();
+    assert_eq!(raised, 1);
+}
+
+#[cfg(all(feature = "pprof", unix))]
+#[test]
+fn anomaly_profile() {
+    use opentelemetry::logs::AnyValue;
+    use opentelemetry_sdk::logs::{InMemoryLogExporter, SdkLoggerProvider};
+
+    let runtime = busy_runtime();
+    let exporter = InMemoryLogExporter::default();
+    let logger_provider = SdkLoggerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+
+    let _guard = Config::new()
+        .named("profiled")
+        .with_logger_provider(logger_provider)
+        .with_sampling_interval(Duration::from_millis(10))
+        .with_alert(Alert::alive_tasks_above(0))
+        .with_anomaly_profiling(Duration::from_millis(100))
+        .observe_runtime(runtime.handle())
+        .unwrap();
+
+    let profile = wait_for_event(&exporter, "tokio.runtime.profile");
+
+    let Some(AnyValue::Bytes(body)) = profile.body() else {
+        panic!(
+            "expected the profile as the body, found {:?}",
+            profile.body()
+        );
+    };
+    assert!(!body.is_empty());
+
+    assert_eq!(
+        attribute(&profile, "tokio.runtime.name"),
+        Some(AnyValue::String("profiled".into()))
+    );
+    assert_eq!(
+        attribute(&profile, "tokio.alert.condition"),
+        Some(AnyValue::String("alive_tasks".into()))
+    );
+    assert_eq!(
+        attribute(&profile, "tokio.profile.duration"),
+        Some(AnyValue::Double(0.1))
+    );
+}
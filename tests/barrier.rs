@@ -0,0 +1,38 @@
+//! Instrumented barriers, checked through the collected metrics.
+
+use std::sync::Arc;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::InstrumentedBarrier;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn barrier_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.barrier.name", name)
+}
+
+#[tokio::test]
+async fn waiters_until_released() {
+    let metrics = TestMetrics::install();
+    let barrier = Arc::new(InstrumentedBarrier::new("stage", 3));
+
+    let waiters: Vec<_> = (0..2)
+        .map(|_| {
+            let barrier = barrier.clone();
+            tokio::spawn(async move { barrier.wait().await.is_leader() })
+        })
+        .collect();
+    tokio::task::yield_now().await;
+    metrics
+        .collect()
+        .assert_metric("tokio.barrier.waiters", &[barrier_name("stage")], 2.0);
+
+    let mut leaders = usize::from(barrier.wait().await.is_leader());
+    for waiter in waiters {
+        leaders += usize::from(waiter.await.unwrap());
+    }
+    assert_eq!(leaders, 1);
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.barrier.waiters", &[barrier_name("stage")], 0.0);
+    collection.assert_metric("tokio.barrier.wait_duration", &[barrier_name("stage")], 3.0);
+}
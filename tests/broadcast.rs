@@ -0,0 +1,59 @@
+//! Instrumented broadcast channels, checked through the collected metrics.
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::broadcast;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+use tokio::sync::broadcast::error::TryRecvError;
+
+fn channel_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.channel.name", name)
+}
+
+#[test]
+fn messages_sent_and_received() {
+    let metrics = TestMetrics::install();
+    let (tx, mut rx) = broadcast::channel("sent", 4);
+
+    for message in 0..3 {
+        tx.send(message).unwrap();
+    }
+    assert_eq!(rx.try_recv().unwrap(), 0);
+    assert_eq!(rx.try_recv().unwrap(), 1);
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.broadcast.messages_sent",
+        &[channel_name("sent")],
+        3.0,
+    );
+    // One lag value is recorded per message received
+    collection.assert_metric("tokio.broadcast.receiver_lag", &[channel_name("sent")], 2.0);
+    assert_eq!(
+        collection.value("tokio.broadcast.messages_lagged", &[channel_name("sent")]),
+        None
+    );
+}
+
+#[test]
+fn messages_lagged() {
+    let metrics = TestMetrics::install();
+    let (tx, mut rx) = broadcast::channel("lagged", 2);
+
+    for message in 0..5 {
+        tx.send(message).unwrap();
+    }
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Lagged(3)));
+    assert_eq!(rx.try_recv().unwrap(), 3);
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.broadcast.messages_lagged",
+        &[channel_name("lagged")],
+        3.0,
+    );
+    collection.assert_metric(
+        "tokio.broadcast.receiver_lag",
+        &[channel_name("lagged")],
+        1.0,
+    );
+}
@@ -0,0 +1,96 @@
+//! Skipping the unchanged per-worker observations, checked through the series
+//! exported with delta temporality, which only include the observed ones.
+
+use std::thread;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::Config;
+use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData, ResourceMetrics};
+use opentelemetry_sdk::metrics::{
+    InMemoryMetricExporterBuilder, PeriodicReader, SdkMeterProvider, Temporality,
+};
+use tokio::runtime::{Builder, Runtime};
+
+/// The `tokio.worker.park_count` series of a runtime, summed.
+fn park_count(metrics: &[ResourceMetrics], runtime: &KeyValue) -> Option<u64> {
+    let mut values = metrics
+        .iter()
+        .flat_map(ResourceMetrics::scope_metrics)
+        .flat_map(|scope| scope.metrics())
+        .filter(|metric| metric.name() == "tokio.worker.park_count")
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .filter(|point| point.attributes().any(|label| label == runtime))
+                .map(|point| point.value())
+                .collect::<Vec<_>>(),
+            data => panic!("unexpected data for tokio.worker.park_count: {data:?}"),
+        })
+        .peekable();
+    values.peek()?;
+    Some(values.sum())
+}
+
+/// Run a task on the worker of a runtime, and wait for it to park again.
+fn run_task(runtime: &Runtime) {
+    let parks = runtime.metrics().worker_park_count(0);
+    runtime.block_on(runtime.spawn(async {})).unwrap();
+    while runtime.metrics().worker_park_count(0) == parks {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn unchanged_observations_skipped() {
+    let exporter = InMemoryMetricExporterBuilder::new()
+        .with_temporality(Temporality::Delta)
+        .build();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+
+    let detected = KeyValue::new("tokio.runtime.name", "detected");
+    let observed = KeyValue::new("tokio.runtime.name", "observed");
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .build()
+        .unwrap();
+    let other = Builder::new_current_thread().build().unwrap();
+    let _guard = Config::new()
+        .named("detected")
+        .with_change_detection(3)
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    let _other = Config::new()
+        .named("observed")
+        .observe_runtime(other.handle())
+        .unwrap();
+    // Let the worker settle down after starting
+    run_task(&runtime);
+
+    let collect = || {
+        exporter.reset();
+        provider.force_flush().unwrap();
+        exporter.get_finished_metrics().unwrap()
+    };
+
+    // The first collection observes all the series
+    assert!(park_count(&collect(), &detected).is_some());
+
+    // The idle worker isn't observed again until the next refresh, unlike the
+    // runtime without change detection
+    for _ in 0..2 {
+        let metrics = collect();
+        assert_eq!(park_count(&metrics, &detected), None);
+        assert_eq!(park_count(&metrics, &observed), Some(0));
+    }
+    // The refresh observes it again, which reports its total again with delta
+    // temporality
+    assert!(park_count(&collect(), &detected).is_some());
+
+    // The worker parks once done with the task, which is observed right away
+    run_task(&runtime);
+    assert!(park_count(&collect(), &detected).is_some());
+    assert_eq!(park_count(&collect(), &detected), None);
+}
@@ -0,0 +1,71 @@
+//! Observing many runtimes from the threads of the collection pool.
+
+use std::future;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::Config;
+use opentelemetry_instrumentation_tokio::testing::{Collection, TestMetrics};
+use tokio::runtime::{Builder, Runtime};
+
+/// The number of collection threads spawned so far.
+#[cfg(target_os = "linux")]
+fn collection_threads() -> usize {
+    std::fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter(|task| {
+            let comm = std::fs::read_to_string(task.as_ref().unwrap().path().join("comm"));
+            // Thread names are truncated to 15 bytes
+            comm.is_ok_and(|comm| comm.trim_end() == "otel-tokio-coll")
+        })
+        .count()
+}
+
+/// Observe a runtime named after its index, with as many alive tasks.
+fn tenant_runtime(index: usize) -> Runtime {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    for _ in 0..index {
+        runtime.spawn(future::pending::<()>());
+    }
+    // The runtimes stay observed until they are dropped
+    let _guard = Config::new()
+        .named(format!("tenant-{index}"))
+        .with_collection_threads(4)
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    runtime
+}
+
+fn assert_tenants(collection: &Collection, runtimes: &[Runtime]) {
+    for index in 0..runtimes.len() {
+        let labels = [KeyValue::new(
+            "tokio.runtime.name",
+            format!("tenant-{index}"),
+        )];
+        collection.assert_metric("tokio.workers", &labels, 1.0);
+        #[expect(clippy::cast_precision_loss)]
+        collection.assert_metric("tokio.alive_tasks", &labels, index as f64);
+    }
+}
+
+#[test]
+fn parallel_collection_threshold() {
+    let metrics = TestMetrics::install();
+
+    // Below the threshold, the runtimes are observed on the collecting thread
+    let mut runtimes: Vec<_> = (0..15).map(tenant_runtime).collect();
+    assert_tenants(&metrics.collect(), &runtimes);
+    #[cfg(target_os = "linux")]
+    assert_eq!(collection_threads(), 0);
+
+    // From the threshold, they are split across 4 threads, including the
+    // collecting one
+    runtimes.extend((15..40).map(tenant_runtime));
+    assert_tenants(&metrics.collect(), &runtimes);
+    #[cfg(target_os = "linux")]
+    assert_eq!(collection_threads(), 3);
+
+    // The threads are kept for the next collections
+    assert_tenants(&metrics.collect(), &runtimes);
+    #[cfg(target_os = "linux")]
+    assert_eq!(collection_threads(), 3);
+}
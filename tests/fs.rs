@@ -0,0 +1,86 @@
+//! Instrumented filesystem operations, checked through the collected metrics.
+
+use std::path::PathBuf;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::fs::{self, InstrumentedFile};
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn operation(operation: &'static str) -> KeyValue {
+    KeyValue::new("tokio.fs.operation", operation)
+}
+
+/// A directory of its own for a test, removed by the test once done.
+fn test_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "opentelemetry-instrumentation-tokio-{name}-{}",
+        std::process::id()
+    ))
+}
+
+#[tokio::test]
+async fn operations_and_bytes() {
+    let metrics = TestMetrics::install();
+    let dir = test_dir("fs");
+
+    fs::create_dir_all(&dir).await.unwrap();
+    fs::write(dir.join("a"), "hello").await.unwrap();
+    assert_eq!(fs::read_to_string(dir.join("a")).await.unwrap(), "hello");
+    assert_eq!(fs::copy(dir.join("a"), dir.join("b")).await.unwrap(), 5);
+    fs::rename(dir.join("b"), dir.join("c")).await.unwrap();
+
+    let mut file = InstrumentedFile::create(dir.join("d")).await.unwrap();
+    file.write_all(b"abc").await.unwrap();
+    file.sync_all().await.unwrap();
+    drop(file);
+    let mut contents = Vec::new();
+    InstrumentedFile::open(dir.join("d"))
+        .await
+        .unwrap()
+        .read_to_end(&mut contents)
+        .await
+        .unwrap();
+    assert_eq!(contents, b"abc");
+
+    for file in ["a", "c", "d"] {
+        fs::remove_file(dir.join(file)).await.unwrap();
+    }
+    std::fs::remove_dir(&dir).unwrap();
+
+    let collection = metrics.collect();
+    for (name, count) in [
+        ("create_dir_all", 1.0),
+        ("write", 1.0),
+        ("read", 1.0),
+        ("copy", 1.0),
+        ("rename", 1.0),
+        ("create", 1.0),
+        ("sync_all", 1.0),
+        ("open", 1.0),
+        ("remove_file", 3.0),
+    ] {
+        collection.assert_metric("tokio.fs.operations", &[operation(name)], count);
+        collection.assert_metric("tokio.fs.operation.duration", &[operation(name)], count);
+    }
+    // Written by `write`, `copy` and the file, and read by `read_to_string`,
+    // `copy` and the file
+    collection.assert_metric("tokio.fs.bytes_written", &[], 13.0);
+    collection.assert_metric("tokio.fs.bytes_read", &[], 13.0);
+}
+
+#[tokio::test]
+async fn failed_operations() {
+    let metrics = TestMetrics::install();
+
+    assert!(fs::metadata(test_dir("missing")).await.is_err());
+
+    metrics.collect().assert_metric(
+        "tokio.fs.operations",
+        &[
+            operation("metadata"),
+            KeyValue::new("error.type", "NotFound"),
+        ],
+        1.0,
+    );
+}
@@ -0,0 +1,86 @@
+//! Health checks of runtimes against saturation thresholds.
+
+use std::thread;
+use std::time::Duration;
+
+use opentelemetry_instrumentation_tokio::{
+    Config, HealthStatus, Saturation, Thresholds, health, health_by_name,
+};
+use tokio::runtime::Builder;
+
+#[test]
+fn queue_depth() {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let thresholds = Thresholds::new().with_max_queue_depth(2);
+
+    // Tasks spawned from outside the runtime wait in the global queue until
+    // it is driven
+    runtime.spawn(async {});
+    runtime.spawn(async {});
+    assert_eq!(health(runtime.handle(), &thresholds), HealthStatus::Healthy);
+    runtime.spawn(async {});
+    assert_eq!(
+        health(runtime.handle(), &thresholds),
+        HealthStatus::Saturated(vec![Saturation::QueueDepth { depth: 3, max: 2 }])
+    );
+
+    runtime.block_on(tokio::task::yield_now());
+    assert!(health(runtime.handle(), &thresholds).is_healthy());
+}
+
+#[test]
+fn busy_ratio() {
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(1)
+        .build()
+        .unwrap();
+    let thresholds = Thresholds::new().with_max_busy_ratio(0.5);
+
+    // The first check only records the busy duration of the workers
+    assert!(health(runtime.handle(), &thresholds).is_healthy());
+    runtime
+        .block_on(runtime.spawn(async { thread::sleep(Duration::from_millis(50)) }))
+        .unwrap();
+
+    let HealthStatus::Saturated(saturations) = health(runtime.handle(), &thresholds) else {
+        panic!("the worker was busy for the whole check");
+    };
+    assert!(
+        matches!(
+            saturations[..],
+            [Saturation::BusyRatio { ratio, max: 0.5 }] if ratio > 0.5
+        ),
+        "{saturations:?}"
+    );
+
+    // The worker was idle since the previous check
+    thread::sleep(Duration::from_millis(50));
+    assert!(health(runtime.handle(), &thresholds).is_healthy());
+}
+
+#[test]
+fn by_name() {
+    let runtime = Builder::new_current_thread().build().unwrap();
+    let _guard = Config::new()
+        .named("checked")
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    let thresholds = Thresholds::new().with_max_queue_depth(0);
+    // Run the task watching for the shutdown of the runtime, spawned when it
+    // was observed
+    runtime.block_on(tokio::task::yield_now());
+
+    assert_eq!(
+        health_by_name("checked", &thresholds),
+        Some(HealthStatus::Healthy)
+    );
+    runtime.spawn(async {});
+    assert_eq!(
+        health_by_name("checked", &thresholds),
+        Some(HealthStatus::Saturated(vec![Saturation::QueueDepth {
+            depth: 1,
+            max: 0
+        }]))
+    );
+    assert_eq!(health_by_name("unknown", &thresholds), None);
+}
@@ -0,0 +1,88 @@
+//! Registration of the runtime instruments with additional meter providers.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use opentelemetry::InstrumentationScope;
+use opentelemetry::metrics::{Meter, MeterProvider};
+use opentelemetry_instrumentation_tokio::Config;
+use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData, ResourceMetrics};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use tokio::runtime::Builder;
+
+/// A meter provider counting the meters the instruments are registered with.
+struct CountingProvider {
+    inner: SdkMeterProvider,
+    meters: Arc<AtomicUsize>,
+}
+
+impl MeterProvider for CountingProvider {
+    fn meter_with_scope(&self, scope: InstrumentationScope) -> Meter {
+        self.meters.fetch_add(1, Ordering::Relaxed);
+        self.inner.meter_with_scope(scope)
+    }
+}
+
+/// The attributes of the data points of the `tokio.workers` gauge.
+fn workers_attributes(metrics: &[ResourceMetrics]) -> Vec<Vec<String>> {
+    metrics
+        .iter()
+        .flat_map(ResourceMetrics::scope_metrics)
+        .flat_map(|scope| scope.metrics())
+        .filter(|metric| metric.name() == "tokio.workers")
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::U64(MetricData::Gauge(gauge)) => gauge
+                .data_points()
+                .map(|point| {
+                    let mut attributes: Vec<_> = point
+                        .attributes()
+                        .map(|label| format!("{}={}", label.key, label.value))
+                        .collect();
+                    attributes.sort();
+                    attributes
+                })
+                .collect::<Vec<_>>(),
+            data => panic!("unexpected data for tokio.workers: {data:?}"),
+        })
+        .collect()
+}
+
+#[test]
+fn registered_once_per_provider() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    let meters = Arc::new(AtomicUsize::new(0));
+    let config = Config::new().also_with_meter_provider(CountingProvider {
+        inner: provider.clone(),
+        meters: meters.clone(),
+    });
+
+    let (first, second) = (
+        Builder::new_current_thread().build().unwrap(),
+        Builder::new_current_thread().build().unwrap(),
+    );
+    let _first = config
+        .clone()
+        .named("first")
+        .observe_runtime(first.handle())
+        .unwrap();
+    let registered = meters.load(Ordering::Relaxed);
+    assert!(registered > 0);
+    let _second = config
+        .named("second")
+        .observe_runtime(second.handle())
+        .unwrap();
+    assert_eq!(meters.load(Ordering::Relaxed), registered);
+
+    provider.force_flush().unwrap();
+    let attributes = workers_attributes(&exporter.get_finished_metrics().unwrap());
+    assert_eq!(attributes.len(), 2, "{attributes:?}");
+    assert_eq!(
+        attributes.iter().collect::<HashSet<_>>().len(),
+        2,
+        "{attributes:?}"
+    );
+}
@@ -0,0 +1,105 @@
+//! Instrumented mpsc channels, checked through the collected metrics.
+
+use std::thread;
+use std::time::Duration;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::mpsc;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn channel_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.channel.name", name)
+}
+
+#[tokio::test]
+async fn full_time_while_full() {
+    let metrics = TestMetrics::install();
+    let (tx, mut rx) = mpsc::channel("stays-full", 1);
+
+    tx.send(1).await.unwrap();
+    thread::sleep(Duration::from_millis(20));
+
+    // The channel is still full, and its full time is observed anyway
+    let full_time = metrics
+        .collect()
+        .value("tokio.mpsc.full_time", &[channel_name("stays-full")])
+        .unwrap();
+    assert!(full_time >= 0.02, "full for {full_time}s");
+
+    assert_eq!(rx.recv().await, Some(1));
+    let full_time = metrics
+        .collect()
+        .value("tokio.mpsc.full_time", &[channel_name("stays-full")])
+        .unwrap();
+    thread::sleep(Duration::from_millis(20));
+    metrics.collect().assert_metric(
+        "tokio.mpsc.full_time",
+        &[channel_name("stays-full")],
+        full_time,
+    );
+}
+
+#[tokio::test]
+async fn full_time_of_dropped_channels() {
+    let metrics = TestMetrics::install();
+    let (tx, _rx) = mpsc::channel("dropped", 1);
+    let (other_tx, other_rx) = mpsc::channel("dropped", 1);
+
+    tx.send(1).await.unwrap();
+    other_tx.send(1).await.unwrap();
+    thread::sleep(Duration::from_millis(20));
+    drop((other_tx, other_rx));
+
+    // The time the dropped channel spent full is still counted
+    let full_time = metrics
+        .collect()
+        .value("tokio.mpsc.full_time", &[channel_name("dropped")])
+        .unwrap();
+    assert!(full_time >= 0.04, "full for {full_time}s");
+}
+
+#[tokio::test]
+async fn send_wait_duration_on_success() {
+    let metrics = TestMetrics::install();
+    let (tx, rx) = mpsc::channel("send-wait", 1);
+
+    tx.send(1).await.unwrap();
+    drop(rx);
+    assert!(tx.send(2).await.is_err());
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.mpsc.send_wait_duration",
+        &[channel_name("send-wait")],
+        1.0,
+    );
+    collection.assert_metric(
+        "tokio.mpsc.messages_dropped",
+        &[channel_name("send-wait")],
+        1.0,
+    );
+}
+
+#[test]
+fn unbounded_len_after_receiver_dropped() {
+    let metrics = TestMetrics::install();
+    let (tx, mut rx) = mpsc::unbounded_channel("receiver-dropped");
+
+    for message in 0..3 {
+        tx.send(message).unwrap();
+    }
+    assert_eq!(rx.try_recv().unwrap(), 0);
+    metrics
+        .collect()
+        .assert_metric("tokio.mpsc.len", &[channel_name("receiver-dropped")], 2.0);
+
+    drop(rx);
+    assert!(tx.send(3).is_err());
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.mpsc.len", &[channel_name("receiver-dropped")], 0.0);
+    collection.assert_metric(
+        "tokio.mpsc.high_water_mark",
+        &[channel_name("receiver-dropped")],
+        3.0,
+    );
+}
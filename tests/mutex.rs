@@ -0,0 +1,39 @@
+//! Instrumented mutexes, checked through the collected metrics.
+
+use std::thread;
+use std::time::Duration;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::InstrumentedMutex;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn mutex_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.mutex.name", name)
+}
+
+#[tokio::test]
+async fn hold_duration_recorded_on_unlock() {
+    let metrics = TestMetrics::install();
+    let mutex = InstrumentedMutex::new("held", 0);
+
+    let mut guard = mutex.lock().await;
+    *guard += 1;
+    thread::sleep(Duration::from_millis(20));
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.mutex.acquisitions", &[mutex_name("held")], 1.0);
+    collection.assert_metric("tokio.mutex.wait_duration", &[mutex_name("held")], 1.0);
+    // The mutex is still held
+    assert_eq!(
+        collection.value("tokio.mutex.hold_duration", &[mutex_name("held")]),
+        None
+    );
+
+    drop(guard);
+    *mutex.lock().await += 1;
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.mutex.acquisitions", &[mutex_name("held")], 2.0);
+    collection.assert_metric("tokio.mutex.hold_duration", &[mutex_name("held")], 2.0);
+    assert_eq!(mutex.into_inner(), 2);
+}
@@ -0,0 +1,149 @@
+//! Instrumented networking primitives, checked through the collected metrics.
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::net::{
+    self, InstrumentedTcpListener, InstrumentedUdpSocket,
+};
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+use tokio::net::TcpStream;
+
+fn socket_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.socket.name", name)
+}
+
+fn listener_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.listener.name", name)
+}
+
+#[tokio::test]
+async fn tcp_accepted_connections() {
+    let metrics = TestMetrics::install();
+    let listener = InstrumentedTcpListener::bind("accept", "127.0.0.1:0")
+        .await
+        .unwrap();
+    let addr = listener.local_addr().unwrap();
+    let port = KeyValue::new("network.local.port", i64::from(addr.port()));
+
+    for _ in 0..2 {
+        let (_client, accepted) = tokio::join!(TcpStream::connect(addr), listener.accept());
+        accepted.unwrap();
+    }
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.tcp_listener.accepted_connections",
+        &[listener_name("accept"), port.clone()],
+        2.0,
+    );
+    // The latency is recorded when the loop comes back to accept
+    collection.assert_metric(
+        "tokio.tcp_listener.accept_loop_latency",
+        &[listener_name("accept"), port],
+        1.0,
+    );
+    assert_eq!(
+        collection.value(
+            "tokio.tcp_listener.accept_errors",
+            &[listener_name("accept")]
+        ),
+        None
+    );
+}
+
+#[tokio::test]
+async fn udp_datagrams() {
+    let metrics = TestMetrics::install();
+    let sender = InstrumentedUdpSocket::bind("sender", "127.0.0.1:0")
+        .await
+        .unwrap();
+    let receiver = InstrumentedUdpSocket::bind("receiver", "127.0.0.1:0")
+        .await
+        .unwrap();
+    let receiver_addr = receiver.local_addr().unwrap();
+
+    sender.send_to(b"hello", receiver_addr).await.unwrap();
+    sender.send_to(b"world!", receiver_addr).await.unwrap();
+    let mut buf = [0; 16];
+    for _ in 0..2 {
+        receiver.recv_from(&mut buf).await.unwrap();
+    }
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.udp_socket.datagrams_sent",
+        &[socket_name("sender")],
+        2.0,
+    );
+    collection.assert_metric(
+        "tokio.udp_socket.bytes_sent",
+        &[socket_name("sender")],
+        11.0,
+    );
+    collection.assert_metric(
+        "tokio.udp_socket.datagrams_received",
+        &[socket_name("receiver")],
+        2.0,
+    );
+    collection.assert_metric(
+        "tokio.udp_socket.bytes_received",
+        &[socket_name("receiver")],
+        11.0,
+    );
+    assert_eq!(
+        collection.value(
+            "tokio.udp_socket.datagrams_received",
+            &[socket_name("sender")]
+        ),
+        None
+    );
+}
+
+#[tokio::test]
+async fn udp_send_errors() {
+    let metrics = TestMetrics::install();
+    let socket = InstrumentedUdpSocket::bind("unconnected", "127.0.0.1:0")
+        .await
+        .unwrap();
+
+    // Sending without a peer address fails
+    socket.send(b"lost").await.unwrap_err();
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.udp_socket.send_errors",
+        &[socket_name("unconnected")],
+        1.0,
+    );
+    assert_eq!(
+        collection.value(
+            "tokio.udp_socket.datagrams_sent",
+            &[socket_name("unconnected")]
+        ),
+        None
+    );
+}
+
+#[tokio::test]
+async fn dns_lookups_by_outcome() {
+    let metrics = TestMetrics::install();
+    let outcome = |outcome: &'static str| KeyValue::new("tokio.dns.outcome", outcome);
+
+    // Addresses are parsed without querying a resolver
+    let addrs: Vec<_> = net::lookup_host("127.0.0.1:80").await.unwrap().collect();
+    assert_eq!(addrs, ["127.0.0.1:80".parse().unwrap()]);
+    // The port is missing
+    assert!(net::lookup_host("127.0.0.1").await.is_err());
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.dns.lookups", &[outcome("ok")], 1.0);
+    collection.assert_metric(
+        "tokio.dns.lookups",
+        &[
+            outcome("error"),
+            KeyValue::new("error.type", "InvalidInput"),
+        ],
+        1.0,
+    );
+    collection.assert_metric("tokio.dns.lookup.duration", &[outcome("ok")], 1.0);
+    collection.assert_metric("tokio.dns.lookup.duration", &[outcome("error")], 1.0);
+}
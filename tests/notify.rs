@@ -0,0 +1,70 @@
+//! Instrumented `Notify`, checked through the collected metrics.
+
+use std::sync::Arc;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::InstrumentedNotify;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn notify_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.notify.name", name)
+}
+
+fn method(method: &'static str) -> KeyValue {
+    KeyValue::new("tokio.notify.method", method)
+}
+
+#[tokio::test]
+async fn waiters_and_notifications() {
+    let metrics = TestMetrics::install();
+    let notify = Arc::new(InstrumentedNotify::new("waiters"));
+
+    let waiter = tokio::spawn({
+        let notify = notify.clone();
+        async move { notify.notified().await }
+    });
+    tokio::task::yield_now().await;
+    metrics
+        .collect()
+        .assert_metric("tokio.notify.waiters", &[notify_name("waiters")], 1.0);
+
+    notify.notify_one();
+    waiter.await.unwrap();
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.notify.waiters", &[notify_name("waiters")], 0.0);
+    collection.assert_metric("tokio.notify.wait_duration", &[notify_name("waiters")], 1.0);
+    collection.assert_metric(
+        "tokio.notify.notifications",
+        &[notify_name("waiters"), method("notify_one")],
+        1.0,
+    );
+}
+
+#[tokio::test]
+async fn notifications_by_method() {
+    let metrics = TestMetrics::install();
+    let notify = InstrumentedNotify::new("methods");
+
+    notify.notify_waiters();
+    notify.notify_waiters();
+    notify.notify_one();
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.notify.notifications",
+        &[notify_name("methods"), method("notify_waiters")],
+        2.0,
+    );
+    collection.assert_metric(
+        "tokio.notify.notifications",
+        &[notify_name("methods"), method("notify_one")],
+        1.0,
+    );
+
+    // The permit stored by `notify_one` is consumed without waiting
+    notify.notified().await;
+    metrics
+        .collect()
+        .assert_metric("tokio.notify.wait_duration", &[notify_name("methods")], 1.0);
+}
@@ -0,0 +1,75 @@
+//! Instrumented oneshot channels, checked through the collected metrics.
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::oneshot;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn channel_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.channel.name", name)
+}
+
+#[tokio::test]
+async fn completed_channels_are_not_counted() {
+    let metrics = TestMetrics::install();
+    let (tx, rx) = oneshot::channel("completed");
+
+    tx.send(1).unwrap();
+    assert_eq!(rx.await.unwrap(), 1);
+
+    let collection = metrics.collect();
+    for name in [
+        "tokio.oneshot.senders_dropped",
+        "tokio.oneshot.receivers_dropped",
+    ] {
+        assert_eq!(collection.value(name, &[channel_name("completed")]), None);
+    }
+}
+
+#[tokio::test]
+async fn sender_dropped() {
+    let metrics = TestMetrics::install();
+    let (tx, rx) = oneshot::channel::<()>("sender-dropped");
+
+    drop(tx);
+    assert!(rx.await.is_err());
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.oneshot.senders_dropped",
+        &[channel_name("sender-dropped")],
+        1.0,
+    );
+    // The receiver got the error, so it completed
+    assert_eq!(
+        collection.value(
+            "tokio.oneshot.receivers_dropped",
+            &[channel_name("sender-dropped")]
+        ),
+        None
+    );
+}
+
+#[test]
+fn receiver_dropped() {
+    let metrics = TestMetrics::install();
+    let (tx, rx) = oneshot::channel("receiver-dropped");
+
+    drop(rx);
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(1), Err(1));
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.oneshot.receivers_dropped",
+        &[channel_name("receiver-dropped")],
+        1.0,
+    );
+    // The sender tried to send a value, so it isn't counted as dropped
+    assert_eq!(
+        collection.value(
+            "tokio.oneshot.senders_dropped",
+            &[channel_name("receiver-dropped")]
+        ),
+        None
+    );
+}
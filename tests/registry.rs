@@ -0,0 +1,163 @@
+//! Registration of the runtimes, checked through the collected metrics.
+//!
+//! The tests share the meter provider of the binary, so each of them tells
+//! its runtimes apart with their name.
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+use opentelemetry_instrumentation_tokio::{Config, ObserveError};
+use tokio::runtime::{Builder, Runtime};
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().build().unwrap()
+}
+
+fn name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.runtime.name", name)
+}
+
+fn instance(instance: i64) -> KeyValue {
+    KeyValue::new("tokio.runtime.instance", instance)
+}
+
+#[test]
+fn duplicate_registration() {
+    let metrics = TestMetrics::install();
+    let runtime = runtime();
+    let config = Config::new().named("duplicate");
+
+    let _guard = config.clone().observe_runtime(runtime.handle()).unwrap();
+    let result = config.clone().observe_runtime(runtime.handle());
+    assert!(matches!(result, Err(ObserveError::DuplicateRegistration)));
+    metrics
+        .collect()
+        .assert_metric("tokio.workers", &[name("duplicate")], 1.0);
+
+    let _again = config
+        .with_duplicate_registrations(true)
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    metrics
+        .collect()
+        .assert_metric("tokio.workers", &[name("duplicate")], 2.0);
+}
+
+#[test]
+fn duplicate_registration_in_one_batch() {
+    let metrics = TestMetrics::install();
+    let (first, second) = (runtime(), runtime());
+    let config = Config::new().named("duplicate-batch");
+
+    let result = config.clone().observe_runtimes([
+        (first.handle(), Vec::new()),
+        (second.handle(), Vec::new()),
+        (first.handle(), Vec::new()),
+    ]);
+    assert!(matches!(result, Err(ObserveError::DuplicateRegistration)));
+    assert_eq!(
+        metrics
+            .collect()
+            .value("tokio.workers", &[name("duplicate-batch")]),
+        None,
+    );
+
+    let _guards = config
+        .observe_runtimes([(first.handle(), Vec::new()), (second.handle(), Vec::new())])
+        .unwrap();
+    metrics
+        .collect()
+        .assert_metric("tokio.workers", &[name("duplicate-batch")], 2.0);
+}
+
+#[test]
+fn strict_label_collision() {
+    let metrics = TestMetrics::install();
+    let (first, second) = (runtime(), runtime());
+    let config = Config::new()
+        .named("strict")
+        .with_runtime_id("strict")
+        .with_strict_labels(true);
+
+    let guard = config.clone().observe_runtime(first.handle()).unwrap();
+    let result = config.clone().observe_runtime(second.handle());
+    assert!(matches!(result, Err(ObserveError::LabelCollision { .. })));
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.workers", &[name("strict")], 1.0);
+    assert_eq!(
+        collection.value("tokio.workers", &[name("strict"), instance(1)]),
+        None,
+    );
+
+    // The rejected runtime didn't take an instance, so the runtime replacing
+    // the first one gets its labels as is
+    guard.unobserve();
+    let _guard = config.observe_runtime(second.handle()).unwrap();
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.workers", &[name("strict")], 1.0);
+    assert_eq!(
+        collection.value("tokio.workers", &[name("strict"), instance(1)]),
+        None,
+    );
+}
+
+#[test]
+fn instance_for_concurrent_runtimes_with_the_same_labels() {
+    let metrics = TestMetrics::install();
+    let (first, second) = (runtime(), runtime());
+    let config = Config::new()
+        .named("instances")
+        .with_runtime_id("instances");
+
+    let _first = config.clone().observe_runtime(first.handle()).unwrap();
+    let _second = config.observe_runtime(second.handle()).unwrap();
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.workers", &[name("instances")], 2.0);
+    collection.assert_metric("tokio.workers", &[name("instances"), instance(1)], 1.0);
+}
+
+#[test]
+fn unobserve_removes_series() {
+    let metrics = TestMetrics::install();
+    let runtime = runtime();
+
+    let guard = Config::new()
+        .named("unobserved")
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    metrics
+        .collect()
+        .assert_metric("tokio.workers", &[name("unobserved")], 1.0);
+
+    guard.unobserve();
+    assert_eq!(
+        metrics
+            .collect()
+            .value("tokio.workers", &[name("unobserved")]),
+        None,
+    );
+}
+
+#[test]
+fn shutdown_removes_series() {
+    let metrics = TestMetrics::install();
+    let runtime = runtime();
+
+    let _guard = Config::new()
+        .named("shut-down")
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    metrics
+        .collect()
+        .assert_metric("tokio.workers", &[name("shut-down")], 1.0);
+
+    drop(runtime);
+    assert_eq!(
+        metrics
+            .collect()
+            .value("tokio.workers", &[name("shut-down")]),
+        None,
+    );
+}
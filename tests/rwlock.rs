@@ -0,0 +1,48 @@
+//! Instrumented read-write locks, checked through the collected metrics.
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::InstrumentedRwLock;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn lock_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.rwlock.name", name)
+}
+
+fn mode(mode: &'static str) -> KeyValue {
+    KeyValue::new("tokio.rwlock.mode", mode)
+}
+
+#[tokio::test]
+async fn reads_and_writes() {
+    let metrics = TestMetrics::install();
+    let lock = InstrumentedRwLock::new("modes", 0);
+
+    {
+        let (first, second) = (lock.read().await, lock.read().await);
+        assert_eq!(*first + *second, 0);
+    }
+    *lock.write().await += 1;
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.rwlock.acquisitions",
+        &[lock_name("modes"), mode("read")],
+        2.0,
+    );
+    collection.assert_metric(
+        "tokio.rwlock.acquisitions",
+        &[lock_name("modes"), mode("write")],
+        1.0,
+    );
+    collection.assert_metric(
+        "tokio.rwlock.wait_duration",
+        &[lock_name("modes"), mode("read")],
+        2.0,
+    );
+    collection.assert_metric(
+        "tokio.rwlock.wait_duration",
+        &[lock_name("modes"), mode("write")],
+        1.0,
+    );
+    assert_eq!(lock.into_inner(), 1);
+}
@@ -0,0 +1,107 @@
+//! Counters recorded by the background sampler, checked against the runtime
+//! metrics.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::Config;
+use opentelemetry_sdk::metrics::data::{AggregatedMetrics, MetricData, ResourceMetrics};
+use opentelemetry_sdk::metrics::{InMemoryMetricExporter, PeriodicReader, SdkMeterProvider};
+use tokio::runtime::Builder;
+
+const SCOPE: &str = "opentelemetry-instrumentation-tokio";
+const SAMPLER_SCOPE: &str = "opentelemetry-instrumentation-tokio/sampler";
+
+/// The sum of a counter over the series of a runtime, in the given scope.
+fn counter_value(
+    metrics: &[ResourceMetrics],
+    scope: &str,
+    name: &str,
+    runtime: &KeyValue,
+) -> Option<u64> {
+    let mut values = metrics
+        .iter()
+        .flat_map(ResourceMetrics::scope_metrics)
+        .filter(|scope_metrics| scope_metrics.scope().name() == scope)
+        .flat_map(|scope_metrics| scope_metrics.metrics())
+        .filter(|metric| metric.name() == name)
+        .flat_map(|metric| match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => sum
+                .data_points()
+                .filter(|point| point.attributes().any(|label| label == runtime))
+                .map(|point| point.value())
+                .collect::<Vec<_>>(),
+            data => panic!("unexpected data for {name}: {data:?}"),
+        })
+        .peekable();
+    values.peek()?;
+    Some(values.sum())
+}
+
+#[test]
+fn sampled_counters() {
+    let exporter = InMemoryMetricExporter::default();
+    let provider = SdkMeterProvider::builder()
+        .with_reader(PeriodicReader::builder(exporter.clone()).build())
+        .build();
+    opentelemetry::global::set_meter_provider(provider.clone());
+
+    let sampled = KeyValue::new("tokio.runtime.name", "sampled");
+    let observed = KeyValue::new("tokio.runtime.name", "observed");
+    let runtime = Builder::new_multi_thread()
+        .worker_threads(2)
+        .build()
+        .unwrap();
+    let other = Builder::new_current_thread().build().unwrap();
+    let _guard = Config::new()
+        .named("sampled")
+        .with_sampling_interval(Duration::from_millis(10))
+        .observe_runtime(runtime.handle())
+        .unwrap();
+    let _other = Config::new()
+        .named("observed")
+        .observe_runtime(other.handle())
+        .unwrap();
+
+    // Make the workers park and unpark a few times
+    runtime.block_on(async {
+        for _ in 0..10 {
+            tokio::spawn(async {}).await.unwrap();
+        }
+    });
+    let runtime_metrics = runtime.metrics();
+    let parks = || {
+        (0..runtime_metrics.num_workers())
+            .map(|worker| runtime_metrics.worker_park_count(worker))
+            .sum::<u64>()
+    };
+
+    // Once the runtime is idle, the sampled increments add up to its totals
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        exporter.reset();
+        provider.force_flush().unwrap();
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let value =
+            |scope, runtime| counter_value(&metrics, scope, "tokio.worker.park_count", runtime);
+
+        // Sampled runtimes are skipped by the observable counters, and the
+        // others by the sampler
+        assert_eq!(value(SCOPE, &sampled), None);
+        assert_eq!(value(SAMPLER_SCOPE, &observed), None);
+        assert!(value(SCOPE, &observed).is_some());
+
+        let sampled_parks = value(SAMPLER_SCOPE, &sampled);
+        if sampled_parks.is_some_and(|sampled_parks| sampled_parks == parks()) {
+            break;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "sampled {sampled_parks:?} parks, expected {}",
+            parks()
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert!(parks() > 0);
+}
@@ -0,0 +1,79 @@
+//! Instrumented semaphores, checked through the collected metrics.
+
+use std::future;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::sync::InstrumentedSemaphore;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn semaphore_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.semaphore.name", name)
+}
+
+#[tokio::test]
+async fn acquisitions() {
+    let metrics = TestMetrics::install();
+    let semaphore = InstrumentedSemaphore::new("acquired", 3);
+
+    let first = semaphore.acquire().await.unwrap();
+    let second = semaphore.acquire_many(2).await.unwrap();
+    // No permits are left, which isn't counted
+    assert!(semaphore.try_acquire().is_err());
+    drop((first, second));
+    let _third = semaphore.try_acquire().unwrap();
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.semaphore.acquisitions",
+        &[semaphore_name("acquired")],
+        3.0,
+    );
+    // Only the waiting acquires record their wait
+    collection.assert_metric(
+        "tokio.semaphore.wait_duration",
+        &[semaphore_name("acquired")],
+        2.0,
+    );
+    for name in [
+        "tokio.semaphore.acquire_timeouts",
+        "tokio.semaphore.closed_acquires",
+    ] {
+        assert_eq!(collection.value(name, &[semaphore_name("acquired")]), None);
+    }
+}
+
+#[tokio::test]
+async fn timed_out_and_closed_acquires() {
+    let metrics = TestMetrics::install();
+    let semaphore = InstrumentedSemaphore::new("failed", 1);
+
+    let permit = semaphore.acquire().await.unwrap();
+    // The acquire is polled once, then given up
+    tokio::select! {
+        biased;
+        _ = semaphore.acquire() => panic!("the permit was acquired twice"),
+        () = future::ready(()) => {}
+    }
+    drop(permit);
+
+    semaphore.close();
+    assert!(semaphore.acquire().await.is_err());
+    assert!(semaphore.try_acquire().is_err());
+
+    let collection = metrics.collect();
+    collection.assert_metric(
+        "tokio.semaphore.acquire_timeouts",
+        &[semaphore_name("failed")],
+        1.0,
+    );
+    collection.assert_metric(
+        "tokio.semaphore.closed_acquires",
+        &[semaphore_name("failed")],
+        2.0,
+    );
+    collection.assert_metric(
+        "tokio.semaphore.acquisitions",
+        &[semaphore_name("failed")],
+        1.0,
+    );
+}
@@ -0,0 +1,39 @@
+//! Outcomes of the instrumented tasks, checked through the collected metrics.
+
+use std::future;
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::task;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+
+fn task_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.task.name", name)
+}
+
+#[tokio::test]
+async fn task_outcomes() {
+    let metrics = TestMetrics::install();
+
+    task::spawn("completes", async {}).await.unwrap();
+
+    let pending = task::spawn("cancelled", future::pending::<()>());
+    pending.abort();
+    assert!(pending.await.unwrap_err().is_cancelled());
+
+    let panicking = task::spawn("panics", async { panic!("task panicked") });
+    assert!(panicking.await.unwrap_err().is_panic());
+
+    let collection = metrics.collect();
+    for (metric, task) in [
+        ("tokio.task.completed_tasks", "completes"),
+        ("tokio.task.cancelled_tasks", "cancelled"),
+        ("tokio.task.panics", "panics"),
+    ] {
+        collection.assert_metric(metric, &[task_name(task)], 1.0);
+    }
+    collection.assert_metric("tokio.task.spawned_tasks", &[task_name("completes")], 1.0);
+    assert_eq!(
+        collection.value("tokio.task.panics", &[task_name("completes")]),
+        None,
+    );
+}
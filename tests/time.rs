@@ -0,0 +1,108 @@
+//! Instrumented timer utilities, checked through the collected metrics.
+
+use std::time::Duration;
+use std::{future, thread};
+
+use opentelemetry::KeyValue;
+use opentelemetry_instrumentation_tokio::testing::TestMetrics;
+use opentelemetry_instrumentation_tokio::{Config, time};
+use tokio::time::MissedTickBehavior;
+
+fn runtime_name(name: &'static str) -> KeyValue {
+    KeyValue::new("tokio.runtime.name", name)
+}
+
+#[tokio::test]
+async fn sleep_overshoot() {
+    let metrics = TestMetrics::install();
+    let _guard = Config::new()
+        .named("sleep")
+        .observe_current_runtime()
+        .unwrap();
+
+    time::sleep(Duration::from_millis(10)).await;
+    time::sleep_until(tokio::time::Instant::now() + Duration::from_millis(10)).await;
+
+    metrics
+        .collect()
+        .assert_metric("tokio.sleep.overshoot", &[runtime_name("sleep")], 2.0);
+}
+
+#[tokio::test]
+async fn interval_missed_ticks() {
+    let metrics = TestMetrics::install();
+    let labels = [KeyValue::new("tokio.interval.name", "missed")];
+    let mut interval = time::interval("missed", Duration::from_millis(10));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    // The first tick completes immediately
+    interval.tick().await;
+    // Block the runtime past the next two ticks
+    thread::sleep(Duration::from_millis(35));
+    interval.tick().await;
+
+    let collection = metrics.collect();
+    collection.assert_metric("tokio.interval.ticks", &labels, 2.0);
+    collection.assert_metric("tokio.interval.tick_drift", &labels, 2.0);
+    let missed = collection
+        .value("tokio.interval.missed_ticks", &labels)
+        .unwrap();
+    assert!(missed >= 2.0, "missed {missed} ticks");
+}
+
+#[tokio::test]
+async fn timeout_outcomes() {
+    let metrics = TestMetrics::install();
+    let name = KeyValue::new("tokio.timeout.name", "outcomes");
+    let outcome = |outcome: &'static str| KeyValue::new("tokio.timeout.outcome", outcome);
+
+    let completed =
+        time::timeout_instrumented("outcomes", Duration::from_secs(60), future::ready(1)).await;
+    assert_eq!(completed.unwrap(), 1);
+    let timed_out = time::timeout_instrumented(
+        "outcomes",
+        Duration::from_millis(10),
+        future::pending::<()>(),
+    )
+    .await;
+    assert!(timed_out.is_err());
+
+    let collection = metrics.collect();
+    for outcome in [outcome("completed"), outcome("timed_out")] {
+        collection.assert_metric(
+            "tokio.timeout.completions",
+            &[name.clone(), outcome.clone()],
+            1.0,
+        );
+        collection.assert_metric("tokio.timeout.duration", &[name.clone(), outcome], 1.0);
+    }
+}
+
+#[tokio::test]
+async fn pending_timers() {
+    let metrics = TestMetrics::install();
+    let _guard = Config::new()
+        .named("pending")
+        .observe_current_runtime()
+        .unwrap();
+
+    let sleep = tokio::spawn(time::sleep(Duration::from_secs(60)));
+    let timeout = tokio::spawn(time::timeout_instrumented(
+        "pending",
+        Duration::from_secs(60),
+        future::pending::<()>(),
+    ));
+    tokio::task::yield_now().await;
+    metrics
+        .collect()
+        .assert_metric("tokio.timer.pending", &[runtime_name("pending")], 2.0);
+
+    // Dropping the timers counts them out
+    sleep.abort();
+    timeout.abort();
+    assert!(sleep.await.unwrap_err().is_cancelled());
+    assert!(timeout.await.unwrap_err().is_cancelled());
+    metrics
+        .collect()
+        .assert_metric("tokio.timer.pending", &[runtime_name("pending")], 0.0);
+}